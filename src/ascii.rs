@@ -0,0 +1,401 @@
+//! A fast path for strings which are entirely ASCII.
+//!
+//! When both strings are ASCII, every `char` is a single byte, so char offsets are
+//! byte offsets and the strings can be compared as byte slices without building
+//! char vectors or byte-to-char maps. On top of that, the scans for equal bytes
+//! are vectorized: with the `std` feature on `x86_64`, AVX2 is used when the CPU
+//! supports it (detected at runtime), otherwise SSE2, which every `x86_64` CPU has.
+//! Other targets use the scalar implementations.
+//!
+//! Every function here produces exactly the same result as its generic counterpart.
+
+use crate::primitives::{self, MatchingStreak};
+use alloc::vec::Vec;
+
+/// Returns the length of the common prefix of `a` and `b`.
+pub(crate) fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    {
+        if std::is_x86_feature_detected!("avx2") {
+            // Safety: the CPU supports AVX2.
+            return unsafe { x86::common_prefix_avx2(a, b) };
+        }
+    }
+    #[cfg(target_arch = "x86_64")]
+    {
+        // Safety: SSE2 is part of the x86_64 baseline.
+        unsafe { x86::common_prefix_sse2(a, b) }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        scalar::common_prefix(a, b)
+    }
+}
+
+/// Returns the length of the common suffix of `a` and `b`.
+pub(crate) fn common_suffix(a: &[u8], b: &[u8]) -> usize {
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    {
+        if std::is_x86_feature_detected!("avx2") {
+            // Safety: the CPU supports AVX2.
+            return unsafe { x86::common_suffix_avx2(a, b) };
+        }
+    }
+    #[cfg(target_arch = "x86_64")]
+    {
+        // Safety: SSE2 is part of the x86_64 baseline.
+        unsafe { x86::common_suffix_sse2(a, b) }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        scalar::common_suffix(a, b)
+    }
+}
+
+/// Returns the index of the first occurrence of `needle` in `haystack`.
+pub(crate) fn find_byte(haystack: &[u8], needle: u8) -> Option<usize> {
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    {
+        if std::is_x86_feature_detected!("avx2") {
+            // Safety: the CPU supports AVX2.
+            return unsafe { x86::find_byte_avx2(haystack, needle) };
+        }
+    }
+    #[cfg(target_arch = "x86_64")]
+    {
+        // Safety: SSE2 is part of the x86_64 baseline.
+        unsafe { x86::find_byte_sse2(haystack, needle) }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        scalar::find_byte(haystack, needle)
+    }
+}
+
+/// [primitives::find_longest_match] for bytes.
+///
+/// Rather than trying every window size, this finds every pair of equal bytes with
+/// [find_byte], and extends those which start a streak with [common_prefix].
+/// Streaks are visited in order of their start in `shorter`, then in `longer`, and
+/// only a strictly longer streak replaces the best one, which gives the same
+/// tie-breaking as the generic implementation.
+pub(crate) fn find_longest_match(
+    shorter: &[u8],
+    longer: &[u8],
+    low1: usize,
+    high1: usize,
+    low2: usize,
+    high2: usize,
+) -> MatchingStreak {
+    let mut best = MatchingStreak {
+        idx1: low1,
+        idx2: low2,
+        size: 0,
+    };
+    for i in low1..high1 {
+        // No streak starting here or later can beat the best one.
+        if high1 - i <= best.size {
+            break;
+        }
+        let needle = shorter[i];
+        let mut from = low2;
+        while let Some(offset) = find_byte(&longer[from..high2], needle) {
+            let j = from + offset;
+            from = j + 1;
+            if high2 - j <= best.size {
+                break;
+            }
+            // Only the start of a streak is extended; the rest are suffixes of it.
+            if i > low1 && j > low2 && shorter[i - 1] == longer[j - 1] {
+                continue;
+            }
+            let size = common_prefix(&shorter[i..high1], &longer[j..high2]);
+            if size > best.size {
+                best = MatchingStreak {
+                    idx1: i,
+                    idx2: j,
+                    size,
+                };
+            }
+        }
+    }
+    best
+}
+
+/// [primitives::get_matching_blocks] for bytes.
+pub(crate) fn get_matching_blocks(a: &[u8], b: &[u8]) -> Vec<(usize, usize, usize)> {
+    primitives::get_matching_blocks_with(a, b, find_longest_match)
+}
+
+/// [bounded_levenshtein](primitives::bounded_levenshtein) for bytes.
+///
+/// The common prefix and suffix are stripped with vectorized scans. If the shorter of
+/// the remaining strings fits into a machine word, the distance is computed with the
+/// bit-parallel algorithm of Myers (in Hyyrö's formulation), which processes a whole
+/// column of the distance matrix per step. Otherwise this defers to the banded
+/// generic implementation.
+pub(crate) fn bounded_levenshtein(a: &[u8], b: &[u8], max_distance: usize) -> Option<usize> {
+    let prefix = common_prefix(a, b);
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let suffix = common_suffix(a, b);
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+    let (shorter, longer) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if longer.len() - shorter.len() > max_distance {
+        return None;
+    }
+    if shorter.is_empty() {
+        return Some(longer.len());
+    }
+    if shorter.len() > 64 {
+        return primitives::bounded_levenshtein(shorter, longer, max_distance);
+    }
+    let distance = myers_distance(shorter, longer);
+    if distance <= max_distance {
+        Some(distance)
+    } else {
+        None
+    }
+}
+
+/// Bit-parallel Levenshtein distance for a `pattern` of at most 64 bytes.
+///
+/// Bit `i` of the vertical delta vectors `pv`/`mv` is set when the distance matrix
+/// cell in row `i + 1` is one more/less than the cell above it.
+fn myers_distance(pattern: &[u8], text: &[u8]) -> usize {
+    debug_assert!(!pattern.is_empty() && pattern.len() <= 64);
+    let mut peq = [0u64; 256];
+    for (i, &c) in pattern.iter().enumerate() {
+        peq[c as usize] |= 1 << i;
+    }
+    let last = 1u64 << (pattern.len() - 1);
+    let mut pv = !0u64;
+    let mut mv = 0u64;
+    let mut score = pattern.len();
+    for &c in text {
+        let eq = peq[c as usize];
+        let xv = eq | mv;
+        let xh = ((eq & pv).wrapping_add(pv) ^ pv) | eq;
+        let mut ph = mv | !(xh | pv);
+        let mut mh = pv & xh;
+        if ph & last != 0 {
+            score += 1;
+        } else if mh & last != 0 {
+            score -= 1;
+        }
+        // The first row of the matrix increases by one per column.
+        ph = (ph << 1) | 1;
+        mh <<= 1;
+        pv = mh | !(xv | ph);
+        mv = ph & xv;
+    }
+    score
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+mod scalar {
+    pub(super) fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+        a.iter().zip(b).take_while(|(x, y)| x == y).count()
+    }
+
+    pub(super) fn common_suffix(a: &[u8], b: &[u8]) -> usize {
+        a.iter()
+            .rev()
+            .zip(b.iter().rev())
+            .take_while(|(x, y)| x == y)
+            .count()
+    }
+
+    pub(super) fn find_byte(haystack: &[u8], needle: u8) -> Option<usize> {
+        haystack.iter().position(|&c| c == needle)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use core::arch::x86_64::*;
+    use core::cmp::min;
+
+    // Each function compares full vectors while they fit, then finishes byte by byte.
+    // Loads are unaligned and never read past the end of either slice.
+
+    #[cfg(feature = "std")]
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn common_prefix_avx2(a: &[u8], b: &[u8]) -> usize {
+        let len = min(a.len(), b.len());
+        let mut i = 0;
+        while i + 32 <= len {
+            let va = _mm256_loadu_si256(a.as_ptr().add(i) as *const __m256i);
+            let vb = _mm256_loadu_si256(b.as_ptr().add(i) as *const __m256i);
+            let equal = _mm256_movemask_epi8(_mm256_cmpeq_epi8(va, vb)) as u32;
+            if equal != u32::MAX {
+                return i + (!equal).trailing_zeros() as usize;
+            }
+            i += 32;
+        }
+        i + common_prefix_sse2(&a[i..], &b[i..])
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn common_prefix_sse2(a: &[u8], b: &[u8]) -> usize {
+        let len = min(a.len(), b.len());
+        let mut i = 0;
+        while i + 16 <= len {
+            let va = _mm_loadu_si128(a.as_ptr().add(i) as *const __m128i);
+            let vb = _mm_loadu_si128(b.as_ptr().add(i) as *const __m128i);
+            let equal = _mm_movemask_epi8(_mm_cmpeq_epi8(va, vb)) as u32;
+            if equal != 0xffff {
+                return i + (!equal).trailing_zeros() as usize;
+            }
+            i += 16;
+        }
+        i + a[i..len]
+            .iter()
+            .zip(&b[i..len])
+            .take_while(|(x, y)| x == y)
+            .count()
+    }
+
+    #[cfg(feature = "std")]
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn common_suffix_avx2(a: &[u8], b: &[u8]) -> usize {
+        let len = min(a.len(), b.len());
+        let (a_end, b_end) = (a.len(), b.len());
+        let mut i = 0;
+        while i + 32 <= len {
+            let va = _mm256_loadu_si256(a.as_ptr().add(a_end - i - 32) as *const __m256i);
+            let vb = _mm256_loadu_si256(b.as_ptr().add(b_end - i - 32) as *const __m256i);
+            let equal = _mm256_movemask_epi8(_mm256_cmpeq_epi8(va, vb)) as u32;
+            if equal != u32::MAX {
+                return i + (!equal).leading_zeros() as usize;
+            }
+            i += 32;
+        }
+        i + common_suffix_sse2(&a[..a_end - i], &b[..b_end - i])
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn common_suffix_sse2(a: &[u8], b: &[u8]) -> usize {
+        let len = min(a.len(), b.len());
+        let (a_end, b_end) = (a.len(), b.len());
+        let mut i = 0;
+        while i + 16 <= len {
+            let va = _mm_loadu_si128(a.as_ptr().add(a_end - i - 16) as *const __m128i);
+            let vb = _mm_loadu_si128(b.as_ptr().add(b_end - i - 16) as *const __m128i);
+            let equal = _mm_movemask_epi8(_mm_cmpeq_epi8(va, vb)) as u16;
+            if equal != 0xffff {
+                return i + (!equal).leading_zeros() as usize;
+            }
+            i += 16;
+        }
+        i + a[..a_end - i]
+            .iter()
+            .rev()
+            .zip(b[..b_end - i].iter().rev())
+            .take_while(|(x, y)| x == y)
+            .count()
+    }
+
+    #[cfg(feature = "std")]
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn find_byte_avx2(haystack: &[u8], needle: u8) -> Option<usize> {
+        let splat = _mm256_set1_epi8(needle as i8);
+        let mut i = 0;
+        while i + 32 <= haystack.len() {
+            let v = _mm256_loadu_si256(haystack.as_ptr().add(i) as *const __m256i);
+            let equal = _mm256_movemask_epi8(_mm256_cmpeq_epi8(v, splat)) as u32;
+            if equal != 0 {
+                return Some(i + equal.trailing_zeros() as usize);
+            }
+            i += 32;
+        }
+        find_byte_sse2(&haystack[i..], needle).map(|offset| i + offset)
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn find_byte_sse2(haystack: &[u8], needle: u8) -> Option<usize> {
+        let splat = _mm_set1_epi8(needle as i8);
+        let mut i = 0;
+        while i + 16 <= haystack.len() {
+            let v = _mm_loadu_si128(haystack.as_ptr().add(i) as *const __m128i);
+            let equal = _mm_movemask_epi8(_mm_cmpeq_epi8(v, splat)) as u32;
+            if equal != 0 {
+                return Some(i + equal.trailing_zeros() as usize);
+            }
+            i += 16;
+        }
+        haystack[i..]
+            .iter()
+            .position(|&c| c == needle)
+            .map(|offset| i + offset)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    fn random_ascii(rng: &mut impl Rng, alphabet: &[u8], len: usize) -> String {
+        (0..len)
+            .map(|_| alphabet[rng.gen_range(0..alphabet.len())] as char)
+            .collect()
+    }
+
+    #[test]
+    fn byte_scans_match_scalar() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(30);
+        for _ in 0..2000 {
+            let len = rng.gen_range(0..100);
+            let a = random_ascii(&mut rng, b"ab", len);
+            let mut b = a.clone().into_bytes();
+            // Flip one byte somewhere, or nowhere.
+            if !b.is_empty() && rng.gen_bool(0.8) {
+                let at = rng.gen_range(0..b.len());
+                b[at] = b'z';
+            }
+            let (a, b) = (a.as_bytes(), &b[..]);
+            let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+            let suffix = a
+                .iter()
+                .rev()
+                .zip(b.iter().rev())
+                .take_while(|(x, y)| x == y)
+                .count();
+            assert_eq!(common_prefix(a, b), prefix);
+            assert_eq!(common_suffix(a, b), suffix);
+            assert_eq!(find_byte(b, b'z'), b.iter().position(|&c| c == b'z'));
+        }
+    }
+
+    #[test]
+    fn matching_blocks_match_generic() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(30);
+        for _ in 0..2000 {
+            let (la, lb) = (rng.gen_range(0..40), rng.gen_range(0..80));
+            let a = random_ascii(&mut rng, b"abc ", la);
+            let b = random_ascii(&mut rng, b"abc ", lb);
+            let (ca, cb): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+            assert_eq!(
+                get_matching_blocks(a.as_bytes(), b.as_bytes()),
+                primitives::get_matching_blocks(&ca, &cb),
+                "{:?} {:?}",
+                a,
+                b
+            );
+        }
+    }
+
+    #[test]
+    fn levenshtein_matches_generic() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(30);
+        for _ in 0..2000 {
+            let (la, lb) = (rng.gen_range(0..90), rng.gen_range(0..90));
+            let a = random_ascii(&mut rng, b"abcd", la);
+            let b = random_ascii(&mut rng, b"abcd", lb);
+            let expected = primitives::levenshtein(a.as_bytes(), b.as_bytes());
+            let k = rng.gen_range(0..90);
+            let bounded = bounded_levenshtein(a.as_bytes(), b.as_bytes(), k);
+            assert_eq!(bounded, if expected <= k { Some(expected) } else { None });
+        }
+    }
+}
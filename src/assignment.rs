@@ -0,0 +1,232 @@
+//! One-to-one matching between two lists, maximizing the total score.
+//!
+//! Calling [extract_one](crate::process::extract_one) for every item of one list can match
+//! several of them to the same counterpart. [assign] instead solves the assignment problem over
+//! a [ScoreMatrix]: every item is matched at most once, only pairs meeting a minimum score are
+//! matched, and the sum of the scores of the matched pairs is as large as possible.
+//!
+//! ```
+//! # use fuzzywuzzy::assignment::assign_strings;
+//! # use fuzzywuzzy::processor::FullProcess;
+//! # use fuzzywuzzy::scorer::Ratio;
+//! let left = ["Acme Corp", "Acme Corporation Ltd", "Globex"];
+//! let right = ["Initech", "ACME Corporation", "ACME Corp.", "Globex Inc"];
+//! let assignment = assign_strings(&left, &right, FullProcess::default(), Ratio, 60);
+//! assert_eq!(assignment.pairs, vec![(0, 2, 100), (1, 1, 89), (2, 3, 75)]);
+//! assert_eq!(assignment.unmatched_left, vec![]);
+//! assert_eq!(assignment.unmatched_right, vec![0]);
+//! ```
+
+use crate::process::{self, ScoreMatrix};
+use crate::processor::Processor;
+use crate::scorer::Scorer;
+use alloc::vec::Vec;
+
+/// The result of [assign].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Assignment<T> {
+    /// The matched `(row, column, score)` triples, by row.
+    pub pairs: Vec<(usize, usize, T)>,
+    /// The rows matched to no column, in order.
+    pub unmatched_left: Vec<usize>,
+    /// The columns matched to no row, in order.
+    pub unmatched_right: Vec<usize>,
+}
+
+/// Matches rows of `matrix` to columns, maximizing the sum of the scores of the matched pairs.
+///
+/// Pairs scoring below `min_score` are never matched, so the number of pairs may be lower than
+/// the number of rows and of columns. This runs the Jonker-Volgenant shortest augmenting path
+/// variant of the Hungarian algorithm, in `O(n² m)` time for `n ≤ m`.
+pub fn assign<T>(matrix: &ScoreMatrix<T>, min_score: T) -> Assignment<T>
+where
+    T: Copy + PartialOrd + Into<f64>,
+{
+    let (rows, columns) = (matrix.rows(), matrix.columns());
+    // Disallowed pairs weigh as much as leaving both sides unmatched.
+    let weight = |row: usize, column: usize| {
+        let score = matrix.get(row, column).expect("in bounds");
+        if score >= min_score {
+            score.into()
+        } else {
+            0.0
+        }
+    };
+    // The solver needs at least as many columns as rows, so it may run on the transpose.
+    let row_of_column = if rows <= columns {
+        solve(rows, columns, |i, j| -weight(i, j))
+    } else {
+        let column_of_row = solve(columns, rows, |i, j| -weight(j, i));
+        let mut row_of_column = vec![None; columns];
+        for (row, column) in column_of_row.into_iter().enumerate() {
+            if let Some(column) = column {
+                row_of_column[column] = Some(row);
+            }
+        }
+        row_of_column
+    };
+
+    let mut column_of_row = vec![None; rows];
+    for (column, row) in row_of_column.into_iter().enumerate() {
+        if let Some(row) = row {
+            let score = matrix.get(row, column).expect("in bounds");
+            if score >= min_score {
+                column_of_row[row] = Some(column);
+            }
+        }
+    }
+    let mut assignment = Assignment {
+        pairs: Vec::new(),
+        unmatched_left: Vec::new(),
+        unmatched_right: Vec::new(),
+    };
+    let mut matched_columns = vec![false; columns];
+    for (row, column) in column_of_row.into_iter().enumerate() {
+        match column {
+            Some(column) => {
+                matched_columns[column] = true;
+                let score = matrix.get(row, column).expect("in bounds");
+                assignment.pairs.push((row, column, score));
+            }
+            None => assignment.unmatched_left.push(row),
+        }
+    }
+    assignment.unmatched_right = (0..columns).filter(|&c| !matched_columns[c]).collect();
+    assignment
+}
+
+/// Scores `left` against `right` with [cdist](crate::process::cdist), then [assign]s them.
+pub fn assign_strings<A, B, P, S>(
+    left: &[A],
+    right: &[B],
+    processor: P,
+    scorer: S,
+    min_score: S::Score,
+) -> Assignment<S::Score>
+where
+    A: AsRef<str>,
+    B: AsRef<str>,
+    P: Processor,
+    S: Scorer,
+    S::Score: Into<f64>,
+{
+    assign(&process::cdist(left, right, processor, scorer), min_score)
+}
+
+/// Finds the minimum cost assignment of all `rows` to distinct columns, for `rows <= columns`,
+/// and returns the row assigned to each column.
+fn solve<C: Fn(usize, usize) -> f64>(rows: usize, columns: usize, cost: C) -> Vec<Option<usize>> {
+    // Potentials and the current matching are 1-based, with column 0 as the virtual start of
+    // each augmenting path.
+    let mut u = vec![0.0; rows + 1];
+    let mut v = vec![0.0; columns + 1];
+    let mut row_of = vec![0; columns + 1];
+    let mut previous = vec![0; columns + 1];
+    for row in 1..=rows {
+        row_of[0] = row;
+        let mut column = 0;
+        let mut min_slack = vec![f64::INFINITY; columns + 1];
+        let mut visited = vec![false; columns + 1];
+        loop {
+            visited[column] = true;
+            let current = row_of[column];
+            let mut delta = f64::INFINITY;
+            let mut next = 0;
+            for j in 1..=columns {
+                if visited[j] {
+                    continue;
+                }
+                let slack = cost(current - 1, j - 1) - u[current] - v[j];
+                if slack < min_slack[j] {
+                    min_slack[j] = slack;
+                    previous[j] = column;
+                }
+                if min_slack[j] < delta {
+                    delta = min_slack[j];
+                    next = j;
+                }
+            }
+            for j in 0..=columns {
+                if visited[j] {
+                    u[row_of[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    min_slack[j] -= delta;
+                }
+            }
+            column = next;
+            if row_of[column] == 0 {
+                break;
+            }
+        }
+        // Flip the augmenting path back to its start.
+        while column != 0 {
+            let before = previous[column];
+            row_of[column] = row_of[before];
+            column = before;
+        }
+    }
+    row_of[1..]
+        .iter()
+        .map(|&row| if row == 0 { None } else { Some(row - 1) })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    /// The best total score over all matchings, by exhaustive search.
+    fn best_total(matrix: &ScoreMatrix<u8>, min_score: u8, row: usize, used: &mut [bool]) -> u32 {
+        if row == matrix.rows() {
+            return 0;
+        }
+        let mut best = best_total(matrix, min_score, row + 1, used);
+        for column in 0..matrix.columns() {
+            let score = matrix.get(row, column).unwrap();
+            if !used[column] && score >= min_score {
+                used[column] = true;
+                let total = u32::from(score) + best_total(matrix, min_score, row + 1, used);
+                used[column] = false;
+                best = best.max(total);
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn assignment_is_optimal() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(44);
+        for _ in 0..300 {
+            let (rows, columns) = (rng.gen_range(0..6), rng.gen_range(0..6));
+            let scores = (0..rows * columns)
+                .map(|_| rng.gen_range(0..=100))
+                .collect();
+            let matrix = ScoreMatrix::new(rows, columns, scores);
+            let min_score = rng.gen_range(0..=100);
+            let assignment = assign(&matrix, min_score);
+
+            let mut matched_rows = vec![false; rows];
+            let mut matched_columns = vec![false; columns];
+            let mut total = 0;
+            for &(row, column, score) in assignment.pairs.iter() {
+                assert!(!matched_rows[row] && !matched_columns[column]);
+                assert_eq!(matrix.get(row, column), Some(score));
+                assert!(score >= min_score);
+                matched_rows[row] = true;
+                matched_columns[column] = true;
+                total += u32::from(score);
+            }
+            let unmatched_rows: Vec<usize> = (0..rows).filter(|&r| !matched_rows[r]).collect();
+            let unmatched_columns: Vec<usize> =
+                (0..columns).filter(|&c| !matched_columns[c]).collect();
+            assert_eq!(assignment.unmatched_left, unmatched_rows);
+            assert_eq!(assignment.unmatched_right, unmatched_columns);
+            assert_eq!(
+                total,
+                best_total(&matrix, min_score, 0, &mut vec![false; columns])
+            );
+        }
+    }
+}
@@ -0,0 +1,358 @@
+//! Candidate pair generation for record linkage, so not every pair of records gets scored.
+//!
+//! A [Blocker] is built from a blocking key per record, such as a name or a postcode, either
+//! for two lists to [link](Blocker::link) or for one list to [deduplicate](Blocker::dedup).
+//! Each strategy returns the [Candidates] pairs it lets through; strategies can be combined
+//! with [Candidates::union] to catch pairs one of them misses. Empty keys are treated as
+//! missing and never block records together.
+//!
+//! ```
+//! # use fuzzywuzzy::blocking::Blocker;
+//! let left = ["Smith", "Jones", "Robert"];
+//! let right = ["Smyth", "Rupert", "Jonas", "Brown"];
+//! let blocker = Blocker::link(&left, &right);
+//! assert_eq!(blocker.exact().len(), 0);
+//! let candidates = blocker.soundex();
+//! assert_eq!(candidates.iter().collect::<Vec<_>>(), vec![(0, 0), (1, 2), (2, 1)]);
+//! assert_eq!(candidates.total(), 12);
+//! assert_eq!(candidates.reduction_ratio(), 0.75);
+//! ```
+
+use crate::index::{Filter, NgramIndex};
+use crate::normalization::PassthroughNormalizer;
+use alloc::collections::{btree_set, BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Candidate pairs of records, as `(left, right)` indices.
+///
+/// When deduplicating, both indices are in the same list and `left < right`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Candidates {
+    pairs: BTreeSet<(usize, usize)>,
+    total: usize,
+}
+
+impl Candidates {
+    /// The number of candidate pairs.
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    /// Whether there are no candidate pairs.
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Whether `(left, right)` is a candidate pair.
+    pub fn contains(&self, left: usize, right: usize) -> bool {
+        self.pairs.contains(&(left, right))
+    }
+
+    /// Iterates over the candidate pairs, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.pairs.iter().cloned()
+    }
+
+    /// The number of pairs there are without blocking.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// The fraction of all pairs which are not candidates, between 0 and 1.
+    pub fn reduction_ratio(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            1.0 - self.pairs.len() as f64 / self.total as f64
+        }
+    }
+
+    /// The fraction of the `matches` which are candidates, between 0 and 1, or 1 if there are
+    /// no matches. Also known as pair completeness, it measures what blocking misses.
+    pub fn recall(&self, matches: &[(usize, usize)]) -> f64 {
+        if matches.is_empty() {
+            return 1.0;
+        }
+        let found = matches
+            .iter()
+            .filter(|&&(left, right)| self.contains(left, right))
+            .count();
+        found as f64 / matches.len() as f64
+    }
+
+    /// The candidate pairs of either `self` or `other`.
+    ///
+    /// Panics if they were not generated for the same records.
+    pub fn union(mut self, other: Candidates) -> Candidates {
+        assert_eq!(self.total, other.total, "candidates of different records");
+        self.pairs.extend(other.pairs);
+        self
+    }
+}
+
+impl<'a> IntoIterator for &'a Candidates {
+    type Item = (usize, usize);
+    type IntoIter = core::iter::Cloned<btree_set::Iter<'a, (usize, usize)>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pairs.iter().cloned()
+    }
+}
+
+/// Generates candidate pairs from the blocking keys of records.
+#[derive(Clone, Debug)]
+pub struct Blocker<'a> {
+    left: Vec<&'a str>,
+    /// `None` when deduplicating `left`.
+    right: Option<Vec<&'a str>>,
+}
+
+impl<'a> Blocker<'a> {
+    /// Pairs records of `left` with records of `right`, by their keys.
+    pub fn link<A: AsRef<str>, B: AsRef<str>>(left: &'a [A], right: &'a [B]) -> Blocker<'a> {
+        Blocker {
+            left: left.iter().map(|key| key.as_ref()).collect(),
+            right: Some(right.iter().map(|key| key.as_ref()).collect()),
+        }
+    }
+
+    /// Pairs records of `keys` with each other.
+    pub fn dedup<A: AsRef<str>>(keys: &'a [A]) -> Blocker<'a> {
+        Blocker {
+            left: keys.iter().map(|key| key.as_ref()).collect(),
+            right: None,
+        }
+    }
+
+    fn right(&self) -> &[&'a str] {
+        self.right.as_ref().unwrap_or(&self.left)
+    }
+
+    fn candidates(&self) -> Candidates {
+        let n = self.left.len();
+        Candidates {
+            pairs: BTreeSet::new(),
+            total: match &self.right {
+                Some(right) => n * right.len(),
+                None => n * n.saturating_sub(1) / 2,
+            },
+        }
+    }
+
+    /// Adds `(left, right)`, unless it pairs a record with itself or is a duplicate the wrong
+    /// way around.
+    fn add(&self, candidates: &mut Candidates, left: usize, right: usize) {
+        if self.right.is_some() {
+            candidates.pairs.insert((left, right));
+        } else if left != right {
+            candidates.pairs.insert((left.min(right), left.max(right)));
+        }
+    }
+
+    /// Pairs the records whose keys are equal.
+    pub fn exact(&self) -> Candidates {
+        self.by_key(|key| String::from(key))
+    }
+
+    /// Pairs the records whose keys have the same [soundex] code.
+    pub fn soundex(&self) -> Candidates {
+        self.by_key(soundex)
+    }
+
+    /// Pairs the records whose keys map to the same non-empty block key.
+    pub fn by_key<K: Ord, F: Fn(&str) -> K>(&self, block_key: F) -> Candidates {
+        let mut blocks: BTreeMap<K, Vec<usize>> = BTreeMap::new();
+        for (j, key) in self.right().iter().enumerate() {
+            if !key.is_empty() {
+                blocks.entry(block_key(key)).or_default().push(j);
+            }
+        }
+        let mut candidates = self.candidates();
+        for (i, key) in self.left.iter().enumerate() {
+            if key.is_empty() {
+                continue;
+            }
+            if let Some(block) = blocks.get(&block_key(key)) {
+                for &j in block {
+                    self.add(&mut candidates, i, j);
+                }
+            }
+        }
+        candidates
+    }
+
+    /// Sorts the records of both sides together by key, and pairs the records less than
+    /// `window` positions apart.
+    ///
+    /// Unlike key blocks, this pairs records whose keys are similar but not equal, as long as
+    /// they sort close to each other; every record gets at most `2 * (window - 1)` candidates.
+    pub fn sorted_neighbourhood(&self, window: usize) -> Candidates {
+        // Records as (key, side, index), side 0 being left.
+        let mut records: Vec<(&str, usize, usize)> = self
+            .left
+            .iter()
+            .enumerate()
+            .map(|(i, &key)| (key, 0, i))
+            .collect();
+        if let Some(right) = &self.right {
+            records.extend(right.iter().enumerate().map(|(j, &key)| (key, 1, j)));
+        }
+        records.retain(|&(key, _, _)| !key.is_empty());
+        records.sort_unstable();
+        let mut candidates = self.candidates();
+        for (p, &(_, side, index)) in records.iter().enumerate() {
+            for &(_, other_side, other) in records.iter().skip(p + 1).take(window.saturating_sub(1))
+            {
+                match (side, other_side) {
+                    (0, 1) => self.add(&mut candidates, index, other),
+                    (1, 0) => self.add(&mut candidates, other, index),
+                    (0, 0) if self.right.is_none() => self.add(&mut candidates, index, other),
+                    _ => {}
+                }
+            }
+        }
+        candidates
+    }
+
+    /// Pairs the records whose keys share at least `min_shared` character `n`-grams, using an
+    /// [NgramIndex] of the right keys.
+    pub fn ngrams(&self, n: usize, min_shared: usize) -> Candidates {
+        let index = NgramIndex::from_choices(n, PassthroughNormalizer, self.right());
+        let mut candidates = self.candidates();
+        for (i, key) in self.left.iter().enumerate() {
+            if key.is_empty() {
+                continue;
+            }
+            for j in index.candidates(key, 0, Filter::MinOverlap(min_shared)) {
+                if !self.right()[j].is_empty() {
+                    self.add(&mut candidates, i, j);
+                }
+            }
+        }
+        candidates
+    }
+}
+
+/// The American Soundex code of `s`: its first letter followed by three digits standing for
+/// the sounds of the next consonants, or an empty string if `s` has no ASCII letters.
+///
+/// Non-ASCII characters are ignored.
+///
+/// ```
+/// # use fuzzywuzzy::blocking::soundex;
+/// assert_eq!(soundex("Robert"), "R163");
+/// assert_eq!(soundex("Rupert"), "R163");
+/// assert_eq!(soundex("Ashcraft"), "A261");
+/// assert_eq!(soundex("Tymczak"), "T522");
+/// assert_eq!(soundex("Pfister"), "P236");
+/// assert_eq!(soundex("Lee"), "L000");
+/// assert_eq!(soundex("123"), "");
+/// ```
+pub fn soundex(s: &str) -> String {
+    // The digit of each letter; 0 for vowels, which separate consonants, and None for 'h' and
+    // 'w', which do not.
+    fn digit(c: char) -> Option<u8> {
+        match c {
+            'b' | 'f' | 'p' | 'v' => Some(1),
+            'c' | 'g' | 'j' | 'k' | 'q' | 's' | 'x' | 'z' => Some(2),
+            'd' | 't' => Some(3),
+            'l' => Some(4),
+            'm' | 'n' => Some(5),
+            'r' => Some(6),
+            'h' | 'w' => None,
+            _ => Some(0),
+        }
+    }
+    let mut letters = s
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .map(|c| c.to_ascii_lowercase());
+    let first = match letters.next() {
+        Some(first) => first,
+        None => return String::new(),
+    };
+    let mut code = String::with_capacity(4);
+    code.push(first.to_ascii_uppercase());
+    let mut previous = digit(first).unwrap_or(0);
+    for c in letters {
+        if code.len() == 4 {
+            break;
+        }
+        match digit(c) {
+            None => {}
+            Some(0) => previous = 0,
+            Some(d) => {
+                if d != previous {
+                    code.push(char::from(b'0' + d));
+                }
+                previous = d;
+            }
+        }
+    }
+    while code.len() < 4 {
+        code.push('0');
+    }
+    code
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The candidates of `strategy` for deduplicating `keys`, by linking them with themselves.
+    fn dedup_by_linking(
+        keys: &[&str],
+        strategy: impl Fn(&Blocker) -> Candidates,
+    ) -> Vec<(usize, usize)> {
+        strategy(&Blocker::link(keys, keys))
+            .iter()
+            .filter(|&(i, j)| i < j)
+            .collect()
+    }
+
+    #[test]
+    fn dedup_agrees_with_linking_a_list_with_itself() {
+        let keys = [
+            "smith", "smyth", "", "jones", "smith", "jonas", "brown", "browne", "smith",
+        ];
+        let dedup = Blocker::dedup(&keys);
+        assert_eq!(dedup.exact().total(), 36);
+        let strategies: [fn(&Blocker) -> Candidates; 3] =
+            [|b| b.exact(), |b| b.soundex(), |b| b.ngrams(2, 3)];
+        for strategy in strategies.iter() {
+            assert_eq!(
+                strategy(&dedup).iter().collect::<Vec<_>>(),
+                dedup_by_linking(&keys, strategy)
+            );
+        }
+        assert_eq!(
+            dedup.exact().iter().collect::<Vec<_>>(),
+            vec![(0, 4), (0, 8), (4, 8)]
+        );
+    }
+
+    #[test]
+    fn sorted_neighbourhood_pairs_close_keys() {
+        let left = ["carter", "baker", "", "abbot"];
+        let right = ["barker", "cartier", "abbott", "dalton"];
+        let blocker = Blocker::link(&left, &right);
+        // Sorted: abbot(l3) abbott(r2) baker(l1) barker(r0) carter(l0) cartier(r1) dalton(r3)
+        assert_eq!(
+            blocker.sorted_neighbourhood(2).iter().collect::<Vec<_>>(),
+            vec![(0, 0), (0, 1), (1, 0), (1, 2), (3, 2)]
+        );
+        assert_eq!(
+            blocker.sorted_neighbourhood(3).iter().collect::<Vec<_>>(),
+            vec![(0, 0), (0, 1), (0, 3), (1, 0), (1, 2), (3, 2)]
+        );
+        let dedup = Blocker::dedup(&right).sorted_neighbourhood(2);
+        assert_eq!(
+            dedup.iter().collect::<Vec<_>>(),
+            vec![(0, 1), (0, 2), (1, 3)]
+        );
+        assert_eq!(dedup.total(), 6);
+        let all = blocker.exact().union(blocker.sorted_neighbourhood(2));
+        assert_eq!(all.recall(&[(0, 1), (3, 2), (1, 1)]), 2.0 / 3.0);
+    }
+}
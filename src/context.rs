@@ -0,0 +1,264 @@
+//! Reusable scratch buffers for allocation-free repeated scoring.
+//!
+//! Every function in [fuzz](crate::fuzz) allocates: the strings are collected into
+//! characters, matching blocks are accumulated into vectors and processed strings
+//! are built from scratch. That is fine for a handful of comparisons but adds up
+//! in a hot loop over millions of pairs.
+//!
+//! A [ScoringContext] owns all of those buffers and lends them to its scorers.
+//! Once its buffers have grown to fit the largest inputs seen, scoring performs
+//! no further heap allocations (the one exception being strings containing `'Σ'`,
+//! whose lowercase form depends on its neighbours). The scores are identical to the ones produced by
+//! the equivalent [fuzz](crate::fuzz) functions, which run the same code with fresh buffers.
+//!
+//! ```
+//! # use fuzzywuzzy::context::ScoringContext;
+//! # use fuzzywuzzy::fuzz;
+//! let mut ctx = ScoringContext::new();
+//! let choices = ["new york mets", "new york yankees", "atlanta braves"];
+//! for choice in choices.iter() {
+//!     assert_eq!(ctx.ratio("new york", choice), fuzz::ratio("new york", choice));
+//!     assert_eq!(ctx.wratio("new york", choice, true, true), fuzz::wratio("new york", choice, true, true));
+//! }
+//! ```
+
+use crate::fuzz::Scratch;
+
+/// Holds the scratch buffers used by the scorers of this module.
+///
+/// A context is cheap to create but is meant to be kept around and reused:
+/// its buffers only ever grow, so after a warm-up period scoring does not
+/// allocate. A context is not shared between threads; create one per thread.
+#[derive(Default, Debug)]
+pub struct ScoringContext {
+    scratch: Scratch,
+}
+
+impl ScoringContext {
+    /// Creates a context with empty buffers.
+    pub fn new() -> ScoringContext {
+        Default::default()
+    }
+
+    /// Same as [fuzz::ratio](crate::fuzz::ratio).
+    ///
+    /// ```
+    /// # use fuzzywuzzy::context::ScoringContext;
+    /// let mut ctx = ScoringContext::new();
+    /// assert_eq!(ctx.ratio("cd", "abcd"), 67);
+    /// assert_eq!(ctx.ratio("new york mets", "new YORK mets"), 69);
+    /// ```
+    pub fn ratio(&mut self, a: &str, b: &str) -> u8 {
+        self.scratch.ratio(a, b)
+    }
+
+    /// Same as [fuzz::partial_ratio](crate::fuzz::partial_ratio).
+    ///
+    /// ```
+    /// # use fuzzywuzzy::context::ScoringContext;
+    /// let mut ctx = ScoringContext::new();
+    /// assert_eq!(ctx.partial_ratio("ad", "abcd"), 50);
+    /// assert_eq!(ctx.partial_ratio("new york mets", "the new york mets"), 100);
+    /// ```
+    pub fn partial_ratio(&mut self, s1: &str, s2: &str) -> u8 {
+        self.scratch.partial_ratio(s1, s2)
+    }
+
+    /// Same as [utils::full_process](crate::utils::full_process), but writes into a reused buffer.
+    ///
+    /// ```
+    /// # use fuzzywuzzy::context::ScoringContext;
+    /// let mut ctx = ScoringContext::new();
+    /// assert_eq!(ctx.full_process("C'est la vie", false), "c est la vie");
+    /// assert_eq!(ctx.full_process("¬Camarões assados", true), "camares assados");
+    /// ```
+    pub fn full_process(&mut self, s: &str, force_ascii: bool) -> &str {
+        self.scratch.full_process(s, force_ascii)
+    }
+
+    /// Same as [fuzz::token_sort_ratio](crate::fuzz::token_sort_ratio).
+    pub fn token_sort_ratio(
+        &mut self,
+        s1: &str,
+        s2: &str,
+        force_ascii: bool,
+        full_process: bool,
+    ) -> u8 {
+        self.scratch
+            .token_sort(s1, s2, false, force_ascii, full_process)
+    }
+
+    /// Same as [fuzz::partial_token_sort_ratio](crate::fuzz::partial_token_sort_ratio).
+    pub fn partial_token_sort_ratio(
+        &mut self,
+        s1: &str,
+        s2: &str,
+        force_ascii: bool,
+        full_process: bool,
+    ) -> u8 {
+        self.scratch
+            .token_sort(s1, s2, true, force_ascii, full_process)
+    }
+
+    /// Same as [fuzz::token_set_ratio](crate::fuzz::token_set_ratio).
+    pub fn token_set_ratio(
+        &mut self,
+        s1: &str,
+        s2: &str,
+        force_ascii: bool,
+        full_process: bool,
+    ) -> u8 {
+        self.scratch
+            .token_set(s1, s2, false, force_ascii, full_process)
+    }
+
+    /// Same as [fuzz::partial_token_set_ratio](crate::fuzz::partial_token_set_ratio).
+    pub fn partial_token_set_ratio(
+        &mut self,
+        s1: &str,
+        s2: &str,
+        force_ascii: bool,
+        full_process: bool,
+    ) -> u8 {
+        self.scratch
+            .token_set(s1, s2, true, force_ascii, full_process)
+    }
+
+    /// Same as [fuzz::qratio](crate::fuzz::qratio).
+    pub fn qratio(&mut self, s1: &str, s2: &str, force_ascii: bool) -> u8 {
+        self.scratch.qratio(s1, s2, force_ascii)
+    }
+
+    /// Same as [fuzz::uqratio](crate::fuzz::uqratio).
+    pub fn uqratio(&mut self, s1: &str, s2: &str) -> u8 {
+        self.qratio(s1, s2, false)
+    }
+
+    /// Same as [fuzz::wratio](crate::fuzz::wratio).
+    ///
+    /// ```
+    /// # use fuzzywuzzy::context::ScoringContext;
+    /// let mut ctx = ScoringContext::new();
+    /// assert_eq!(ctx.wratio("hello world", "world hello", true, true), 95);
+    /// assert_eq!(ctx.wratio("new york mets", "the wonderful new york mets", true, true), 90);
+    /// ```
+    pub fn wratio(&mut self, s1: &str, s2: &str, force_ascii: bool, full_process: bool) -> u8 {
+        self.scratch.wratio(s1, s2, force_ascii, full_process)
+    }
+
+    /// Same as [fuzz::uwratio](crate::fuzz::uwratio).
+    pub fn uwratio(&mut self, s1: &str, s2: &str, full_process: bool) -> u8 {
+        self.wratio(s1, s2, false, full_process)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{fuzz, utils};
+    use rand::{Rng, SeedableRng};
+
+    const PAIRS: [(&str, &str); 12] = [
+        ("new york mets", "new YORK mets"),
+        ("new york mets", "the wonderful new york mets"),
+        (
+            "new york mets vs atlanta braves",
+            "atlanta braves vs new york mets",
+        ),
+        ("fuzzy was a bear", "fuzzy fuzzy was a bear"),
+        (
+            "what about supercalifragilisticexpialidocious",
+            "supercalifragilisticexpialidocious about what",
+        ),
+        ("スマホでchance", "chance"),
+        ("fòllòwbáck", "followback"),
+        ("ΣΑΣ σας", "σας ΣΑΣ!"),
+        ("", "nonempty"),
+        ("", ""),
+        ("¬Camarões assados", "camaroes assados"),
+        ("a", "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"),
+    ];
+
+    fn assert_same_scores(ctx: &mut ScoringContext, a: &str, b: &str) {
+        assert_eq!(ctx.ratio(a, b), fuzz::ratio(a, b), "{} {}", a, b);
+        assert_eq!(
+            ctx.partial_ratio(a, b),
+            fuzz::partial_ratio(a, b),
+            "{} {}",
+            a,
+            b
+        );
+        assert_eq!(
+            ctx.qratio(a, b, true),
+            fuzz::qratio(a, b, true),
+            "{} {}",
+            a,
+            b
+        );
+        assert_eq!(ctx.uqratio(a, b), fuzz::uqratio(a, b), "{} {}", a, b);
+        assert_eq!(ctx.full_process(a, true), utils::full_process(a, true));
+        assert_eq!(ctx.full_process(a, false), utils::full_process(a, false));
+        for &(force_ascii, full_process) in [(true, true), (false, true), (true, false)].iter() {
+            assert_eq!(
+                ctx.token_sort_ratio(a, b, force_ascii, full_process),
+                fuzz::token_sort_ratio(a, b, force_ascii, full_process),
+                "{} {}",
+                a,
+                b
+            );
+            assert_eq!(
+                ctx.partial_token_sort_ratio(a, b, force_ascii, full_process),
+                fuzz::partial_token_sort_ratio(a, b, force_ascii, full_process),
+                "{} {}",
+                a,
+                b
+            );
+            assert_eq!(
+                ctx.token_set_ratio(a, b, force_ascii, full_process),
+                fuzz::token_set_ratio(a, b, force_ascii, full_process),
+                "{} {}",
+                a,
+                b
+            );
+            assert_eq!(
+                ctx.partial_token_set_ratio(a, b, force_ascii, full_process),
+                fuzz::partial_token_set_ratio(a, b, force_ascii, full_process),
+                "{} {}",
+                a,
+                b
+            );
+            assert_eq!(
+                ctx.wratio(a, b, force_ascii, full_process),
+                fuzz::wratio(a, b, force_ascii, full_process),
+                "{} {}",
+                a,
+                b
+            );
+        }
+    }
+
+    #[test]
+    fn same_scores_as_fuzz() {
+        let mut ctx = ScoringContext::new();
+        for &(a, b) in PAIRS.iter() {
+            assert_same_scores(&mut ctx, a, b);
+            assert_same_scores(&mut ctx, b, a);
+        }
+    }
+
+    #[test]
+    fn same_scores_as_fuzz_random() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(27);
+        let alphabet = ['a', 'b', 'c', ' ', 'B', 'é'];
+        let mut ctx = ScoringContext::new();
+        for _ in 0..500 {
+            let mut random_string = |len: usize| -> String {
+                (0..len)
+                    .map(|_| alphabet[rng.gen_range(0..alphabet.len())])
+                    .collect()
+            };
+            let (a, b) = (random_string(12), random_string(8));
+            assert_same_scores(&mut ctx, &a, &b);
+        }
+    }
+}
@@ -0,0 +1,297 @@
+//! Choosing a score cutoff from labeled pairs, rather than by hand.
+//!
+//! An [Evaluation] counts, for every cutoff from 0 to 100, how many labeled pairs would be
+//! accepted or rejected correctly, and derives precision, recall, F1, and ROC and
+//! precision-recall curves from that.
+//!
+//! ```
+//! # use fuzzywuzzy::evaluation::evaluate;
+//! # use fuzzywuzzy::processor::FullProcess;
+//! # use fuzzywuzzy::scorer::TokenSortRatio;
+//! let pairs = [
+//!     ("John Smith", "Smith, John", true),
+//!     ("Jon Smith", "John Smith", true),
+//!     ("Acme Corp", "ACME Corporation", true),
+//!     ("John Smith", "Jane Smith", false),
+//!     ("Acme Corp", "Apex Corp", false),
+//!     ("Globex", "Initech", false),
+//! ];
+//! let evaluation = evaluate(&pairs, FullProcess::default(), TokenSortRatio::default());
+//! let cutoff = evaluation.best_cutoff();
+//! assert_eq!(cutoff, 95);
+//! assert_eq!(evaluation.at(cutoff).f1(), 0.8);
+//! // Accepting "Acme Corp" would take accepting "Jane Smith" as well.
+//! assert_eq!(evaluation.cutoff_for_precision(1.0), Some(81));
+//! assert!(evaluation.roc_auc() > 0.75);
+//! ```
+
+use crate::normalization;
+use crate::processor::Processor;
+use crate::scorer::Scorer;
+use alloc::vec::Vec;
+
+/// The outcome of accepting the pairs scoring at least `cutoff`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Threshold {
+    /// The score cutoff.
+    pub cutoff: u8,
+    /// Matches accepted.
+    pub true_positives: usize,
+    /// Non-matches accepted.
+    pub false_positives: usize,
+    /// Non-matches rejected.
+    pub true_negatives: usize,
+    /// Matches rejected.
+    pub false_negatives: usize,
+}
+
+impl Threshold {
+    /// The fraction of accepted pairs which are matches, or 1 if none are accepted.
+    pub fn precision(&self) -> f64 {
+        ratio(
+            self.true_positives,
+            self.true_positives + self.false_positives,
+            1.0,
+        )
+    }
+
+    /// The fraction of matches which are accepted, or 1 if there are no matches.
+    pub fn recall(&self) -> f64 {
+        ratio(
+            self.true_positives,
+            self.true_positives + self.false_negatives,
+            1.0,
+        )
+    }
+
+    /// The harmonic mean of precision and recall, or 0 if nothing is accepted correctly.
+    pub fn f1(&self) -> f64 {
+        ratio(
+            2 * self.true_positives,
+            2 * self.true_positives + self.false_positives + self.false_negatives,
+            0.0,
+        )
+    }
+
+    /// The fraction of non-matches which are accepted, or 0 if there are no non-matches.
+    pub fn false_positive_rate(&self) -> f64 {
+        ratio(
+            self.false_positives,
+            self.false_positives + self.true_negatives,
+            0.0,
+        )
+    }
+
+    /// The fraction of pairs classified correctly, or 1 if there are no pairs.
+    pub fn accuracy(&self) -> f64 {
+        ratio(
+            self.true_positives + self.true_negatives,
+            self.true_positives + self.true_negatives + self.false_positives + self.false_negatives,
+            1.0,
+        )
+    }
+}
+
+/// `numerator / denominator`, or `otherwise` if the denominator is 0.
+fn ratio(numerator: usize, denominator: usize, otherwise: f64) -> f64 {
+    if denominator == 0 {
+        otherwise
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+/// The [Threshold]s of labeled scores at every cutoff from 0 to 100.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Evaluation {
+    thresholds: Vec<Threshold>,
+}
+
+impl Evaluation {
+    /// Evaluates `(score, is_match)` pairs. Scores above 100 count as 100.
+    pub fn from_scores<I: IntoIterator<Item = (u8, bool)>>(scores: I) -> Evaluation {
+        // The number of matches and non-matches at each score.
+        let mut matches = [0; 101];
+        let mut others = [0; 101];
+        for (score, is_match) in scores {
+            let score = usize::from(score.min(100));
+            if is_match {
+                matches[score] += 1;
+            } else {
+                others[score] += 1;
+            }
+        }
+        let (all_matches, all_others): (usize, usize) = (matches.iter().sum(), others.iter().sum());
+        let (mut accepted_matches, mut accepted_others) = (0, 0);
+        let mut thresholds: Vec<Threshold> = (0..=100u8)
+            .rev()
+            .map(|cutoff| {
+                accepted_matches += matches[usize::from(cutoff)];
+                accepted_others += others[usize::from(cutoff)];
+                Threshold {
+                    cutoff,
+                    true_positives: accepted_matches,
+                    false_positives: accepted_others,
+                    true_negatives: all_others - accepted_others,
+                    false_negatives: all_matches - accepted_matches,
+                }
+            })
+            .collect();
+        thresholds.reverse();
+        Evaluation { thresholds }
+    }
+
+    /// The outcome at every cutoff, from 0 to 100.
+    pub fn thresholds(&self) -> &[Threshold] {
+        &self.thresholds
+    }
+
+    /// The outcome at `cutoff`. Cutoffs above 100 count as 100.
+    pub fn at(&self, cutoff: u8) -> Threshold {
+        self.thresholds[usize::from(cutoff.min(100))]
+    }
+
+    /// The cutoff with the highest F1, the highest one on ties.
+    pub fn best_cutoff(&self) -> u8 {
+        self.thresholds
+            .iter()
+            .rev()
+            .fold(None::<&Threshold>, |best, threshold| match best {
+                Some(best) if best.f1() >= threshold.f1() => Some(best),
+                _ => Some(threshold),
+            })
+            .map_or(100, |best| best.cutoff)
+    }
+
+    /// The lowest cutoff whose precision is at least `min_precision`, so it keeps the most
+    /// recall, or `None` if no cutoff accepting anything reaches it.
+    pub fn cutoff_for_precision(&self, min_precision: f64) -> Option<u8> {
+        self.thresholds
+            .iter()
+            .find(|threshold| {
+                threshold.true_positives + threshold.false_positives > 0
+                    && threshold.precision() >= min_precision
+            })
+            .map(|threshold| threshold.cutoff)
+    }
+
+    /// The points `(false positive rate, recall)` of the ROC curve, from accepting nothing at
+    /// `(0, 0)` through each cutoff down to 0.
+    pub fn roc_curve(&self) -> Vec<(f64, f64)> {
+        let mut points = vec![(0.0, 0.0)];
+        points.extend(
+            self.thresholds
+                .iter()
+                .rev()
+                .map(|threshold| (threshold.false_positive_rate(), threshold.recall())),
+        );
+        points
+    }
+
+    /// The area under the ROC curve: the probability that a random match scores higher than a
+    /// random non-match, counting ties as half.
+    pub fn roc_auc(&self) -> f64 {
+        self.roc_curve()
+            .windows(2)
+            .map(|pair| (pair[1].0 - pair[0].0) * (pair[0].1 + pair[1].1) / 2.0)
+            .sum()
+    }
+
+    /// The points `(recall, precision)` of the precision-recall curve, for each cutoff
+    /// accepting some pairs, from the highest one down.
+    pub fn precision_recall_curve(&self) -> Vec<(f64, f64)> {
+        self.thresholds
+            .iter()
+            .rev()
+            .filter(|threshold| threshold.true_positives + threshold.false_positives > 0)
+            .map(|threshold| (threshold.recall(), threshold.precision()))
+            .collect()
+    }
+}
+
+/// Scores labeled `(a, b, is_match)` pairs with `scorer` after `processor`, and evaluates them.
+pub fn evaluate<A, B, P, S>(pairs: &[(A, B, bool)], processor: P, scorer: S) -> Evaluation
+where
+    A: AsRef<str>,
+    B: AsRef<str>,
+    P: Processor,
+    S: Scorer<Score = u8>,
+{
+    let prepare = |s: &str| {
+        let processed = processor.process(s);
+        normalization::then(processed, |s| scorer.preprocess(s)).into_owned()
+    };
+    Evaluation::from_scores(pairs.iter().map(|(a, b, is_match)| {
+        let score = scorer.score_processed(&prepare(a.as_ref()), &prepare(b.as_ref()));
+        (score, *is_match)
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn metrics_agree_with_direct_counts() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(49);
+        let scores: Vec<(u8, bool)> = (0..300)
+            .map(|_| {
+                let is_match = rng.gen_bool(0.3);
+                let score = if is_match {
+                    rng.gen_range(50..=100)
+                } else {
+                    rng.gen_range(0..=80)
+                };
+                (score, is_match)
+            })
+            .collect();
+        let evaluation = Evaluation::from_scores(scores.iter().cloned());
+        for cutoff in 0..=100 {
+            let count = |accepted: bool, is_match: bool| {
+                scores
+                    .iter()
+                    .filter(|&&(s, m)| (s >= cutoff) == accepted && m == is_match)
+                    .count()
+            };
+            let threshold = evaluation.at(cutoff);
+            assert_eq!(threshold.cutoff, cutoff);
+            assert_eq!(threshold.true_positives, count(true, true));
+            assert_eq!(threshold.false_positives, count(true, false));
+            assert_eq!(threshold.true_negatives, count(false, false));
+            assert_eq!(threshold.false_negatives, count(false, true));
+        }
+        let best = evaluation.at(evaluation.best_cutoff()).f1();
+        assert!(evaluation.thresholds().iter().all(|t| t.f1() <= best));
+
+        // The AUC is the probability that a match outscores a non-match.
+        let (mut wins, mut comparisons) = (0.0, 0.0);
+        for &(a, _) in scores.iter().filter(|&&(_, m)| m) {
+            for &(b, _) in scores.iter().filter(|&&(_, m)| !m) {
+                comparisons += 1.0;
+                wins += match a.cmp(&b) {
+                    core::cmp::Ordering::Greater => 1.0,
+                    core::cmp::Ordering::Equal => 0.5,
+                    core::cmp::Ordering::Less => 0.0,
+                };
+            }
+        }
+        assert!((evaluation.roc_auc() - wins / comparisons).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cutoffs_of_separable_scores() {
+        let evaluation = Evaluation::from_scores(vec![(95, true), (90, true), (60, false)]);
+        assert_eq!(evaluation.best_cutoff(), 90);
+        assert_eq!(evaluation.cutoff_for_precision(1.0), Some(61));
+        assert_eq!(evaluation.roc_auc(), 1.0);
+        assert_eq!(evaluation.at(61).accuracy(), 1.0);
+        assert_eq!(
+            evaluation.precision_recall_curve().first(),
+            Some(&(0.5, 1.0))
+        );
+        let empty = Evaluation::from_scores(vec![]);
+        assert_eq!(empty.best_cutoff(), 100);
+        assert_eq!(empty.cutoff_for_precision(0.0), None);
+    }
+}
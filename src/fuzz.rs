@@ -1,7 +1,10 @@
 //! Fuzzy string matching scoring primitives.
 
+use crate::ascii;
+use crate::primitives::{self, MatchingStreak};
 use crate::utils;
-use std::collections::HashSet;
+use alloc::string::String;
+use alloc::vec::Vec;
 
 /// Returns the ratio of the length of matching character sequences to the sum of the length of the input strings.
 ///
@@ -24,17 +27,57 @@ use std::collections::HashSet;
 /// assert_eq!(ratio("hello test", "hello world"), 57);
 /// ```
 pub fn ratio(a: &str, b: &str) -> u8 {
+    Scratch::default().ratio(a, b)
+}
+
+/// Returns the similarity of two strings based on their Levenshtein distance as a number between 0 and 100.
+///
+/// The distance is computed over `char`s and normalized by the length of the longer string,
+/// so the result is `100 * (1 - distance / max(a.len(), b.len()))`, rounded.
+///
+/// Unlike [ratio], a `score_cutoff` on this ratio can be translated into a maximum edit
+/// distance (see [utils::max_distance_for_ratio]), which allows cheaper bounded computations
+/// such as [process::extract_levenshtein](crate::process::extract_levenshtein).
+///
+/// ```
+/// # use fuzzywuzzy::fuzz::levenshtein_ratio;
+/// assert_eq!(levenshtein_ratio("", ""), 100);
+/// assert_eq!(levenshtein_ratio("", "nonempty"), 0);
+/// assert_eq!(levenshtein_ratio("kitten", "sitting"), 57);
+/// assert_eq!(levenshtein_ratio("new york mets", "new york mets"), 100);
+/// assert_eq!(levenshtein_ratio("new york mets", "new YORK mets"), 69);
+/// ```
+pub fn levenshtein_ratio(a: &str, b: &str) -> u8 {
     check_trivial!(a, b);
-    let matches: usize = utils::get_matching_blocks(a, b)
-        .iter()
-        .map(|&(_, _, s)| s)
-        .sum();
-    let sumlength: f32 = (a.chars().count() + b.chars().count()) as f32;
-    if sumlength > 0.0 {
-        (100.0 * (2.0 * (matches as f32) / sumlength)).round() as u8
-    } else {
-        100
+    if a.is_ascii() && b.is_ascii() {
+        let length = core::cmp::max(a.len(), b.len());
+        let distance = ascii::bounded_levenshtein(a.as_bytes(), b.as_bytes(), length)
+            .expect("distance never exceeds the longer length");
+        return utils::ratio_from_distance(distance, length);
     }
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let distance = primitives::levenshtein(&a, &b);
+    utils::ratio_from_distance(distance, core::cmp::max(a.len(), b.len()))
+}
+
+/// Returns the Jaro-Winkler similarity of two strings as a number between 0 and 100.
+///
+/// See [primitives::jaro_winkler]. It favors strings which share a prefix, and suits short
+/// strings such as names better than [ratio].
+///
+/// ```
+/// # use fuzzywuzzy::fuzz::jaro_winkler_ratio;
+/// assert_eq!(jaro_winkler_ratio("", ""), 100);
+/// assert_eq!(jaro_winkler_ratio("", "nonempty"), 0);
+/// assert_eq!(jaro_winkler_ratio("martha", "marhta"), 96);
+/// assert_eq!(jaro_winkler_ratio("dwayne", "duane"), 84);
+/// ```
+pub fn jaro_winkler_ratio(a: &str, b: &str) -> u8 {
+    check_trivial!(a, b);
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    utils::round_to_u8(100.0 * primitives::jaro_winkler(&a, &b))
 }
 
 /// Return the ratio of the most similar substring as a number between 0 and 100.
@@ -57,6 +100,8 @@ pub fn ratio(a: &str, b: &str) -> u8 {
 /// assert_eq!(partial_ratio("hello", "hello world"), 100);
 /// assert_eq!(partial_ratio("new york mets", "the new york mets"), 100);
 /// assert_eq!(partial_ratio("the new york mets", "new york mets"), 100);
+/// assert_eq!(partial_ratio("chance", "スマホでchance"), 100);
+/// assert_eq!(partial_ratio("éé", "éé Σ"), 100);
 /// // Note the order dependence due to not finding the optimal alignment
 /// assert_eq!(partial_ratio(
 ///    "what about supercalifragilisticexpialidocious",
@@ -66,53 +111,7 @@ pub fn ratio(a: &str, b: &str) -> u8 {
 ///    "what about supercalifragilisticexpialidocious"), 86);
 /// ```
 pub fn partial_ratio(s1: &str, s2: &str) -> u8 {
-    check_trivial!(s1, s2);
-    let (shorter, longer) = if s1.chars().count() <= s2.chars().count() {
-        (s1, s2)
-    } else {
-        (s2, s1)
-    };
-    let blocks = utils::get_matching_blocks(shorter, longer);
-    let mut max: u8 = 0;
-    for (i, j, _) in blocks {
-        let long_start = if j > i { j - i } else { 0 };
-        let long_end = std::cmp::min(long_start + shorter.chars().count(), longer.chars().count());
-        let long_substr = &longer[long_start..long_end];
-        let r = ratio(shorter, long_substr);
-        if r > 99 {
-            return 100;
-        } else if r > max {
-            max = r;
-        }
-    }
-    max
-}
-
-/// Return a cleaned string with token sorted.
-fn process_and_sort(s: &str, force_ascii: bool, full_process: bool) -> String {
-    let ts = if full_process {
-        utils::full_process(s, force_ascii)
-    } else {
-        s.to_string()
-    };
-    let mut ts_split: Vec<_> = ts.split_whitespace().collect();
-    ts_split.sort_unstable();
-    ts_split.join(" ")
-}
-
-/// Sorted Token
-/// # find all alphanumeric tokens in the string
-/// # sort those tokens and take ratio of resulting joined strings
-/// # controls for unordered string elements
-fn token_sort(s1: &str, s2: &str, partial: bool, force_ascii: bool, full_process: bool) -> u8 {
-    check_trivial!(s1, s2);
-    let sorted1 = process_and_sort(s1, force_ascii, full_process);
-    let sorted2 = process_and_sort(s2, force_ascii, full_process);
-    if partial {
-        partial_ratio(sorted1.as_ref(), sorted2.as_ref())
-    } else {
-        ratio(sorted1.as_ref(), sorted2.as_ref())
-    }
+    Scratch::default().partial_ratio(s1, s2)
 }
 
 /// Return a measure of the sequences' similarity between 0 and 100, but sort the token before
@@ -133,7 +132,7 @@ fn token_sort(s1: &str, s2: &str, partial: bool, force_ascii: bool, full_process
 /// ```
 pub fn token_sort_ratio(s1: &str, s2: &str, force_ascii: bool, full_process: bool) -> u8 {
     // trivial check omitted because this is a shallow delegator to token_sort which checks.
-    token_sort(s1, s2, false, force_ascii, full_process)
+    Scratch::default().token_sort(s1, s2, false, force_ascii, full_process)
 }
 
 /// Return the ratio of the most similar substring as a number between 0 and 100, but sort the tokens
@@ -155,64 +154,7 @@ pub fn token_sort_ratio(s1: &str, s2: &str, force_ascii: bool, full_process: boo
 /// ```
 pub fn partial_token_sort_ratio(s1: &str, s2: &str, force_ascii: bool, full_process: bool) -> u8 {
     // trivial check omitted because this is a shallow delegator to token_sort which checks.
-    token_sort(s1, s2, true, force_ascii, full_process)
-}
-
-/// Find all alphanumeric tokens in each string...
-///  # treat them as a set
-///  # construct two strings of the form: <sorted_intersection><sorted_remainder>
-///  # take ratios of those two strings
-///  # controls for unordered partial matches
-fn token_set(s1: &str, s2: &str, partial: bool, force_ascii: bool, full_process: bool) -> u8 {
-    check_trivial!(s1, s2);
-    let (p1, p2) = if full_process {
-        (
-            utils::full_process(s1, force_ascii),
-            utils::full_process(s2, force_ascii),
-        )
-    } else {
-        (s1.to_string(), s2.to_string())
-    };
-    let t1: HashSet<_> = p1.split_whitespace().collect();
-    let t2: HashSet<_> = p2.split_whitespace().collect();
-    let mut intersection: Vec<_> = t1.intersection(&t2).cloned().collect();
-    let mut diff1to2: Vec<_> = t1.difference(&t2).cloned().collect();
-    let mut diff2to1: Vec<_> = t2.difference(&t1).cloned().collect();
-    intersection.sort_unstable();
-    diff1to2.sort_unstable();
-    diff2to1.sort_unstable();
-    let intersect_str = intersection.join(" ");
-    let diff1to2_str = diff1to2.join(" ");
-    let diff2to1_str = diff2to1.join(" ");
-    let combined_1to2 = if !diff1to2_str.is_empty() {
-        intersect_str.to_string() + " " + &diff1to2_str
-    } else {
-        intersect_str.to_string()
-    };
-    let combined_2to1 = if !diff2to1_str.is_empty() {
-        intersect_str.to_string() + " " + &diff2to1_str
-    } else {
-        intersect_str.to_string()
-    };
-    if partial {
-        *vec![
-            partial_ratio(&intersect_str, &combined_1to2),
-            partial_ratio(&intersect_str, &combined_2to1),
-            partial_ratio(&combined_1to2, &combined_2to1),
-        ]
-        .iter()
-        .max()
-        .unwrap()
-    } else {
-        *vec![
-            ratio(&intersect_str, &combined_1to2),
-            ratio(&intersect_str, &combined_2to1),
-            ratio(&combined_1to2, &combined_2to1),
-        ]
-        .iter()
-        .max()
-        .unwrap()
-    }
+    Scratch::default().token_sort(s1, s2, true, force_ascii, full_process)
 }
 
 /// Return the ratio of the most similar substring constructed from the strings treated as sets, as a number between 0 and 100.
@@ -238,7 +180,7 @@ fn token_set(s1: &str, s2: &str, partial: bool, force_ascii: bool, full_process:
 /// ```
 pub fn token_set_ratio(s1: &str, s2: &str, force_ascii: bool, full_process: bool) -> u8 {
     // trivial check omitted because this is a shallow delegator to token_set which checks.
-    token_set(s1, s2, false, force_ascii, full_process)
+    Scratch::default().token_set(s1, s2, false, force_ascii, full_process)
 }
 
 /// Return the partial ratio of the most similar substring constructed from the strings treated as sets, as a number between 0 and 100.
@@ -264,7 +206,7 @@ pub fn token_set_ratio(s1: &str, s2: &str, force_ascii: bool, full_process: bool
 /// ```
 pub fn partial_token_set_ratio(s1: &str, s2: &str, force_ascii: bool, full_process: bool) -> u8 {
     // trivial check omitted because this is a shallow delegator to token_set which checks.
-    token_set(s1, s2, true, force_ascii, full_process)
+    Scratch::default().token_set(s1, s2, true, force_ascii, full_process)
 }
 
 /// Quick `ratio` comparison between two strings.
@@ -272,15 +214,7 @@ pub fn partial_token_set_ratio(s1: &str, s2: &str, force_ascii: bool, full_proce
 //  Runs utils::full_process on both strings.
 //  Short circuits if either of the strings is empty after processing.
 pub fn qratio(s1: &str, s2: &str, force_ascii: bool) -> u8 {
-    check_trivial!(s1, s2);
-    let (p1, p2) = (
-        utils::full_process(s1, force_ascii),
-        utils::full_process(s2, force_ascii),
-    );
-    if !utils::validate_string(p1.as_str()) || !utils::validate_string(p2.as_str()) {
-        return 0;
-    }
-    ratio(&p1, &p2)
+    Scratch::default().qratio(s1, s2, force_ascii)
 }
 
 /// micro-quick-ratio: `qratio` comparison between two strings without forcing to ascii.
@@ -323,68 +257,505 @@ pub fn uqratio(s1: &str, s2: &str) -> u8 {
 /// assert_eq!(wratio("new york mets vs atlanta braves", "atlanta braves vs new york mets", true, true), 95);
 /// ```
 pub fn wratio(s1: &str, s2: &str, force_ascii: bool, full_process: bool) -> u8 {
-    check_trivial!(s1, s2);
-    let (p1, p2) = if full_process {
-        (
-            utils::full_process(s1, force_ascii),
-            utils::full_process(s2, force_ascii),
+    Scratch::default().wratio(s1, s2, force_ascii, full_process)
+}
+
+/// Runs `wratio` without forcing to ascii.
+pub fn uwratio(s1: &str, s2: &str, full_process: bool) -> u8 {
+    // trivial check omitted because this is a shallow delegator to wratio which checks.
+    wratio(s1, s2, false, full_process)
+}
+
+/// The buffers used by the scorers of this module.
+///
+/// The functions above score with fresh buffers, while a
+/// [ScoringContext](crate::context::ScoringContext) keeps its buffers around so that,
+/// once they have grown, scoring does not allocate.
+#[derive(Default, Debug)]
+pub(crate) struct Scratch {
+    matcher: Matcher,
+    processed: [String; 2],
+    sorted: [String; 2],
+    tokens: [Vec<(usize, usize)>; 2],
+    intersection: String,
+    combined: [String; 2],
+}
+
+impl Scratch {
+    pub(crate) fn ratio(&mut self, a: &str, b: &str) -> u8 {
+        self.matcher.ratio(a, b)
+    }
+
+    pub(crate) fn partial_ratio(&mut self, s1: &str, s2: &str) -> u8 {
+        self.matcher.partial_ratio(s1, s2)
+    }
+
+    pub(crate) fn full_process(&mut self, s: &str, force_ascii: bool) -> &str {
+        utils::full_process_into(s, force_ascii, &mut self.processed[0]);
+        &self.processed[0]
+    }
+
+    /// Sorted Token
+    /// # find all alphanumeric tokens in the string
+    /// # sort those tokens and take ratio of resulting joined strings
+    /// # controls for unordered string elements
+    pub(crate) fn token_sort(
+        &mut self,
+        s1: &str,
+        s2: &str,
+        partial: bool,
+        force_ascii: bool,
+        full_process: bool,
+    ) -> u8 {
+        check_trivial!(s1, s2);
+        let [p1, p2] = &mut self.processed;
+        copy_or_process(s1, force_ascii, full_process, p1);
+        copy_or_process(s2, force_ascii, full_process, p2);
+        token_sort(
+            &mut self.matcher,
+            &mut self.sorted,
+            &mut self.tokens,
+            p1,
+            p2,
+            partial,
         )
-    } else {
-        (s1.to_string(), s2.to_string())
-    };
-    let (p1r, p2r) = (p1.as_str(), p2.as_str());
-    if !utils::validate_string(p1r) || !utils::validate_string(p2r) {
-        return 0;
     }
-    let mut try_partial = true;
-    const UNBASE_SCALE: f64 = 0.95;
-    let mut partial_scale = 0.90;
 
-    let base = ratio(p1r, p2r);
-    let (p1_len, p2_len) = (p1.chars().count(), p2.chars().count());
-    let len_ratio = std::cmp::max(p1_len, p2_len) as f64 / std::cmp::min(p1_len, p2_len) as f64;
+    /// Find all alphanumeric tokens in each string...
+    ///  # treat them as a set
+    ///  # construct two strings of the form: <sorted_intersection><sorted_remainder>
+    ///  # take ratios of those two strings
+    ///  # controls for unordered partial matches
+    pub(crate) fn token_set(
+        &mut self,
+        s1: &str,
+        s2: &str,
+        partial: bool,
+        force_ascii: bool,
+        full_process: bool,
+    ) -> u8 {
+        check_trivial!(s1, s2);
+        let [p1, p2] = &mut self.processed;
+        copy_or_process(s1, force_ascii, full_process, p1);
+        copy_or_process(s2, force_ascii, full_process, p2);
+        token_set(
+            &mut self.matcher,
+            &mut self.tokens,
+            &mut self.intersection,
+            &mut self.combined,
+            p1,
+            p2,
+            partial,
+        )
+    }
 
-    // if strings are similar length, don't use partials
-    if len_ratio < 1.5 {
-        try_partial = false;
+    pub(crate) fn qratio(&mut self, s1: &str, s2: &str, force_ascii: bool) -> u8 {
+        check_trivial!(s1, s2);
+        let [p1, p2] = &mut self.processed;
+        utils::full_process_into(s1, force_ascii, p1);
+        utils::full_process_into(s2, force_ascii, p2);
+        if !utils::validate_string(p1) || !utils::validate_string(p2) {
+            return 0;
+        }
+        self.matcher.ratio(p1, p2)
     }
 
-    // if one string is much shorter than the other
-    if len_ratio > 8.0 {
-        partial_scale = 0.6;
+    pub(crate) fn wratio(
+        &mut self,
+        s1: &str,
+        s2: &str,
+        force_ascii: bool,
+        full_process: bool,
+    ) -> u8 {
+        check_trivial!(s1, s2);
+        let Scratch {
+            matcher,
+            processed: [p1, p2],
+            sorted,
+            tokens,
+            intersection,
+            combined,
+        } = self;
+        copy_or_process(s1, force_ascii, full_process, p1);
+        copy_or_process(s2, force_ascii, full_process, p2);
+        let (p1, p2) = (p1.as_str(), p2.as_str());
+        if !utils::validate_string(p1) || !utils::validate_string(p2) {
+            return 0;
+        }
+        let mut try_partial = true;
+        const UNBASE_SCALE: f64 = 0.95;
+        let mut partial_scale = 0.90;
+
+        let base = matcher.ratio(p1, p2);
+        let (p1_len, p2_len) = (p1.chars().count(), p2.chars().count());
+        let len_ratio =
+            core::cmp::max(p1_len, p2_len) as f64 / core::cmp::min(p1_len, p2_len) as f64;
+
+        // if strings are similar length, don't use partials
+        if len_ratio < 1.5 {
+            try_partial = false;
+        }
+
+        // if one string is much shorter than the other
+        if len_ratio > 8.0 {
+            partial_scale = 0.6;
+        }
+
+        if try_partial {
+            let partial = matcher.partial_ratio(p1, p2) as f64 * partial_scale;
+            let ptsor = token_sort(matcher, sorted, tokens, p1, p2, true) as f64
+                * UNBASE_SCALE
+                * partial_scale;
+            let ptser = token_set(matcher, tokens, intersection, combined, p1, p2, true) as f64
+                * UNBASE_SCALE
+                * partial_scale;
+            // This conversion to u8 from the maximum f64 seems spooky, but let's hope nothing bad happens!
+            return utils::round_to_u8(
+                [base as f64, partial, ptsor, ptser]
+                    .iter()
+                    .cloned()
+                    .fold(f64::NAN, f64::max),
+            );
+        }
+        let tsor = token_sort(matcher, sorted, tokens, p1, p2, false) as f64 * UNBASE_SCALE;
+        let tser =
+            token_set(matcher, tokens, intersection, combined, p1, p2, false) as f64 * UNBASE_SCALE;
+        utils::round_to_u8(
+            [base as f64, tsor, tser]
+                .iter()
+                .cloned()
+                .fold(f64::NAN, f64::max),
+        )
+    }
+}
+
+/// Buffers used to find the matching blocks of two strings.
+#[derive(Default, Debug)]
+struct Matcher {
+    chars: [Vec<char>; 2],
+    queue: Vec<(usize, usize, usize, usize)>,
+    blocks: Vec<(usize, usize, usize)>,
+    window_blocks: Vec<(usize, usize, usize)>,
+    rows: [Vec<usize>; 2],
+}
+
+impl Matcher {
+    fn ratio(&mut self, a: &str, b: &str) -> u8 {
+        let Matcher {
+            chars: [chars_a, chars_b],
+            queue,
+            blocks,
+            rows,
+            ..
+        } = self;
+        if a.is_ascii() && b.is_ascii() {
+            // Every char is a byte, so the fast path can work on the bytes directly.
+            return ratio_of(
+                a.as_bytes(),
+                b.as_bytes(),
+                ascii::find_longest_match,
+                queue,
+                blocks,
+            );
+        }
+        load_chars(a, chars_a);
+        load_chars(b, chars_b);
+        ratio_of(
+            chars_a,
+            chars_b,
+            |a: &[char], b: &[char], low1, high1, low2, high2| {
+                longest_match(a, b, low1, high1, low2, high2, rows)
+            },
+            queue,
+            blocks,
+        )
+    }
+
+    fn partial_ratio(&mut self, s1: &str, s2: &str) -> u8 {
+        check_trivial!(s1, s2);
+        let Matcher {
+            chars: [chars_a, chars_b],
+            queue,
+            blocks,
+            window_blocks,
+            rows,
+        } = self;
+        if s1.is_ascii() && s2.is_ascii() {
+            let (shorter, longer) = if s1.len() <= s2.len() {
+                (s1, s2)
+            } else {
+                (s2, s1)
+            };
+            return partial_ratio_of(
+                shorter.as_bytes(),
+                longer.as_bytes(),
+                ascii::find_longest_match,
+                queue,
+                blocks,
+                window_blocks,
+            );
+        }
+        load_chars(s1, chars_a);
+        load_chars(s2, chars_b);
+        let (shorter, longer) = if chars_a.len() <= chars_b.len() {
+            (chars_a, chars_b)
+        } else {
+            (chars_b, chars_a)
+        };
+        partial_ratio_of(
+            shorter,
+            longer,
+            |a: &[char], b: &[char], low1, high1, low2, high2| {
+                longest_match(a, b, low1, high1, low2, high2, rows)
+            },
+            queue,
+            blocks,
+            window_blocks,
+        )
+    }
+}
+
+fn load_chars(s: &str, buffer: &mut Vec<char>) {
+    buffer.clear();
+    buffer.extend(s.chars());
+}
+
+/// [ratio] over `a` and `b`, finding matching blocks with `find_longest_match`.
+fn ratio_of<T, F>(
+    a: &[T],
+    b: &[T],
+    find_longest_match: F,
+    queue: &mut Vec<(usize, usize, usize, usize)>,
+    blocks: &mut Vec<(usize, usize, usize)>,
+) -> u8
+where
+    T: Eq,
+    F: FnMut(&[T], &[T], usize, usize, usize, usize) -> MatchingStreak,
+{
+    check_trivial!(a, b);
+    let (shorter, longer) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    primitives::matching_blocks_into(shorter, longer, find_longest_match, queue, blocks);
+    let matches: usize = blocks.iter().map(|&(_, _, s)| s).sum();
+    let sumlength: f32 = (a.len() + b.len()) as f32;
+    utils::round_to_u8(f64::from(100.0 * (2.0 * (matches as f32) / sumlength)))
+}
+
+/// [partial_ratio] over `shorter` and `longer`, finding matching blocks with `find_longest_match`.
+fn partial_ratio_of<T, F>(
+    shorter: &[T],
+    longer: &[T],
+    mut find_longest_match: F,
+    queue: &mut Vec<(usize, usize, usize, usize)>,
+    blocks: &mut Vec<(usize, usize, usize)>,
+    window_blocks: &mut Vec<(usize, usize, usize)>,
+) -> u8
+where
+    T: Eq,
+    F: FnMut(&[T], &[T], usize, usize, usize, usize) -> MatchingStreak,
+{
+    primitives::matching_blocks_into(shorter, longer, &mut find_longest_match, queue, blocks);
+    // The final trivial block of `get_matching_blocks` is a candidate window too.
+    blocks.push((shorter.len(), longer.len(), 0));
+    // Adjacent blocks share a diagonal, so collapsing them (as `get_matching_blocks` does)
+    // would only remove duplicate windows. They are not collapsed here.
+    let mut max: u8 = 0;
+    for &(i, j, _) in blocks.iter() {
+        let long_start = j.saturating_sub(i);
+        let long_end = core::cmp::min(long_start + shorter.len(), longer.len());
+        let r = ratio_of(
+            shorter,
+            &longer[long_start..long_end],
+            &mut find_longest_match,
+            queue,
+            window_blocks,
+        );
+        if r > 99 {
+            return 100;
+        } else if r > max {
+            max = r;
+        }
+    }
+    max
+}
+
+/// Finds the longest matching streak of `a[low1..high1]` in `b[low2..high2]`.
+///
+/// Like [primitives::find_longest_match], the earliest streak in `a` wins, then the earliest
+/// in `b`. This uses the dynamic programming formulation from difflib: `row[j]` is the
+/// length of the streak ending just before `b[j]`.
+#[allow(clippy::needless_range_loop)]
+fn longest_match(
+    a: &[char],
+    b: &[char],
+    low1: usize,
+    high1: usize,
+    low2: usize,
+    high2: usize,
+    rows: &mut [Vec<usize>; 2],
+) -> MatchingStreak {
+    let [row, next_row] = rows;
+    let width = high2 - low2 + 1;
+    row.clear();
+    row.resize(width, 0);
+    next_row.clear();
+    next_row.resize(width, 0);
+    let mut best = MatchingStreak {
+        idx1: low1,
+        idx2: low2,
+        size: 0,
+    };
+    for i in low1..high1 {
+        for j in low2..high2 {
+            let idx = j - low2 + 1;
+            if a[i] == b[j] {
+                let k = row[idx - 1] + 1;
+                next_row[idx] = k;
+                // Strictly greater, so ties keep the streak ending (and so starting) earliest.
+                if k > best.size {
+                    best = MatchingStreak {
+                        idx1: i + 1 - k,
+                        idx2: j + 1 - k,
+                        size: k,
+                    };
+                }
+            } else {
+                next_row[idx] = 0;
+            }
+        }
+        core::mem::swap(row, next_row);
+    }
+    best
+}
+
+fn copy_or_process(s: &str, force_ascii: bool, full_process: bool, out: &mut String) {
+    if full_process {
+        utils::full_process_into(s, force_ascii, out);
+    } else {
+        out.clear();
+        out.push_str(s);
     }
+}
+
+/// Collects the byte ranges of the whitespace separated tokens of `s`, sorted by token.
+fn sorted_tokens(s: &str, tokens: &mut Vec<(usize, usize)>) {
+    tokens.clear();
+    let base = s.as_ptr() as usize;
+    tokens.extend(s.split_whitespace().map(|token| {
+        let start = token.as_ptr() as usize - base;
+        (start, start + token.len())
+    }));
+    tokens.sort_unstable_by(|&(s1, e1), &(s2, e2)| s[s1..e1].cmp(&s[s2..e2]));
+}
 
-    if try_partial {
-        let partial = partial_ratio(p1r, p2r) as f64 * partial_scale;
-        let ptsor =
-            partial_token_sort_ratio(p1r, p2r, true, false) as f64 * UNBASE_SCALE * partial_scale;
-        let ptser =
-            partial_token_set_ratio(p1r, p2r, true, false) as f64 * UNBASE_SCALE * partial_scale;
-        // This conversion to u8 from the maximum f64 seems spooky, but let's hope nothing bad happens!
-        return vec![base as f64, partial, ptsor, ptser]
-            .iter()
-            .cloned()
-            .fold(f64::NAN, f64::max)
-            .round() as u8;
+/// Appends `token` to `out`, separated by a space.
+fn push_token(out: &mut String, token: &str) {
+    if !out.is_empty() {
+        out.push(' ');
     }
-    let tsor = token_sort_ratio(p1r, p2r, true, false) as f64 * UNBASE_SCALE;
-    let tser = token_set_ratio(p1r, p2r, true, false) as f64 * UNBASE_SCALE;
-    vec![base as f64, tsor, tser]
+    out.push_str(token);
+}
+
+fn token_sort(
+    matcher: &mut Matcher,
+    sorted: &mut [String; 2],
+    tokens: &mut [Vec<(usize, usize)>; 2],
+    p1: &str,
+    p2: &str,
+    partial: bool,
+) -> u8 {
+    for (s, (tokens, sorted)) in [p1, p2]
         .iter()
-        .cloned()
-        .fold(f64::NAN, f64::max)
-        .round() as u8
+        .zip(tokens.iter_mut().zip(sorted.iter_mut()))
+    {
+        sorted_tokens(s, tokens);
+        sorted.clear();
+        for &(start, end) in tokens.iter() {
+            push_token(sorted, &s[start..end]);
+        }
+    }
+    if partial {
+        matcher.partial_ratio(&sorted[0], &sorted[1])
+    } else {
+        matcher.ratio(&sorted[0], &sorted[1])
+    }
 }
 
-/// Runs `wratio` without forcing to ascii.
-pub fn uwratio(s1: &str, s2: &str, full_process: bool) -> u8 {
-    // trivial check omitted because this is a shallow delegator to wratio which checks.
-    wratio(s1, s2, false, full_process)
+fn token_set(
+    matcher: &mut Matcher,
+    tokens: &mut [Vec<(usize, usize)>; 2],
+    intersection: &mut String,
+    combined: &mut [String; 2],
+    p1: &str,
+    p2: &str,
+    partial: bool,
+) -> u8 {
+    let [tokens1, tokens2] = tokens;
+    sorted_tokens(p1, tokens1);
+    sorted_tokens(p2, tokens2);
+    tokens1.dedup_by(|&mut (s1, e1), &mut (s2, e2)| p1[s1..e1] == p1[s2..e2]);
+    tokens2.dedup_by(|&mut (s1, e1), &mut (s2, e2)| p2[s1..e1] == p2[s2..e2]);
+    // Merge the sorted token sets: common tokens form the intersection, the
+    // remaining ones are appended to the combined string of their side.
+    let [combined1, combined2] = combined;
+    intersection.clear();
+    combined1.clear();
+    combined2.clear();
+    let (mut x, mut y) = (0, 0);
+    while x < tokens1.len() || y < tokens2.len() {
+        let t1 = tokens1.get(x).map(|&(start, end)| &p1[start..end]);
+        let t2 = tokens2.get(y).map(|&(start, end)| &p2[start..end]);
+        match (t1, t2) {
+            (Some(t1), Some(t2)) if t1 == t2 => {
+                push_token(intersection, t1);
+                x += 1;
+                y += 1;
+            }
+            (Some(t1), Some(t2)) if t1 < t2 => {
+                push_token(combined1, t1);
+                x += 1;
+            }
+            (Some(t1), None) => {
+                push_token(combined1, t1);
+                x += 1;
+            }
+            (_, Some(t2)) => {
+                push_token(combined2, t2);
+                y += 1;
+            }
+            (None, None) => unreachable!(),
+        }
+    }
+    prepend_intersection(intersection, combined1);
+    prepend_intersection(intersection, combined2);
+    let scores = if partial {
+        [
+            matcher.partial_ratio(intersection, combined1),
+            matcher.partial_ratio(intersection, combined2),
+            matcher.partial_ratio(combined1, combined2),
+        ]
+    } else {
+        [
+            matcher.ratio(intersection, combined1),
+            matcher.ratio(intersection, combined2),
+            matcher.ratio(combined1, combined2),
+        ]
+    };
+    *scores.iter().max().unwrap()
+}
+
+/// Turns `<sorted_remainder>` into `<sorted_intersection> <sorted_remainder>` in place.
+fn prepend_intersection(intersection: &str, remainder: &mut String) {
+    if !remainder.is_empty() {
+        remainder.insert(0, ' ');
+    }
+    remainder.insert_str(0, intersection);
 }
 
 #[cfg(test)]
 mod test {
-    use super::ratio;
+    use super::{partial_ratio, partial_token_set_ratio, ratio, token_set_ratio, token_sort_ratio};
+    use crate::{primitives, utils};
+    use rand::{Rng, SeedableRng};
     #[test]
     fn ratio_unicode() {
         let list = [
@@ -405,4 +776,42 @@ mod test {
             assert_eq!(ratio(a, b), *r);
         }
     }
+
+    #[test]
+    fn token_ratios_check_the_unprocessed_strings() {
+        // Only the unprocessed strings are checked for being equal or empty, so two
+        // strings with no tokens are not a trivial mismatch.
+        assert_eq!(token_set_ratio(" ", "c  ", false, true), 100);
+        assert_eq!(partial_token_set_ratio("bc!aaB c", "' ", true, true), 100);
+        assert_eq!(token_sort_ratio("!", "?", true, true), 100);
+        assert_eq!(token_sort_ratio("!", "?", true, false), 0);
+    }
+
+    #[test]
+    fn ratios_agree_with_matching_blocks() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(27);
+        for alphabet in [&['a', 'b', 'c', ' '][..], &['a', 'b', 'é', 'ß', ' ']].iter() {
+            for _ in 0..300 {
+                let mut random_string = |len: usize| -> String {
+                    (0..len)
+                        .map(|_| alphabet[rng.gen_range(0..alphabet.len())])
+                        .collect()
+                };
+                let (a, b) = (random_string(10), random_string(6));
+                let (ca, cb): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+                assert_eq!(ratio(&a, &b), primitives::ratio(&ca, &cb), "{} {}", a, b);
+                // The windows of the shorter string over the longer one, at each matching block.
+                let expected = utils::get_matching_blocks(&b, &a)
+                    .into_iter()
+                    .map(|(i, j, _)| {
+                        let start = j.saturating_sub(i);
+                        let end = core::cmp::min(start + cb.len(), ca.len());
+                        primitives::ratio(&cb, &ca[start..end])
+                    })
+                    .max()
+                    .unwrap();
+                assert_eq!(partial_ratio(&b, &a), expected, "{} {}", b, a);
+            }
+        }
+    }
 }
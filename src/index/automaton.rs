@@ -0,0 +1,330 @@
+//! A [Levenshtein automaton](https://en.wikipedia.org/wiki/Levenshtein_automaton) recognizing the strings close to a query.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::cmp;
+
+/// An automaton accepting exactly the strings within `max_distance` edits of a query.
+///
+/// The states of the automaton are rows of the edit distance matrix between the query and
+/// the input read so far, with values capped at `max_distance + 1`. There are finitely many
+/// such rows, so this is a deterministic automaton. It is built lazily: a state or transition
+/// is computed the first time it is reached and then memoized, so stepping along a path
+/// already taken only looks up a table. Characters which do not occur in the query all share
+/// their transitions.
+///
+/// Because a state also tells whether any continuation of the input can still be accepted
+/// ([can_match](LevenshteinAutomaton::can_match)), the automaton can be run along the
+/// branches of a trie or over a sorted word list, abandoning whole subtrees at once.
+///
+/// With `transpositions`, swapping two adjacent characters counts as a single edit
+/// ([optimal string alignment distance](https://en.wikipedia.org/wiki/Damerau%E2%80%93Levenshtein_distance#Optimal_string_alignment_distance)).
+///
+/// The memoized states are behind a [RefCell], so an automaton is not shared between
+/// threads; create one per thread.
+///
+/// ```
+/// # use fuzzywuzzy::index::LevenshteinAutomaton;
+/// let automaton = LevenshteinAutomaton::new("kitten", 2, false);
+/// assert_eq!(automaton.eval("sitting"), None);
+/// assert_eq!(automaton.eval("mittens"), Some(2));
+///
+/// let mut state = automaton.start();
+/// for c in "xyz".chars() {
+///     state = automaton.step(&state, c);
+/// }
+/// // No string starting with "xyz" is within 2 edits of "kitten".
+/// assert!(!automaton.can_match(&state));
+///
+/// let automaton = LevenshteinAutomaton::new("kitten", 1, true);
+/// assert_eq!(automaton.eval("iktten"), Some(1));
+/// ```
+#[derive(Clone, Debug)]
+pub struct LevenshteinAutomaton {
+    /// The class of each char of the query: its index in `alphabet`.
+    query: Vec<usize>,
+    /// The distinct chars of the query, sorted. Every other char is in class `alphabet.len()`.
+    alphabet: Vec<char>,
+    max_distance: usize,
+    transpositions: bool,
+    dfa: RefCell<Dfa>,
+}
+
+/// The states and transitions of a [LevenshteinAutomaton] built so far.
+#[derive(Clone, Debug, Default)]
+struct Dfa {
+    /// The key of each state, `key_width` values per state: its row of distances and, with
+    /// transpositions, the row before it and the class of the last char read.
+    keys: Vec<usize>,
+    /// The state of each key.
+    ids: BTreeMap<Vec<usize>, usize>,
+    /// The distance between the query and the input of each state, capped.
+    distances: Vec<usize>,
+    /// Whether some continuation of the input of each state can be accepted.
+    live: Vec<bool>,
+    /// The next state by state and char class, or [UNKNOWN] if not built yet.
+    transitions: Vec<usize>,
+    /// The key being built by a transition.
+    next: Vec<usize>,
+}
+
+const UNKNOWN: usize = usize::MAX;
+
+/// A state of a [LevenshteinAutomaton], reached after reading some input.
+///
+/// A state is only meaningful to the automaton which returned it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct State(usize);
+
+impl LevenshteinAutomaton {
+    /// Creates an automaton for the strings within `max_distance` edits of `query`.
+    pub fn new(query: &str, max_distance: usize, transpositions: bool) -> LevenshteinAutomaton {
+        let chars: Vec<char> = query.chars().collect();
+        let mut alphabet = chars.clone();
+        alphabet.sort_unstable();
+        alphabet.dedup();
+        let automaton = LevenshteinAutomaton {
+            query: chars
+                .iter()
+                .map(|c| {
+                    alphabet
+                        .binary_search(c)
+                        .expect("every char is in the alphabet")
+                })
+                .collect(),
+            alphabet,
+            max_distance,
+            transpositions,
+            dfa: RefCell::new(Dfa::default()),
+        };
+        // The start state is state 0. Its row before is out of reach and its last char matches
+        // nothing, so no transposition ends at its first char.
+        let cap = max_distance + 1;
+        let width = chars.len() + 1;
+        {
+            let mut dfa = automaton.dfa.borrow_mut();
+            let mut key: Vec<usize> = (0..width).map(|i| cmp::min(i, cap)).collect();
+            if transpositions {
+                key.extend(core::iter::repeat(cap).take(width));
+                key.push(automaton.alphabet.len());
+            }
+            dfa.next = key;
+            automaton.add_state(&mut dfa);
+        }
+        automaton
+    }
+
+    /// The largest distance accepted.
+    pub fn max_distance(&self) -> usize {
+        self.max_distance
+    }
+
+    /// Whether adjacent transpositions count as a single edit.
+    pub fn transpositions(&self) -> bool {
+        self.transpositions
+    }
+
+    /// The length of the query in chars.
+    pub(crate) fn query_len(&self) -> usize {
+        self.query.len()
+    }
+
+    /// The state before reading any input.
+    pub fn start(&self) -> State {
+        State(0)
+    }
+
+    /// The state reached by reading `c` in `state`.
+    pub fn step(&self, state: &State, c: char) -> State {
+        let class = self
+            .alphabet
+            .binary_search(&c)
+            .unwrap_or(self.alphabet.len());
+        let at = state.0 * (self.alphabet.len() + 1) + class;
+        let known = self.dfa.borrow().transitions[at];
+        if known != UNKNOWN {
+            return State(known);
+        }
+        let mut dfa = self.dfa.borrow_mut();
+        self.transition(&mut dfa, state.0, class);
+        let next = self.add_state(&mut dfa);
+        dfa.transitions[at] = next;
+        State(next)
+    }
+
+    /// Builds in `dfa.next` the key of the state reached by reading a char of `class` in `state`.
+    fn transition(&self, dfa: &mut Dfa, state: usize, class: usize) {
+        let cap = self.max_distance + 1;
+        let width = self.query.len() + 1;
+        let key_width = self.key_width();
+        let Dfa { keys, next, .. } = dfa;
+        let key = &keys[state * key_width..(state + 1) * key_width];
+        let row = &key[..width];
+        next.clear();
+        next.push(cmp::min(row[0] + 1, cap));
+        for i in 1..width {
+            let substitution = row[i - 1] + usize::from(self.query[i - 1] != class);
+            let mut distance = cmp::min(cmp::min(next[i - 1], row[i]) + 1, substitution);
+            if self.transpositions {
+                let (before, last) = (&key[width..2 * width], key[2 * width]);
+                if i > 1 && self.query[i - 1] == last && self.query[i - 2] == class {
+                    distance = cmp::min(distance, before[i - 2] + 1);
+                }
+            }
+            next.push(cmp::min(distance, cap));
+        }
+        if self.transpositions {
+            next.extend_from_slice(row);
+            next.push(class);
+        }
+    }
+
+    /// Returns the state whose key is `dfa.next`, adding it if it is new.
+    fn add_state(&self, dfa: &mut Dfa) -> usize {
+        if let Some(&id) = dfa.ids.get(&dfa.next[..]) {
+            return id;
+        }
+        let id = dfa.distances.len();
+        let row = &dfa.next[..self.query.len() + 1];
+        dfa.distances.push(row[self.query.len()]);
+        // Distances along a column never decrease by reading more input. A transposition
+        // reads the row before, but it costs no less than substituting into this one.
+        dfa.live.push(row.iter().any(|&d| d <= self.max_distance));
+        dfa.keys.extend_from_slice(&dfa.next);
+        dfa.ids.insert(dfa.next.clone(), id);
+        let classes = self.alphabet.len() + 1;
+        dfa.transitions
+            .extend(core::iter::repeat(UNKNOWN).take(classes));
+        id
+    }
+
+    /// The number of values in the key of a state.
+    fn key_width(&self) -> usize {
+        if self.transpositions {
+            2 * (self.query.len() + 1) + 1
+        } else {
+            self.query.len() + 1
+        }
+    }
+
+    /// Whether the input read to reach `state` is accepted.
+    pub fn is_match(&self, state: &State) -> bool {
+        self.distance(state).is_some()
+    }
+
+    /// The distance between the query and the input read to reach `state`, if accepted.
+    pub fn distance(&self, state: &State) -> Option<usize> {
+        let distance = self.dfa.borrow().distances[state.0];
+        if distance <= self.max_distance {
+            Some(distance)
+        } else {
+            None
+        }
+    }
+
+    /// Whether some continuation of the input read to reach `state` can be accepted.
+    pub fn can_match(&self, state: &State) -> bool {
+        self.dfa.borrow().live[state.0]
+    }
+
+    /// Runs the automaton over `s`, returning its distance to the query if accepted.
+    pub fn eval(&self, s: &str) -> Option<usize> {
+        let mut state = self.start();
+        for c in s.chars() {
+            if !self.can_match(&state) {
+                return None;
+            }
+            state = self.step(&state, c);
+        }
+        self.distance(&state)
+    }
+
+    /// The number of states built so far.
+    #[cfg(test)]
+    fn states(&self) -> usize {
+        self.dfa.borrow().distances.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::primitives;
+    use rand::{Rng, SeedableRng};
+
+    /// Optimal string alignment distance, by the full matrix.
+    fn osa(a: &[char], b: &[char]) -> usize {
+        let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+        for i in 0..=a.len() {
+            for j in 0..=b.len() {
+                d[i][j] = if i == 0 || j == 0 {
+                    i + j
+                } else {
+                    let mut best = cmp::min(
+                        cmp::min(d[i - 1][j], d[i][j - 1]) + 1,
+                        d[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]),
+                    );
+                    if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                        best = cmp::min(best, d[i - 2][j - 2] + 1);
+                    }
+                    best
+                };
+            }
+        }
+        d[a.len()][b.len()]
+    }
+
+    #[test]
+    fn eval_agrees_with_distance_matrix() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(34);
+        let mut word = || -> Vec<char> {
+            let len = rng.gen_range(0..8);
+            (0..len)
+                .map(|_| ['a', 'b', 'c', 'π'][rng.gen_range(0..4)])
+                .collect()
+        };
+        for _ in 0..2000 {
+            let (a, b) = (word(), word());
+            let (a_str, b_str): (alloc::string::String, alloc::string::String) =
+                (a.iter().collect(), b.iter().collect());
+            for max_distance in 0..4 {
+                let levenshtein = primitives::levenshtein(&a, &b);
+                let expected = Some(levenshtein).filter(|&d| d <= max_distance);
+                let automaton = LevenshteinAutomaton::new(&a_str, max_distance, false);
+                assert_eq!(automaton.eval(&b_str), expected, "{:?} {:?}", a_str, b_str);
+                let expected = Some(osa(&a, &b)).filter(|&d| d <= max_distance);
+                let automaton = LevenshteinAutomaton::new(&a_str, max_distance, true);
+                assert_eq!(automaton.eval(&b_str), expected, "{:?} {:?}", a_str, b_str);
+            }
+        }
+    }
+
+    #[test]
+    fn states_are_memoized() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(35);
+        let words: Vec<alloc::string::String> = (0..500)
+            .map(|_| {
+                let len = rng.gen_range(0..8);
+                (0..len)
+                    .map(|_| ['k', 'i', 't', 'e', 'n', 'x', 'y'][rng.gen_range(0..7)])
+                    .collect()
+            })
+            .collect();
+        for &transpositions in [false, true].iter() {
+            let automaton = LevenshteinAutomaton::new("kitten", 2, transpositions);
+            // Chars not in the query share their transitions.
+            let start = automaton.start();
+            assert_eq!(automaton.step(&start, 'x'), automaton.step(&start, 'y'));
+            let found: Vec<Option<usize>> = words.iter().map(|w| automaton.eval(w)).collect();
+            let states = automaton.states();
+            // The same inputs again only follow memoized transitions.
+            for (word, &distance) in words.iter().zip(found.iter()) {
+                assert_eq!(automaton.eval(word), distance);
+                let fresh = LevenshteinAutomaton::new("kitten", 2, transpositions);
+                assert_eq!(fresh.eval(word), distance, "{:?}", word);
+            }
+            assert_eq!(automaton.states(), states);
+        }
+    }
+}
@@ -0,0 +1,442 @@
+//! A [BK-tree](https://en.wikipedia.org/wiki/BK-tree) for nearest neighbour search by edit distance.
+
+use super::{EntryId, Metric, Neighbor};
+use crate::persist::{Decoder, Encoder, Persist, PersistError};
+use alloc::collections::BinaryHeap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// A metric tree over strings, keyed by an integer edit distance.
+///
+/// Every node stores its children by their distance to it. By the triangle inequality,
+/// a search for entries within distance `k` of a query at distance `d` of a node only
+/// needs to descend into the children at distances `d - k ..= d + k`, which prunes most
+/// of the tree for small `k`.
+///
+/// ```
+/// # use fuzzywuzzy::index::{BkTree, Metric};
+/// let mut tree = BkTree::new(Metric::Levenshtein);
+/// for word in ["book", "books", "cake", "boo", "cape", "cart", "boon", "cook"].iter() {
+///     tree.insert(word);
+/// }
+/// let found: Vec<(&str, usize)> = tree
+///     .find_within("bo", 1)
+///     .into_iter()
+///     .map(|n| (n.choice, n.distance))
+///     .collect();
+/// assert_eq!(found, vec![("boo", 1)]);
+///
+/// let nearest = tree.find_nearest("cooks", 2);
+/// assert_eq!(nearest[0].choice, "books");
+/// assert_eq!(nearest[0].distance, 1);
+/// assert_eq!(nearest[0].score, 80);
+/// assert_eq!(nearest[1].choice, "cook");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct BkTree {
+    metric: Metric,
+    nodes: Vec<Node>,
+    /// The node of each entry, or `None` once removed.
+    node_of: Vec<Option<usize>>,
+    live: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    id: EntryId,
+    choice: String,
+    /// Removed nodes are kept to route searches to their children, until the tree is compacted.
+    removed: bool,
+    /// `(distance, node)` pairs.
+    children: Vec<(usize, usize)>,
+}
+
+impl BkTree {
+    /// Creates an empty tree using the given metric.
+    pub fn new(metric: Metric) -> BkTree {
+        BkTree {
+            metric,
+            nodes: Vec::new(),
+            node_of: Vec::new(),
+            live: 0,
+        }
+    }
+
+    /// Builds a tree from a collection of choices, with IDs assigned in order.
+    pub fn from_choices<I, T>(metric: Metric, choices: I) -> BkTree
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let mut tree = BkTree::new(metric);
+        for choice in choices {
+            tree.insert(choice.as_ref());
+        }
+        tree
+    }
+
+    /// The metric the tree is keyed by.
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// The number of entries in the tree.
+    pub fn len(&self) -> usize {
+        self.live
+    }
+
+    /// Whether the tree has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.live == 0
+    }
+
+    /// Returns the entry with the given ID, unless it was removed.
+    pub fn get(&self, id: EntryId) -> Option<&str> {
+        let node = (*self.node_of.get(id)?)?;
+        Some(&self.nodes[node].choice)
+    }
+
+    /// Adds a choice to the tree and returns its ID. Duplicate choices get distinct IDs.
+    pub fn insert(&mut self, choice: &str) -> EntryId {
+        let id = self.node_of.len();
+        self.node_of.push(None);
+        self.insert_node(id, choice);
+        id
+    }
+
+    /// Removes the entry with the given ID from the tree, returning it.
+    ///
+    /// The node is only marked as removed, so searches keep going through it; the space and
+    /// search time it takes are reclaimed by [compact](BkTree::compact). IDs are never reused.
+    pub fn remove(&mut self, id: EntryId) -> Option<String> {
+        let node = self.node_of.get_mut(id)?.take()?;
+        self.nodes[node].removed = true;
+        self.live -= 1;
+        Some(self.nodes[node].choice.clone())
+    }
+
+    /// Replaces the entry with the given ID, keeping its ID, and returns the previous choice.
+    ///
+    /// Returns `None`, leaving the tree unchanged, if there is no such entry.
+    pub fn update(&mut self, id: EntryId, choice: &str) -> Option<String> {
+        let previous = self.remove(id)?;
+        self.insert_node(id, choice);
+        Some(previous)
+    }
+
+    /// Rebuilds the tree from its live entries, dropping the nodes of removed or updated entries.
+    ///
+    /// IDs are unchanged.
+    pub fn compact(&mut self) {
+        let mut live: Vec<Node> = self.nodes.drain(..).filter(|node| !node.removed).collect();
+        live.sort_unstable_by_key(|node| node.id);
+        self.live = 0;
+        for node in live {
+            self.insert_node(node.id, &node.choice);
+        }
+        self.nodes.shrink_to_fit();
+    }
+
+    fn insert_node(&mut self, id: EntryId, choice: &str) {
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            id,
+            choice: choice.to_string(),
+            removed: false,
+            children: Vec::new(),
+        });
+        self.node_of[id] = Some(node);
+        self.live += 1;
+        if node == 0 {
+            return;
+        }
+        let mut current = 0;
+        loop {
+            let distance = self.metric.distance(&self.nodes[current].choice, choice);
+            let children = &self.nodes[current].children;
+            match children.iter().find(|&&(d, _)| d == distance) {
+                Some(&(_, child)) => current = child,
+                None => {
+                    self.nodes[current].children.push((distance, node));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Returns every entry within `max_distance` of `query`, closest first (ties by ID).
+    pub fn find_within(&self, query: &str, max_distance: usize) -> Vec<Neighbor<'_>> {
+        let query_len = query.chars().count();
+        let mut found = Vec::new();
+        if self.nodes.is_empty() {
+            return found;
+        }
+        let mut stack = vec![0];
+        while let Some(current) = stack.pop() {
+            let node = &self.nodes[current];
+            let distance = self.metric.distance(query, &node.choice);
+            if distance <= max_distance && !node.removed {
+                found.push(Neighbor::new(node.id, &node.choice, query_len, distance));
+            }
+            let low = distance.saturating_sub(max_distance);
+            let high = distance + max_distance;
+            stack.extend(
+                node.children
+                    .iter()
+                    .filter(|&&(d, _)| low <= d && d <= high)
+                    .map(|&(_, child)| child),
+            );
+        }
+        found.sort_unstable_by_key(|n| (n.distance, n.id));
+        found
+    }
+
+    /// Returns the `k` entries closest to `query`, closest first (ties by ID).
+    ///
+    /// The search radius starts unbounded and shrinks to the distance of the `k`-th closest
+    /// entry found so far.
+    pub fn find_nearest(&self, query: &str, k: usize) -> Vec<Neighbor<'_>> {
+        let query_len = query.chars().count();
+        if self.nodes.is_empty() || k == 0 {
+            return Vec::new();
+        }
+        // A max-heap of the best `(distance, id)` pairs found so far.
+        let mut best: BinaryHeap<(usize, EntryId)> = BinaryHeap::with_capacity(k + 1);
+        let mut stack = vec![0];
+        while let Some(current) = stack.pop() {
+            let node = &self.nodes[current];
+            let distance = self.metric.distance(query, &node.choice);
+            if !node.removed {
+                best.push((distance, node.id));
+                if best.len() > k {
+                    best.pop();
+                }
+            }
+            let radius = if best.len() == k {
+                best.peek().map(|&(d, _)| d).unwrap_or(usize::MAX)
+            } else {
+                usize::MAX
+            };
+            let low = distance.saturating_sub(radius);
+            let high = distance.saturating_add(radius);
+            stack.extend(
+                node.children
+                    .iter()
+                    .filter(|&&(d, _)| low <= d && d <= high)
+                    .map(|&(_, child)| child),
+            );
+        }
+        let mut found: Vec<Neighbor> = best
+            .into_iter()
+            .map(|(distance, id)| {
+                let node = &self.nodes[self.node_of[id].expect("found entries are live")];
+                Neighbor::new(id, &node.choice, query_len, distance)
+            })
+            .collect();
+        found.sort_unstable_by_key(|n| (n.distance, n.id));
+        found
+    }
+}
+
+impl Persist for BkTree {
+    const KIND: &'static str = "BkTree";
+    type Context = ();
+
+    fn encode(&self, encoder: &mut Encoder) {
+        self.metric.encode(encoder);
+        encoder.put_usize(self.node_of.len());
+        for &node in self.node_of.iter() {
+            encoder.put_option_usize(node);
+        }
+        encoder.put_usize(self.nodes.len());
+        for node in self.nodes.iter() {
+            encoder.put_usize(node.id);
+            encoder.put_str(&node.choice);
+            encoder.put_bool(node.removed);
+            encoder.put_usize(node.children.len());
+            for &(distance, child) in node.children.iter() {
+                encoder.put_usize(distance);
+                encoder.put_usize(child);
+            }
+        }
+    }
+
+    fn decode(decoder: &mut Decoder<'_>, _: ()) -> Result<Self, PersistError> {
+        let metric = Metric::decode(decoder)?;
+        let node_of = (0..decoder.get_len()?)
+            .map(|_| decoder.get_option_usize())
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut nodes = Vec::new();
+        for _ in 0..decoder.get_len()? {
+            let id = decoder.get_usize()?;
+            let choice = decoder.get_string()?;
+            let removed = decoder.get_bool()?;
+            let children = (0..decoder.get_len()?)
+                .map(|_| Ok((decoder.get_usize()?, decoder.get_usize()?)))
+                .collect::<Result<Vec<_>, PersistError>>()?;
+            nodes.push(Node {
+                id,
+                choice,
+                removed,
+                children,
+            });
+        }
+        // Children come after their parent and every node but the root is the child of
+        // exactly one node, so the nodes form a single tree and searches terminate.
+        let mut parents = vec![0; nodes.len()];
+        let mut ordered = true;
+        for (parent, node) in nodes.iter().enumerate() {
+            for &(_, child) in node.children.iter() {
+                if parent < child && child < nodes.len() {
+                    parents[child] += 1;
+                } else {
+                    ordered = false;
+                }
+            }
+        }
+        let valid = ordered
+            && parents
+                .iter()
+                .enumerate()
+                .all(|(node, &count)| count == usize::from(node != 0))
+            && nodes.iter().all(|node| node.id < node_of.len())
+            && node_of.iter().enumerate().all(|(id, node)| {
+                node.map_or(true, |node| node < nodes.len() && nodes[node].id == id)
+            });
+        decoder.check(valid, "invalid BK-tree node")?;
+        let live = node_of.iter().flatten().count();
+        Ok(BkTree {
+            metric,
+            nodes,
+            node_of,
+            live,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    fn random_words(seed: u64, count: usize) -> Vec<String> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        (0..count)
+            .map(|_| {
+                let len = rng.gen_range(1..8);
+                (0..len)
+                    .map(|_| ['a', 'b', 'c', 'd', 'é'][rng.gen_range(0..5)])
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn searches_agree_with_linear_scan() {
+        let words = random_words(31, 300);
+        let queries = random_words(131, 30);
+        for &metric in [Metric::Levenshtein, Metric::DamerauLevenshtein].iter() {
+            let tree = BkTree::from_choices(metric, &words);
+            for query in queries.iter() {
+                let mut distances: Vec<(usize, EntryId)> = words
+                    .iter()
+                    .enumerate()
+                    .map(|(id, w)| (metric.distance(query, w), id))
+                    .collect();
+                distances.sort_unstable();
+                for k in 0..3 {
+                    let expected: Vec<(usize, EntryId)> =
+                        distances.iter().cloned().filter(|&(d, _)| d <= k).collect();
+                    let found: Vec<(usize, EntryId)> = tree
+                        .find_within(query, k)
+                        .iter()
+                        .map(|n| (n.distance, n.id))
+                        .collect();
+                    assert_eq!(found, expected);
+                }
+                // Ties at the k-th distance may be broken differently, so compare distances.
+                let nearest: Vec<usize> = tree
+                    .find_nearest(query, 5)
+                    .iter()
+                    .map(|n| n.distance)
+                    .collect();
+                let expected: Vec<usize> = distances.iter().take(5).map(|&(d, _)| d).collect();
+                assert_eq!(nearest, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn mutations_agree_with_rebuilt_tree() {
+        let words = random_words(231, 200);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(232);
+        let mut tree = BkTree::from_choices(Metric::Levenshtein, &words[..100]);
+        let mut model: Vec<Option<String>> = words[..100].iter().cloned().map(Some).collect();
+        for word in words[100..].iter() {
+            match rng.gen_range(0..4) {
+                0 => {
+                    assert_eq!(tree.insert(word), model.len());
+                    model.push(Some(word.clone()));
+                }
+                1 => {
+                    let id = rng.gen_range(0..model.len());
+                    assert_eq!(tree.remove(id), model[id].take());
+                }
+                2 => {
+                    let id = rng.gen_range(0..model.len());
+                    let previous = model[id].as_ref().map(|_| word.clone());
+                    assert_eq!(
+                        tree.update(id, word),
+                        core::mem::replace(&mut model[id], previous)
+                    );
+                }
+                _ => tree.compact(),
+            }
+            assert_eq!(tree.len(), model.iter().flatten().count());
+            for query in words[..10].iter() {
+                let mut expected: Vec<(usize, EntryId)> = model
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(id, w)| {
+                        w.as_ref()
+                            .map(|w| (Metric::Levenshtein.distance(query, w), id))
+                    })
+                    .filter(|&(d, _)| d <= 2)
+                    .collect();
+                expected.sort_unstable();
+                let found: Vec<(usize, EntryId)> = tree
+                    .find_within(query, 2)
+                    .iter()
+                    .map(|n| (n.distance, n.id))
+                    .collect();
+                assert_eq!(found, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn trees_with_cycles_are_rejected() {
+        use crate::persist::{from_bytes, to_bytes, PersistError, Pipeline};
+        let pipeline = Pipeline::current("test");
+        let tree = BkTree::from_choices(Metric::Levenshtein, &["kitten", "sitting", "mitten"]);
+        assert!(from_bytes::<BkTree>(&to_bytes(&tree, &pipeline), &pipeline, ()).is_ok());
+
+        // A child pointing back at the root.
+        let mut cyclic = tree.clone();
+        let parent = cyclic.nodes[1..]
+            .iter()
+            .position(|node| node.children.is_empty())
+            .unwrap()
+            + 1;
+        cyclic.nodes[parent].children.push((9, 0));
+        let error = from_bytes::<BkTree>(&to_bytes(&cyclic, &pipeline), &pipeline, ()).unwrap_err();
+        assert!(matches!(error, PersistError::Corrupt(_)));
+
+        // A node reached from two parents.
+        let mut shared = tree.clone();
+        let child = shared.nodes[0].children[0];
+        shared.nodes[0].children.push((child.0 + 9, child.1));
+        let error = from_bytes::<BkTree>(&to_bytes(&shared, &pipeline), &pipeline, ()).unwrap_err();
+        assert!(matches!(error, PersistError::Corrupt(_)));
+    }
+}
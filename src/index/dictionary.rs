@@ -0,0 +1,413 @@
+//! A trie of words searched with a [LevenshteinAutomaton].
+
+use super::{EntryId, LevenshteinAutomaton, Neighbor};
+use crate::persist::{Decoder, Encoder, Persist, PersistError};
+use crate::utils;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// A set of words stored in a trie, searched by intersecting the trie with a [LevenshteinAutomaton].
+///
+/// The automaton is run along the branches of the trie and a branch is abandoned as soon as
+/// no word in it can be accepted, so words sharing a prefix share the work, and most of the
+/// dictionary is never visited. Matching words are found without scoring each of them.
+///
+/// ```
+/// # use fuzzywuzzy::index::FuzzyDictionary;
+/// let dictionary = FuzzyDictionary::from_words(vec!["kitten", "sitting", "mitten", "smitten", "knitting"]);
+/// assert_eq!(
+///     dictionary.extract("kitten", 70),
+///     vec![("kitten".to_string(), 100u8), ("mitten".to_string(), 83u8), ("smitten".to_string(), 71u8)]);
+/// assert_eq!(dictionary.extract_one("knitten", 0), Some(("kitten".to_string(), 86u8)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct FuzzyDictionary {
+    nodes: Vec<Node>,
+    /// Words by ID, or `None` once removed.
+    words: Vec<Option<String>>,
+    live: usize,
+    /// Length in chars of the longest word.
+    longest: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Node {
+    /// `(char, node)` pairs, sorted by char.
+    children: Vec<(char, usize)>,
+    /// The word ending at this node.
+    word: Option<EntryId>,
+}
+
+impl Default for FuzzyDictionary {
+    fn default() -> Self {
+        FuzzyDictionary::new()
+    }
+}
+
+impl FuzzyDictionary {
+    /// Creates an empty dictionary.
+    pub fn new() -> FuzzyDictionary {
+        FuzzyDictionary {
+            nodes: vec![Node::default()],
+            words: Vec::new(),
+            live: 0,
+            longest: 0,
+        }
+    }
+
+    /// Builds a dictionary from a collection of words, with IDs assigned in order.
+    pub fn from_words<I, T>(words: I) -> FuzzyDictionary
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let mut dictionary = FuzzyDictionary::new();
+        for word in words {
+            dictionary.insert(word.as_ref());
+        }
+        dictionary
+    }
+
+    /// The number of distinct words in the dictionary.
+    pub fn len(&self) -> usize {
+        self.live
+    }
+
+    /// Whether the dictionary has no words.
+    pub fn is_empty(&self) -> bool {
+        self.live == 0
+    }
+
+    /// One more than the largest ID assigned so far.
+    pub(crate) fn id_bound(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Returns the word with the given ID, unless it was removed.
+    pub fn get(&self, id: EntryId) -> Option<&str> {
+        self.words.get(id)?.as_deref()
+    }
+
+    /// Adds a word to the dictionary and returns its ID. Adding a word again returns its existing ID.
+    pub fn insert(&mut self, word: &str) -> EntryId {
+        let node = self.node_for(word);
+        if let Some(id) = self.nodes[node].word {
+            return id;
+        }
+        let id = self.words.len();
+        self.words.push(Some(word.to_string()));
+        self.nodes[node].word = Some(id);
+        self.live += 1;
+        id
+    }
+
+    /// Removes the word with the given ID from the dictionary, returning it.
+    ///
+    /// The word is unreachable right away, but its branch of the trie is only pruned by
+    /// [compact](FuzzyDictionary::compact). IDs are never reused.
+    pub fn remove(&mut self, id: EntryId) -> Option<String> {
+        let word = self.words.get_mut(id)?.take()?;
+        let node = self.node_for(&word);
+        self.nodes[node].word = None;
+        self.live -= 1;
+        Some(word)
+    }
+
+    /// Replaces the word with the given ID, keeping its ID, and returns the previous word.
+    ///
+    /// Returns `None`, leaving the dictionary unchanged, if there is no such word, or if the
+    /// new word is already in the dictionary under another ID.
+    pub fn update(&mut self, id: EntryId, word: &str) -> Option<String> {
+        self.get(id)?;
+        let node = self.node_for(word);
+        match self.nodes[node].word {
+            Some(existing) if existing == id => return Some(word.to_string()),
+            Some(_) => return None,
+            None => {}
+        }
+        let previous = self.remove(id)?;
+        self.nodes[node].word = Some(id);
+        self.words[id] = Some(word.to_string());
+        self.live += 1;
+        Some(previous)
+    }
+
+    /// Rebuilds the trie from the words still in the dictionary, pruning the branches of removed words.
+    ///
+    /// IDs are unchanged.
+    pub fn compact(&mut self) {
+        self.nodes = vec![Node::default()];
+        self.longest = 0;
+        for id in 0..self.words.len() {
+            if let Some(word) = self.words[id].take() {
+                let node = self.node_for(&word);
+                self.nodes[node].word = Some(id);
+                self.words[id] = Some(word);
+            }
+        }
+        self.nodes.shrink_to_fit();
+    }
+
+    /// Returns the node for `word`, adding the missing nodes on its path.
+    fn node_for(&mut self, word: &str) -> usize {
+        let mut current = 0;
+        let mut length = 0;
+        for c in word.chars() {
+            length += 1;
+            current = match self.nodes[current]
+                .children
+                .binary_search_by_key(&c, |&(c, _)| c)
+            {
+                Ok(at) => self.nodes[current].children[at].1,
+                Err(at) => {
+                    let child = self.nodes.len();
+                    self.nodes.push(Node::default());
+                    self.nodes[current].children.insert(at, (c, child));
+                    child
+                }
+            };
+        }
+        self.longest = core::cmp::max(self.longest, length);
+        current
+    }
+
+    /// Returns every word accepted by `automaton`, in lexicographic order of their chars.
+    pub fn search(&self, automaton: &LevenshteinAutomaton) -> Vec<Neighbor<'_>> {
+        Trie::search(self, automaton)
+    }
+
+    /// Returns every word within `max_distance` Levenshtein edits of `query`, closest first (ties by ID).
+    pub fn find_within(&self, query: &str, max_distance: usize) -> Vec<Neighbor<'_>> {
+        Trie::find_within(self, query, max_distance)
+    }
+
+    /// Returns the words whose [levenshtein_ratio](crate::fuzz::levenshtein_ratio) with `query`
+    /// meets `score_cutoff`, in lexicographic order.
+    ///
+    /// The results are those of [process::extract_levenshtein](crate::process::extract_levenshtein)
+    /// over the words, with no processing. The cutoff is turned into the largest distance any
+    /// word could be at and still meet it, and the automaton for that distance is run over the trie.
+    pub fn extract(&self, query: &str, score_cutoff: u8) -> Vec<(String, u8)> {
+        Trie::extract(self, query, score_cutoff)
+    }
+
+    /// Returns the word with the best [levenshtein_ratio](crate::fuzz::levenshtein_ratio) with
+    /// `query`, if it meets `score_cutoff`. Ties go to the first word in lexicographic order.
+    pub fn extract_one(&self, query: &str, score_cutoff: u8) -> Option<(String, u8)> {
+        Trie::extract_one(self, query, score_cutoff)
+    }
+}
+
+/// The searches shared by the in-memory and [mapped](super::MappedDictionary) dictionaries,
+/// over a trie whose root is node 0.
+pub(crate) trait Trie {
+    /// The number of children of `node`.
+    fn child_count(&self, node: usize) -> usize;
+
+    /// The `index`th `(char, child)` pair of `node`, sorted by char.
+    fn child(&self, node: usize, index: usize) -> (char, usize);
+
+    /// The word ending at `node`, if any.
+    fn word(&self, node: usize) -> Option<(EntryId, &str)>;
+
+    /// The length in chars of the longest word.
+    fn longest(&self) -> usize;
+
+    fn search(&self, automaton: &LevenshteinAutomaton) -> Vec<Neighbor<'_>> {
+        let query_len = automaton.query_len();
+        let mut found = Vec::new();
+        let mut stack = vec![(0, automaton.start())];
+        while let Some((current, state)) = stack.pop() {
+            if let (Some((id, word)), Some(distance)) =
+                (self.word(current), automaton.distance(&state))
+            {
+                found.push(Neighbor::new(id, word, query_len, distance));
+            }
+            // Children are pushed in reverse so they are visited in order.
+            for index in (0..self.child_count(current)).rev() {
+                let (c, child) = self.child(current, index);
+                let next = automaton.step(&state, c);
+                if automaton.can_match(&next) {
+                    stack.push((child, next));
+                }
+            }
+        }
+        found
+    }
+
+    fn find_within(&self, query: &str, max_distance: usize) -> Vec<Neighbor<'_>> {
+        let mut found = self.search(&LevenshteinAutomaton::new(query, max_distance, false));
+        found.sort_unstable_by_key(|n| (n.distance, n.id));
+        found
+    }
+
+    fn extract(&self, query: &str, score_cutoff: u8) -> Vec<(String, u8)> {
+        let query_len = query.chars().count();
+        // Longer words allow more edits, but are also further away: stop at the longest word
+        // whose length difference alone exceeds its allowance.
+        let mut max_distance = utils::max_distance_for_ratio(query_len, score_cutoff);
+        for length in query_len + 1..=self.longest() {
+            let allowed = utils::max_distance_for_ratio(length, score_cutoff);
+            if length - query_len > allowed {
+                break;
+            }
+            max_distance = allowed;
+        }
+        self.search(&LevenshteinAutomaton::new(query, max_distance, false))
+            .into_iter()
+            .filter(|n| n.score >= score_cutoff)
+            .map(|n| (n.choice.to_string(), n.score))
+            .collect()
+    }
+
+    fn extract_one(&self, query: &str, score_cutoff: u8) -> Option<(String, u8)> {
+        self.extract(query, score_cutoff)
+            .into_iter()
+            .rev()
+            .max_by(|(_, acc_score), (_, score)| acc_score.cmp(score))
+    }
+}
+
+impl Trie for FuzzyDictionary {
+    fn child_count(&self, node: usize) -> usize {
+        self.nodes[node].children.len()
+    }
+
+    fn child(&self, node: usize, index: usize) -> (char, usize) {
+        self.nodes[node].children[index]
+    }
+
+    fn word(&self, node: usize) -> Option<(EntryId, &str)> {
+        let id = self.nodes[node].word?;
+        let word = self.words[id]
+            .as_deref()
+            .expect("words in the trie are live");
+        Some((id, word))
+    }
+
+    fn longest(&self) -> usize {
+        self.longest
+    }
+}
+
+impl Persist for FuzzyDictionary {
+    const KIND: &'static str = "FuzzyDictionary";
+    type Context = ();
+
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_usize(self.nodes.len());
+        for node in self.nodes.iter() {
+            encoder.put_option_usize(node.word);
+            encoder.put_usize(node.children.len());
+            for &(c, child) in node.children.iter() {
+                encoder.put_char(c);
+                encoder.put_usize(child);
+            }
+        }
+        encoder.put_usize(self.words.len());
+        for word in self.words.iter() {
+            encoder.put_bool(word.is_some());
+            if let Some(word) = word {
+                encoder.put_str(word);
+            }
+        }
+        encoder.put_usize(self.longest);
+    }
+
+    fn decode(decoder: &mut Decoder<'_>, _: ()) -> Result<Self, PersistError> {
+        let mut nodes = Vec::new();
+        for _ in 0..decoder.get_len()? {
+            let word = decoder.get_option_usize()?;
+            let children = (0..decoder.get_len()?)
+                .map(|_| Ok((decoder.get_char()?, decoder.get_usize()?)))
+                .collect::<Result<Vec<_>, PersistError>>()?;
+            nodes.push(Node { children, word });
+        }
+        let mut words = Vec::new();
+        for _ in 0..decoder.get_len()? {
+            words.push(if decoder.get_bool()? {
+                Some(decoder.get_string()?)
+            } else {
+                None
+            });
+        }
+        let longest = decoder.get_usize()?;
+        // Children are only checked to be in bounds and sorted: searches still terminate on a
+        // malformed trie, since the automaton rejects paths longer than `longest + max_distance`.
+        let valid = !nodes.is_empty()
+            && nodes.iter().all(|node| {
+                node.children.windows(2).all(|w| w[0].0 < w[1].0)
+                    && node
+                        .children
+                        .iter()
+                        .all(|&(_, child)| child > 0 && child < nodes.len())
+                    && node
+                        .word
+                        .map_or(true, |id| words.get(id).map_or(false, Option::is_some))
+            });
+        decoder.check(valid, "invalid trie node")?;
+        let live = words.iter().flatten().count();
+        Ok(FuzzyDictionary {
+            nodes,
+            words,
+            live,
+            longest,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::normalization::PassthroughNormalizer;
+    use crate::process::extract_levenshtein;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn extract_agrees_with_process() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(134);
+        let mut word = || -> String {
+            let len = rng.gen_range(0..10);
+            (0..len)
+                .map(|_| ['a', 'b', 'c', 'ü'][rng.gen_range(0..4)])
+                .collect()
+        };
+        let mut words: Vec<String> = (0..300).map(|_| word()).collect();
+        let queries: Vec<String> = (0..30).map(|_| word()).collect();
+        let dictionary = FuzzyDictionary::from_words(&words);
+        words.sort_by(|a, b| a.chars().cmp(b.chars()));
+        words.dedup();
+        assert_eq!(dictionary.len(), words.len());
+        for query in queries.iter() {
+            for &cutoff in [0u8, 50, 70, 85, 100].iter() {
+                assert_eq!(
+                    dictionary.extract(query, cutoff),
+                    extract_levenshtein(query, &words, PassthroughNormalizer, cutoff),
+                    "{:?} {}",
+                    query,
+                    cutoff
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn updated_words_keep_their_id() {
+        let mut dictionary = FuzzyDictionary::from_words(vec!["kitten", "sitting", "mitten"]);
+        assert_eq!(dictionary.update(0, "mitten"), None);
+        assert_eq!(dictionary.update(0, "knitting"), Some("kitten".to_string()));
+        assert_eq!(dictionary.remove(2), Some("mitten".to_string()));
+        assert_eq!(dictionary.insert("mitten"), 3);
+        for _ in 0..2 {
+            let found: Vec<(EntryId, usize)> = dictionary
+                .find_within("kitten", 2)
+                .iter()
+                .map(|n| (n.id, n.distance))
+                .collect();
+            assert_eq!(found, vec![(3, 1)]);
+            assert_eq!(dictionary.get(0), Some("knitting"));
+            assert_eq!(dictionary.len(), 3);
+            dictionary.compact();
+        }
+    }
+}
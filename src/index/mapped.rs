@@ -0,0 +1,349 @@
+//! A read-only [FuzzyDictionary] searched in place, without loading it.
+
+use super::dictionary::Trie;
+use super::{EntryId, FuzzyDictionary, LevenshteinAutomaton, Neighbor};
+use crate::persist::{self, Encoder, PersistError, Pipeline};
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const NODE_SIZE: usize = 24;
+const CHILD_SIZE: usize = 12;
+const WORD_SIZE: usize = 16;
+const NONE: u64 = u64::MAX;
+
+/// A [FuzzyDictionary] flattened into a byte buffer, and searched directly in it.
+///
+/// Nothing is copied or decoded: the queries read the records in place, so the buffer can be a
+/// memory mapped file (`persist::MappedFile`, with the `mmap` feature) and processes mapping the
+/// same file share its pages. Opening still reads the whole buffer once, to verify its checksum
+/// and every record, so it takes time proportional to the size of the file. The queries are the
+/// same as those of [FuzzyDictionary], with the same results.
+///
+/// The trie is stored as fixed-size records: nodes point into a table of `(char, node)`
+/// children, and words into a table of string offsets.
+///
+/// ```
+/// # use fuzzywuzzy::index::{FuzzyDictionary, MappedDictionary};
+/// # use fuzzywuzzy::persist::Pipeline;
+/// let dictionary = FuzzyDictionary::from_words(vec!["kitten", "sitting", "mitten", "smitten"]);
+/// let pipeline = Pipeline::current("raw words");
+/// // Typically written to a file once, then mapped by every process.
+/// let bytes = MappedDictionary::to_bytes(&dictionary, &pipeline);
+///
+/// let mapped = MappedDictionary::open(&bytes, &pipeline).unwrap();
+/// assert_eq!(mapped.extract("kitten", 70), dictionary.extract("kitten", 70));
+/// assert_eq!(mapped.get(2), Some("mitten"));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct MappedDictionary<'a> {
+    nodes: &'a [u8],
+    children: &'a [u8],
+    words: &'a [u8],
+    strings: &'a [u8],
+    live: usize,
+    longest: usize,
+}
+
+impl<'a> MappedDictionary<'a> {
+    /// The name of the format, recorded in the header of the buffer.
+    pub const KIND: &'static str = "MappedDictionary";
+
+    /// Flattens `dictionary`, built by `pipeline`, into a buffer which can be opened with [open](MappedDictionary::open).
+    ///
+    /// Branches left over by removed words are dropped; IDs are unchanged.
+    pub fn to_bytes(dictionary: &FuzzyDictionary, pipeline: &Pipeline) -> Vec<u8> {
+        persist::write(Self::KIND, pipeline, |encoder| encode(dictionary, encoder))
+    }
+
+    /// Saves `dictionary`, built by `pipeline`, to `writer` in the format read by [open](MappedDictionary::open).
+    #[cfg(feature = "std")]
+    pub fn save<W: std::io::Write>(
+        dictionary: &FuzzyDictionary,
+        pipeline: &Pipeline,
+        mut writer: W,
+    ) -> Result<(), PersistError> {
+        writer.write_all(&Self::to_bytes(dictionary, pipeline))?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Opens a dictionary written by [to_bytes](MappedDictionary::to_bytes), checking it was built by `pipeline`.
+    ///
+    /// The whole buffer is read once, to verify its checksum and that every record is valid.
+    pub fn open(
+        bytes: &'a [u8],
+        pipeline: &Pipeline,
+    ) -> Result<MappedDictionary<'a>, PersistError> {
+        let mut decoder = persist::open(bytes, Self::KIND, pipeline)?;
+        let live = decoder.get_usize()?;
+        let longest = decoder.get_usize()?;
+        let dictionary = MappedDictionary {
+            nodes: decoder.get_bytes()?,
+            children: decoder.get_bytes()?,
+            words: decoder.get_bytes()?,
+            strings: decoder.get_bytes()?,
+            live,
+            longest,
+        };
+        decoder.check(decoder.remaining() == 0, "trailing data")?;
+        dictionary.validate()?;
+        Ok(dictionary)
+    }
+
+    /// The number of words in the dictionary.
+    pub fn len(&self) -> usize {
+        self.live
+    }
+
+    /// Whether the dictionary has no words.
+    pub fn is_empty(&self) -> bool {
+        self.live == 0
+    }
+
+    /// Returns the word with the given ID, unless it was removed.
+    pub fn get(&self, id: EntryId) -> Option<&'a str> {
+        if id >= self.words.len() / WORD_SIZE {
+            return None;
+        }
+        let offset = read_u64(self.words, id * WORD_SIZE);
+        let len = read_u64(self.words, id * WORD_SIZE + 8);
+        if len == NONE {
+            return None;
+        }
+        let bytes = &self.strings[offset as usize..(offset + len) as usize];
+        Some(core::str::from_utf8(bytes).expect("words are checked when opening"))
+    }
+
+    /// See [FuzzyDictionary::search].
+    pub fn search(&self, automaton: &LevenshteinAutomaton) -> Vec<Neighbor<'_>> {
+        Trie::search(self, automaton)
+    }
+
+    /// See [FuzzyDictionary::find_within].
+    pub fn find_within(&self, query: &str, max_distance: usize) -> Vec<Neighbor<'_>> {
+        Trie::find_within(self, query, max_distance)
+    }
+
+    /// See [FuzzyDictionary::extract].
+    pub fn extract(&self, query: &str, score_cutoff: u8) -> Vec<(String, u8)> {
+        Trie::extract(self, query, score_cutoff)
+    }
+
+    /// See [FuzzyDictionary::extract_one].
+    pub fn extract_one(&self, query: &str, score_cutoff: u8) -> Option<(String, u8)> {
+        Trie::extract_one(self, query, score_cutoff)
+    }
+
+    fn node_count(&self) -> usize {
+        self.nodes.len() / NODE_SIZE
+    }
+
+    /// Checks every record, so reading them never fails afterwards.
+    ///
+    /// Searches terminate even if the children form cycles, since the automaton rejects any
+    /// input longer than the query by more than its maximum distance.
+    fn validate(&self) -> Result<(), PersistError> {
+        let corrupt = |reason| Err(PersistError::Corrupt(reason));
+        if self.nodes.len() % NODE_SIZE != 0
+            || self.children.len() % CHILD_SIZE != 0
+            || self.words.len() % WORD_SIZE != 0
+            || self.node_count() == 0
+        {
+            return corrupt("invalid table size");
+        }
+        let child_count = (self.children.len() / CHILD_SIZE) as u64;
+        let word_count = (self.words.len() / WORD_SIZE) as u64;
+        let mut live = 0;
+        for id in 0..word_count as usize {
+            let offset = read_u64(self.words, id * WORD_SIZE);
+            let len = read_u64(self.words, id * WORD_SIZE + 8);
+            if len == NONE {
+                continue;
+            }
+            live += 1;
+            let end = offset.checked_add(len);
+            match end {
+                Some(end) if end <= self.strings.len() as u64 => {
+                    if core::str::from_utf8(&self.strings[offset as usize..end as usize]).is_err() {
+                        return corrupt("invalid UTF-8");
+                    }
+                }
+                _ => return corrupt("word out of bounds"),
+            }
+        }
+        for node in 0..self.node_count() {
+            let start = read_u64(self.nodes, node * NODE_SIZE);
+            let len = read_u64(self.nodes, node * NODE_SIZE + 8);
+            let word = read_u64(self.nodes, node * NODE_SIZE + 16);
+            if start > child_count || len > child_count - start {
+                return corrupt("children out of bounds");
+            }
+            if word != NONE && (word >= word_count || self.get(word as usize).is_none()) {
+                return corrupt("node of a missing word");
+            }
+        }
+        for child in 0..child_count as usize {
+            let c = read_u32(self.children, child * CHILD_SIZE);
+            let node = read_u64(self.children, child * CHILD_SIZE + 4);
+            if core::char::from_u32(c).is_none() || node == 0 || node >= self.node_count() as u64 {
+                return corrupt("invalid child");
+            }
+        }
+        if live != self.live {
+            return corrupt("wrong word count");
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Trie for MappedDictionary<'a> {
+    fn child_count(&self, node: usize) -> usize {
+        read_u64(self.nodes, node * NODE_SIZE + 8) as usize
+    }
+
+    fn child(&self, node: usize, index: usize) -> (char, usize) {
+        let child = read_u64(self.nodes, node * NODE_SIZE) as usize + index;
+        let c = read_u32(self.children, child * CHILD_SIZE);
+        let node = read_u64(self.children, child * CHILD_SIZE + 4);
+        (
+            core::char::from_u32(c).expect("children are checked when opening"),
+            node as usize,
+        )
+    }
+
+    fn word(&self, node: usize) -> Option<(EntryId, &str)> {
+        let id = read_u64(self.nodes, node * NODE_SIZE + 16);
+        if id == NONE {
+            return None;
+        }
+        let id = id as usize;
+        Some((id, self.get(id).expect("nodes are checked when opening")))
+    }
+
+    fn longest(&self) -> usize {
+        self.longest
+    }
+}
+
+/// Writes the tables of `dictionary`, numbering the nodes reachable from the root breadth first.
+fn encode(dictionary: &FuzzyDictionary, encoder: &mut Encoder) {
+    let mut nodes = Encoder::new();
+    let mut children = Encoder::new();
+    let mut child_count = 0;
+    let mut node_count = 1;
+    let mut queue = VecDeque::new();
+    queue.push_back(0);
+    while let Some(node) = queue.pop_front() {
+        let first_child = child_count;
+        for index in 0..dictionary.child_count(node) {
+            let (c, child) = dictionary.child(node, index);
+            children.put_char(c);
+            children.put_usize(node_count);
+            node_count += 1;
+            child_count += 1;
+            queue.push_back(child);
+        }
+        nodes.put_usize(first_child);
+        nodes.put_usize(child_count - first_child);
+        nodes.put_option_usize(dictionary.word(node).map(|(id, _)| id));
+    }
+    let mut words = Encoder::new();
+    let mut strings = Vec::new();
+    for id in 0..dictionary.id_bound() {
+        match dictionary.get(id) {
+            Some(word) => {
+                words.put_usize(strings.len());
+                words.put_usize(word.len());
+                strings.extend_from_slice(word.as_bytes());
+            }
+            None => {
+                words.put_u64(0);
+                words.put_u64(NONE);
+            }
+        }
+    }
+    encoder.put_usize(dictionary.len());
+    encoder.put_usize(Trie::longest(dictionary));
+    encoder.put_bytes(&nodes.into_bytes());
+    encoder.put_bytes(&children.into_bytes());
+    encoder.put_bytes(&words.into_bytes());
+    encoder.put_bytes(&strings);
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+    let mut le = [0; 8];
+    le.copy_from_slice(&bytes[at..at + 8]);
+    u64::from_le_bytes(le)
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    let mut le = [0; 4];
+    le.copy_from_slice(&bytes[at..at + 4]);
+    u32::from_le_bytes(le)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn queries_agree_with_dictionary() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(38);
+        let mut word = || -> String {
+            let len = rng.gen_range(0..9);
+            (0..len)
+                .map(|_| ['a', 'b', 'c', 'ж'][rng.gen_range(0..4)])
+                .collect()
+        };
+        let mut dictionary = FuzzyDictionary::from_words((0..300).map(|_| word()));
+        for id in (0..dictionary.id_bound()).step_by(7) {
+            dictionary.remove(id);
+        }
+        let queries: Vec<String> = (0..30).map(|_| word()).collect();
+        let pipeline = Pipeline::current("test");
+        let bytes = MappedDictionary::to_bytes(&dictionary, &pipeline);
+        let mapped = MappedDictionary::open(&bytes, &pipeline).unwrap();
+        assert_eq!(mapped.len(), dictionary.len());
+        for id in 0..dictionary.id_bound() + 1 {
+            assert_eq!(mapped.get(id), dictionary.get(id));
+        }
+        for query in queries.iter() {
+            assert_eq!(
+                mapped.find_within(query, 2),
+                dictionary.find_within(query, 2)
+            );
+            for &cutoff in [0u8, 60, 80].iter() {
+                assert_eq!(
+                    mapped.extract(query, cutoff),
+                    dictionary.extract(query, cutoff)
+                );
+            }
+        }
+        let error = MappedDictionary::open(&bytes, &Pipeline::current("other")).unwrap_err();
+        assert!(matches!(error, PersistError::PipelineMismatch { .. }));
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mapped_file_round_trip() {
+        let dictionary = FuzzyDictionary::from_words(vec!["kitten", "sitting", "mitten"]);
+        let pipeline = Pipeline::current("test");
+        let path =
+            std::env::temp_dir().join(format!("fuzzywuzzy-mapped-{}.bin", std::process::id()));
+        MappedDictionary::save(
+            &dictionary,
+            &pipeline,
+            std::fs::File::create(&path).unwrap(),
+        )
+        .unwrap();
+        let file = crate::persist::MappedFile::open(&path).unwrap();
+        let mapped = MappedDictionary::open(&file, &pipeline).unwrap();
+        assert_eq!(
+            mapped.extract("kitten", 70),
+            dictionary.extract("kitten", 70)
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,446 @@
+//! [MinHash](https://en.wikipedia.org/wiki/MinHash) signatures and locality sensitive hashing for near-duplicate detection.
+
+use super::{EntryId, FnvHasher};
+use crate::persist::{Decoder, Encoder, Persist, PersistError};
+use crate::scorer::Scorer;
+use crate::segmentation::Segmenter;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+
+/// The MinHash signature of a string: for each of a family of hash functions, the smallest
+/// hash of the string's shingles.
+///
+/// The probability that two signatures agree at any position is the
+/// [Jaccard similarity](https://en.wikipedia.org/wiki/Jaccard_index) of the sets of shingles.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature(Vec<u64>);
+
+impl Signature {
+    /// The minimum hashes making up the signature.
+    pub fn hashes(&self) -> &[u64] {
+        &self.0
+    }
+
+    /// Estimates the Jaccard similarity of the shingles of the strings, between 0 and 1.
+    ///
+    /// Panics if the signatures have different lengths.
+    pub fn similarity(&self, other: &Signature) -> f64 {
+        assert_eq!(
+            self.0.len(),
+            other.0.len(),
+            "signatures must have the same length"
+        );
+        if self.0.is_empty() {
+            return 1.0;
+        }
+        let agreeing = self
+            .0
+            .iter()
+            .zip(other.0.iter())
+            .filter(|(a, b)| a == b)
+            .count();
+        agreeing as f64 / self.0.len() as f64
+    }
+}
+
+/// Finds near-duplicate strings in large collections, without comparing every pair.
+///
+/// Strings are split by a [Segmenter], and runs of `shingle_size` consecutive segments
+/// (shingles) are hashed into a [Signature] of `bands * rows` hashes. The signature is cut
+/// into `bands` bands of `rows` hashes, and strings with an identical band land in the same
+/// bucket. Two strings whose shingles have Jaccard similarity `s` share a bucket with probability
+/// `1 - (1 - s^rows)^bands`, an S-curve with its steepest point near `(1 / bands)^(1 / rows)`:
+/// more rows make the index stricter, more bands make it more lenient.
+///
+/// The pairs sharing a bucket are only candidates; [near_duplicates](MinHashLsh::near_duplicates)
+/// verifies them with a `fuzz` scorer.
+///
+/// ```
+/// # use fuzzywuzzy::index::MinHashLsh;
+/// # use fuzzywuzzy::segmentation::CodePointSegmenter;
+/// # use fuzzywuzzy::scorer::Ratio;
+/// let mut index = MinHashLsh::new(CodePointSegmenter, 3, 20, 4);
+/// for choice in [
+///     "the quick brown fox jumps over the lazy dog",
+///     "the quick brown fox jumped over the lazy dog",
+///     "pack my box with five dozen liquor jugs",
+///     "pack my box with five dozen liquor jug",
+///     "sphinx of black quartz, judge my vow",
+/// ].iter() {
+///     index.insert(choice);
+/// }
+/// assert_eq!(
+///     index.near_duplicates(Ratio, 90),
+///     vec![(0, 1, 97), (2, 3, 99)]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MinHashLsh<S> {
+    segmenter: S,
+    shingle_size: usize,
+    rows: usize,
+    /// Seeds of the hash functions, one per signature position.
+    seeds: Vec<u64>,
+    /// For each band, the entries by hash of their band.
+    buckets: Vec<BTreeMap<u64, Vec<EntryId>>>,
+    /// Entries by ID, or `None` once removed.
+    choices: Vec<Option<String>>,
+    live: usize,
+}
+
+impl<S> MinHashLsh<S>
+where
+    S: for<'a> Segmenter<'a>,
+    for<'a> <S as Segmenter<'a>>::Output: Hash,
+{
+    /// Creates an empty index over shingles of `shingle_size` segments, with signatures cut into `bands` bands of `rows` hashes.
+    ///
+    /// Panics if any of the sizes is zero.
+    pub fn new(segmenter: S, shingle_size: usize, bands: usize, rows: usize) -> MinHashLsh<S> {
+        assert!(
+            shingle_size > 0 && bands > 0 && rows > 0,
+            "shingle size, bands and rows must be positive"
+        );
+        let mut state = 0x853c_49e6_748f_ea9b;
+        let seeds = (0..bands * rows).map(|_| split_mix(&mut state)).collect();
+        MinHashLsh {
+            segmenter,
+            shingle_size,
+            rows,
+            seeds,
+            buckets: vec![BTreeMap::new(); bands],
+            choices: Vec::new(),
+            live: 0,
+        }
+    }
+
+    /// The number of entries in the index.
+    pub fn len(&self) -> usize {
+        self.live
+    }
+
+    /// Whether the index has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.live == 0
+    }
+
+    /// Returns the entry with the given ID, unless it was removed.
+    pub fn get(&self, id: EntryId) -> Option<&str> {
+        self.choices.get(id)?.as_deref()
+    }
+
+    /// Computes the signature of `s`.
+    ///
+    /// Strings shorter than a shingle have a single shingle of all their segments.
+    pub fn signature(&self, s: &str) -> Signature {
+        let segments = self.segmenter.segment(s);
+        let width = core::cmp::min(self.shingle_size, segments.len());
+        let shingles: BTreeSet<u64> = if width == 0 {
+            BTreeSet::new()
+        } else {
+            segments
+                .windows(width)
+                .map(|shingle| {
+                    let mut hasher = FnvHasher::default();
+                    shingle.hash(&mut hasher);
+                    hasher.finish()
+                })
+                .collect()
+        };
+        Signature(
+            self.seeds
+                .iter()
+                .map(|&seed| {
+                    shingles
+                        .iter()
+                        .map(|&shingle| mix(shingle ^ seed))
+                        .min()
+                        .unwrap_or(u64::MAX)
+                })
+                .collect(),
+        )
+    }
+
+    /// Adds a choice to the index and returns its ID.
+    pub fn insert(&mut self, choice: &str) -> EntryId {
+        let id = self.choices.len();
+        self.choices.push(None);
+        self.insert_entry(id, choice);
+        id
+    }
+
+    /// Removes the entry with the given ID from the index, returning it.
+    ///
+    /// IDs are never reused, so the IDs of the other entries are unaffected.
+    pub fn remove(&mut self, id: EntryId) -> Option<String> {
+        let choice = self.choices.get_mut(id)?.take()?;
+        let signature = self.signature(&choice);
+        let bands: Vec<u64> = self.band_hashes(&signature).collect();
+        for (band, bucket) in bands.into_iter().zip(self.buckets.iter_mut()) {
+            if let Some(ids) = bucket.get_mut(&band) {
+                if let Ok(at) = ids.binary_search(&id) {
+                    ids.remove(at);
+                }
+                if ids.is_empty() {
+                    bucket.remove(&band);
+                }
+            }
+        }
+        self.live -= 1;
+        Some(choice)
+    }
+
+    /// Replaces the entry with the given ID, keeping its ID, and returns the previous choice.
+    ///
+    /// Returns `None`, leaving the index unchanged, if there is no such entry.
+    pub fn update(&mut self, id: EntryId, choice: &str) -> Option<String> {
+        let previous = self.remove(id)?;
+        self.insert_entry(id, choice);
+        Some(previous)
+    }
+
+    /// Releases the memory left over by removed entries.
+    ///
+    /// Removals take effect immediately, so this does not change candidates. IDs are unchanged.
+    pub fn compact(&mut self) {
+        for bucket in self.buckets.iter_mut() {
+            for ids in bucket.values_mut() {
+                ids.shrink_to_fit();
+            }
+        }
+    }
+
+    fn insert_entry(&mut self, id: EntryId, choice: &str) {
+        let signature = self.signature(choice);
+        let bands: Vec<u64> = self.band_hashes(&signature).collect();
+        for (band, bucket) in bands.into_iter().zip(self.buckets.iter_mut()) {
+            let ids = bucket.entry(band).or_default();
+            // Updated entries keep their ID, so they are not necessarily the last one.
+            let at = ids.binary_search(&id).unwrap_or_else(|at| at);
+            ids.insert(at, id);
+        }
+        self.choices[id] = Some(choice.to_string());
+        self.live += 1;
+    }
+
+    /// Returns the IDs of the entries sharing a bucket with `query`, in ID order.
+    pub fn candidates(&self, query: &str) -> Vec<EntryId> {
+        let signature = self.signature(query);
+        let mut candidates = BTreeSet::new();
+        for (band, bucket) in self.band_hashes(&signature).zip(self.buckets.iter()) {
+            if let Some(ids) = bucket.get(&band) {
+                candidates.extend(ids.iter().copied());
+            }
+        }
+        candidates.into_iter().collect()
+    }
+
+    /// Returns every pair of entries sharing a bucket, as `(smaller ID, larger ID)` in order.
+    pub fn candidate_pairs(&self) -> Vec<(EntryId, EntryId)> {
+        let mut pairs = BTreeSet::new();
+        for bucket in self.buckets.iter() {
+            for ids in bucket.values() {
+                for (i, &a) in ids.iter().enumerate() {
+                    pairs.extend(ids[i + 1..].iter().map(|&b| (a, b)));
+                }
+            }
+        }
+        pairs.into_iter().collect()
+    }
+
+    /// Scores the [candidate pairs](MinHashLsh::candidate_pairs) with `scorer` and returns those
+    /// meeting `score_cutoff`, as `(smaller ID, larger ID, score)` in order.
+    ///
+    /// The entries are scored as they were inserted, with no processor.
+    pub fn near_duplicates<F>(&self, scorer: F, score_cutoff: u8) -> Vec<(EntryId, EntryId, u8)>
+    where
+        F: Scorer<Score = u8>,
+    {
+        self.candidate_pairs()
+            .into_iter()
+            .filter_map(|(a, b)| {
+                let (a_choice, b_choice) = (self.get(a)?, self.get(b)?);
+                let score = scorer.score(a_choice, b_choice);
+                if score >= score_cutoff {
+                    Some((a, b, score))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn band_hashes<'s>(&self, signature: &'s Signature) -> impl Iterator<Item = u64> + 's {
+        signature.0.chunks(self.rows).map(|band| {
+            let mut hasher = FnvHasher::default();
+            band.hash(&mut hasher);
+            hasher.finish()
+        })
+    }
+}
+
+impl<S> Persist for MinHashLsh<S>
+where
+    S: for<'a> Segmenter<'a>,
+    for<'a> <S as Segmenter<'a>>::Output: Hash,
+{
+    const KIND: &'static str = "MinHashLsh";
+    /// The segmenter, which should be the one the index was built with.
+    type Context = S;
+
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_usize(self.shingle_size);
+        encoder.put_usize(self.rows);
+        encoder.put_usize(self.seeds.len());
+        for &seed in self.seeds.iter() {
+            encoder.put_u64(seed);
+        }
+        for bucket in self.buckets.iter() {
+            encoder.put_usize(bucket.len());
+            for (&band, ids) in bucket.iter() {
+                encoder.put_u64(band);
+                encoder.put_usize(ids.len());
+                for &id in ids.iter() {
+                    encoder.put_usize(id);
+                }
+            }
+        }
+        encoder.put_usize(self.choices.len());
+        for choice in self.choices.iter() {
+            encoder.put_bool(choice.is_some());
+            if let Some(choice) = choice {
+                encoder.put_str(choice);
+            }
+        }
+    }
+
+    fn decode(decoder: &mut Decoder<'_>, segmenter: S) -> Result<Self, PersistError> {
+        let shingle_size = decoder.get_usize()?;
+        let rows = decoder.get_usize()?;
+        let seeds = (0..decoder.get_len()?)
+            .map(|_| decoder.get_u64())
+            .collect::<Result<Vec<_>, _>>()?;
+        decoder.check(
+            shingle_size > 0 && rows > 0 && !seeds.is_empty() && seeds.len() % rows == 0,
+            "invalid MinHash parameters",
+        )?;
+        let mut buckets = Vec::new();
+        for _ in 0..seeds.len() / rows {
+            let mut bucket = BTreeMap::new();
+            for _ in 0..decoder.get_len()? {
+                let band = decoder.get_u64()?;
+                let ids = (0..decoder.get_len()?)
+                    .map(|_| decoder.get_usize())
+                    .collect::<Result<Vec<_>, _>>()?;
+                decoder.check(ids.windows(2).all(|w| w[0] < w[1]), "unsorted bucket")?;
+                bucket.insert(band, ids);
+            }
+            buckets.push(bucket);
+        }
+        let mut choices = Vec::new();
+        for _ in 0..decoder.get_len()? {
+            choices.push(if decoder.get_bool()? {
+                Some(decoder.get_string()?)
+            } else {
+                None
+            });
+        }
+        let valid = buckets
+            .iter()
+            .flat_map(|bucket| bucket.values().flatten())
+            .all(|&id| choices.get(id).map_or(false, Option::is_some));
+        decoder.check(valid, "bucket of a missing entry")?;
+        let live = choices.iter().flatten().count();
+        Ok(MinHashLsh {
+            segmenter,
+            shingle_size,
+            rows,
+            seeds,
+            buckets,
+            choices,
+            live,
+        })
+    }
+}
+
+/// Advances a [SplitMix64](https://prng.di.unimi.it/splitmix64.c) generator.
+fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    mix(*state)
+}
+
+/// The SplitMix64 finalizer, a bijective mixing of the bits of `x`.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::segmentation::{CodePointSegmenter, WhitespaceSegmenter};
+
+    fn jaccard(a: &BTreeSet<Vec<char>>, b: &BTreeSet<Vec<char>>) -> f64 {
+        a.intersection(b).count() as f64 / a.union(b).count() as f64
+    }
+
+    fn shingles(s: &str) -> BTreeSet<Vec<char>> {
+        let chars: Vec<char> = s.chars().collect();
+        chars.windows(3).map(|w| w.to_vec()).collect()
+    }
+
+    #[test]
+    fn signature_similarity_estimates_jaccard() {
+        let index = MinHashLsh::new(CodePointSegmenter, 3, 64, 4);
+        let pairs = [
+            ("the quick brown fox", "the quick brown fax"),
+            ("the quick brown fox", "a quick brown dog"),
+            ("the quick brown fox", "lorem ipsum dolor sit"),
+            ("same", "same"),
+        ];
+        for (a, b) in pairs.iter() {
+            let estimate = index.signature(a).similarity(&index.signature(b));
+            let exact = jaccard(&shingles(a), &shingles(b));
+            assert!(
+                (estimate - exact).abs() < 0.15,
+                "{} {} {} {}",
+                a,
+                b,
+                estimate,
+                exact
+            );
+        }
+    }
+
+    #[test]
+    fn word_shingles_find_reordered_duplicates() {
+        let mut index = MinHashLsh::new(WhitespaceSegmenter, 1, 16, 2);
+        let a = index.insert("new york mets vs atlanta braves");
+        let b = index.insert("atlanta braves vs new york mets");
+        index.insert("chicago cubs vs chicago white sox");
+        assert_eq!(index.candidate_pairs(), vec![(a, b)]);
+        assert_eq!(
+            index.candidates("braves vs mets new york atlanta"),
+            vec![a, b]
+        );
+        assert_eq!(index.len(), 3);
+
+        assert_eq!(
+            index.update(a, "chicago white sox vs chicago cubs"),
+            Some("new york mets vs atlanta braves".to_string())
+        );
+        assert_eq!(
+            index.remove(b),
+            Some("atlanta braves vs new york mets".to_string())
+        );
+        assert_eq!(index.remove(b), None);
+        index.compact();
+        assert_eq!(index.candidate_pairs(), vec![(a, 2)]);
+        assert!(index
+            .candidates("braves vs mets new york atlanta")
+            .is_empty());
+        assert_eq!(index.len(), 2);
+    }
+}
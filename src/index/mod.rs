@@ -0,0 +1,191 @@
+//! Index structures for searching large collections of choices.
+//!
+//! The functions in [process](crate::process) score the query against every choice,
+//! which does not scale to very large collections. The indexes in this module are
+//! built once over the choices and then answer queries by only looking at a
+//! fraction of them.
+//!
+//! Every entry of an index is identified by the [EntryId] returned when it was inserted.
+//! Indexes are updated in place: entries can be inserted, removed and updated at any time,
+//! and searches reflect every change right away. IDs are never reused nor renumbered, so
+//! they can be stored alongside the choices elsewhere. Removals may leave space behind,
+//! which `compact` reclaims without changing any ID.
+
+use crate::ascii;
+use crate::persist::{Decoder, Encoder, PersistError};
+use crate::primitives;
+use crate::utils;
+use alloc::vec::Vec;
+use core::hash::Hasher;
+
+pub mod automaton;
+pub mod bktree;
+pub mod dictionary;
+pub mod mapped;
+pub mod minhash;
+pub mod ngram;
+pub mod symspell;
+
+pub use self::automaton::LevenshteinAutomaton;
+pub use self::bktree::BkTree;
+pub use self::dictionary::FuzzyDictionary;
+pub use self::mapped::MappedDictionary;
+pub use self::minhash::{MinHashLsh, Signature};
+pub use self::ngram::{Filter, NgramIndex};
+pub use self::symspell::{Composition, Suggestion, SymSpell};
+
+/// Identifies an entry of an index. IDs are assigned in insertion order, starting at zero.
+pub type EntryId = usize;
+
+/// An integer edit distance between strings, computed over their `char`s.
+///
+/// Both are metrics, so they can be used by metric indexes such as [BkTree].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    /// See [primitives::levenshtein]. This is the default.
+    Levenshtein,
+    /// See [primitives::damerau_levenshtein].
+    DamerauLevenshtein,
+}
+
+impl Default for Metric {
+    fn default() -> Metric {
+        Metric::Levenshtein
+    }
+}
+
+impl Metric {
+    /// Returns the distance between `a` and `b` according to this metric.
+    ///
+    /// ```
+    /// # use fuzzywuzzy::index::Metric;
+    /// assert_eq!(Metric::Levenshtein.distance("teh", "the"), 2);
+    /// assert_eq!(Metric::DamerauLevenshtein.distance("teh", "the"), 1);
+    /// assert_eq!(Metric::DamerauLevenshtein.distance("スマホ", "スホマ"), 1);
+    /// ```
+    pub fn distance(&self, a: &str, b: &str) -> usize {
+        if a.is_ascii() && b.is_ascii() {
+            let (a, b) = (a.as_bytes(), b.as_bytes());
+            return match self {
+                Metric::Levenshtein => {
+                    ascii::bounded_levenshtein(a, b, core::cmp::max(a.len(), b.len()))
+                        .expect("distance never exceeds the longer length")
+                }
+                Metric::DamerauLevenshtein => primitives::damerau_levenshtein(a, b),
+            };
+        }
+        let a: Vec<char> = a.chars().collect();
+        let b: Vec<char> = b.chars().collect();
+        match self {
+            Metric::Levenshtein => primitives::levenshtein(&a, &b),
+            Metric::DamerauLevenshtein => primitives::damerau_levenshtein(&a, &b),
+        }
+    }
+}
+
+impl Metric {
+    pub(crate) fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_u8(match self {
+            Metric::Levenshtein => 0,
+            Metric::DamerauLevenshtein => 1,
+        });
+    }
+
+    pub(crate) fn decode(decoder: &mut Decoder<'_>) -> Result<Metric, PersistError> {
+        match decoder.get_u8()? {
+            0 => Ok(Metric::Levenshtein),
+            1 => Ok(Metric::DamerauLevenshtein),
+            _ => Err(PersistError::Corrupt("unknown metric")),
+        }
+    }
+}
+
+/// An entry found by an index search, along with its score.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Match<'a> {
+    /// The ID of the entry.
+    pub id: EntryId,
+    /// The entry itself.
+    pub choice: &'a str,
+    /// The score of the entry against the query.
+    pub score: u8,
+}
+
+/// An entry found by an index search, along with how close it is to the query.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Neighbor<'a> {
+    /// The ID of the entry.
+    pub id: EntryId,
+    /// The entry itself.
+    pub choice: &'a str,
+    /// The edit distance between the query and the entry.
+    pub distance: usize,
+    /// The distance as a ratio between 0 and 100, see [utils::ratio_from_distance].
+    pub score: u8,
+}
+
+/// 64 bit [FNV-1a](https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function) hasher.
+///
+/// Indexes use it to key segments without storing them. Integers are hashed as little endian
+/// and `usize` as 64 bits, so hashes are stable across runs and platforms.
+pub(crate) struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        FnvHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+}
+
+/// Hashes a sequence of chars with [FnvHasher].
+pub(crate) fn hash_chars(chars: &[char]) -> u64 {
+    let mut hasher = FnvHasher::default();
+    for &c in chars {
+        hasher.write_u32(c as u32);
+    }
+    hasher.finish()
+}
+
+impl<'a> Neighbor<'a> {
+    pub(crate) fn new(id: EntryId, choice: &'a str, query_len: usize, distance: usize) -> Self {
+        let length = core::cmp::max(query_len, choice.chars().count());
+        Neighbor {
+            id,
+            choice,
+            distance,
+            score: utils::ratio_from_distance(distance, length),
+        }
+    }
+}
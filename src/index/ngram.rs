@@ -0,0 +1,488 @@
+//! An inverted index of character n-grams for candidate generation.
+
+use super::{hash_chars, EntryId, Match};
+use crate::persist::{Decoder, Encoder, Persist, PersistError};
+use crate::processor::Processor;
+use crate::scorer::Scorer;
+use crate::utils;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// Which entries of an [NgramIndex] are scored against a query.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Filter {
+    /// Only entries sharing at least this many n-grams with the query are scored.
+    ///
+    /// This is a heuristic: it is fast, but it can miss entries whose score meets the cutoff.
+    MinOverlap(usize),
+    /// Only entries which can possibly have a [levenshtein_ratio](crate::fuzz::levenshtein_ratio)
+    /// meeting the cutoff are scored.
+    ///
+    /// An entry at edit distance `k` from the query shares at least
+    /// `max(len) + n - 1 - k * n` n-grams with it, since every edit destroys at most `n` of
+    /// them. The cutoff bounds `k` for every entry length, and so bounds the overlap from below.
+    /// When scoring with `levenshtein_ratio` (or any scorer which never exceeds it), no entry
+    /// meeting the cutoff is missed.
+    Levenshtein,
+}
+
+/// An inverted index from the character n-grams of processed choices to the choices containing them.
+///
+/// Searching is done in two stages: the index quickly retrieves the candidates sharing enough
+/// n-grams with the query (see [Filter]), then only those are scored.
+///
+/// Choices are processed with the index's processor when inserted, and queries when searched,
+/// so each choice is processed once. Strings are padded with `n - 1` sentinels at both ends, so
+/// strings shorter than `n` still have n-grams and the first and last characters count as much
+/// as the rest.
+///
+/// ```
+/// # use fuzzywuzzy::index::{Filter, NgramIndex};
+/// # use fuzzywuzzy::scorer::{LevenshteinRatio, WRatio};
+/// # use fuzzywuzzy::processor::FullProcess;
+/// let index = NgramIndex::from_choices(
+///     3,
+///     FullProcess::default(),
+///     &["new york mets", "new york yankees", "atlanta braves", "new orleans saints"],
+/// );
+/// // Reranks the candidates sharing at least 6 trigrams with the query with `WRatio`.
+/// let found: Vec<(&str, u8)> = index
+///     .extract("new york", WRatio::default(), 0, Filter::MinOverlap(6))
+///     .iter()
+///     .map(|m| (m.choice, m.score))
+///     .collect();
+/// assert_eq!(found, vec![("new york mets", 90), ("new york yankees", 90)]);
+///
+/// // With the Levenshtein filter, nothing scoring at least 60 is missed.
+/// let found: Vec<(&str, u8)> = index
+///     .extract("new yrok mets", LevenshteinRatio, 60, Filter::Levenshtein)
+///     .iter()
+///     .map(|m| (m.choice, m.score))
+///     .collect();
+/// assert_eq!(found, vec![("new york mets", 85)]);
+/// ```
+#[derive(Clone)]
+pub struct NgramIndex<P> {
+    n: usize,
+    processor: P,
+    entries: Vec<Option<Entry>>,
+    /// Posting lists of `(entry, occurrences)`, sorted by entry, keyed by n-gram hash.
+    postings: BTreeMap<u64, Vec<(EntryId, u32)>>,
+    /// Live entries by the length in chars of their processed form.
+    by_length: BTreeMap<usize, Vec<EntryId>>,
+    live: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    choice: String,
+    processed: String,
+    /// Length of `processed` in chars.
+    length: usize,
+}
+
+impl<P: Processor> NgramIndex<P> {
+    /// Creates an empty index of `n`-grams, processing strings with `processor`.
+    ///
+    /// Panics if `n` is zero.
+    pub fn new(n: usize, processor: P) -> NgramIndex<P> {
+        assert!(n > 0, "n-grams must have at least one character");
+        NgramIndex {
+            n,
+            processor,
+            entries: Vec::new(),
+            postings: BTreeMap::new(),
+            by_length: BTreeMap::new(),
+            live: 0,
+        }
+    }
+
+    /// Builds an index from a collection of choices, with IDs assigned in order.
+    pub fn from_choices<I, T>(n: usize, processor: P, choices: I) -> NgramIndex<P>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let mut index = NgramIndex::new(n, processor);
+        for choice in choices {
+            index.insert(choice.as_ref());
+        }
+        index
+    }
+
+    /// The number of characters per n-gram.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// The number of entries in the index.
+    pub fn len(&self) -> usize {
+        self.live
+    }
+
+    /// Whether the index has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.live == 0
+    }
+
+    /// Returns the entry with the given ID, unless it was removed.
+    pub fn get(&self, id: EntryId) -> Option<&str> {
+        self.entries
+            .get(id)
+            .and_then(|entry| entry.as_ref())
+            .map(|entry| entry.choice.as_str())
+    }
+
+    /// Adds a choice to the index and returns its ID.
+    pub fn insert(&mut self, choice: &str) -> EntryId {
+        let id = self.entries.len();
+        self.entries.push(None);
+        self.insert_entry(id, choice);
+        id
+    }
+
+    /// Removes the entry with the given ID from the index, returning it.
+    ///
+    /// IDs are never reused, so the IDs of the other entries are unaffected.
+    pub fn remove(&mut self, id: EntryId) -> Option<String> {
+        let entry = self.entries.get_mut(id)?.take()?;
+        for (gram, _) in self.grams(&entry.processed) {
+            if let Some(posting) = self.postings.get_mut(&gram) {
+                if let Ok(at) = posting.binary_search_by_key(&id, |&(e, _)| e) {
+                    posting.remove(at);
+                }
+                if posting.is_empty() {
+                    self.postings.remove(&gram);
+                }
+            }
+        }
+        if let Some(ids) = self.by_length.get_mut(&entry.length) {
+            if let Ok(at) = ids.binary_search(&id) {
+                ids.remove(at);
+            }
+            if ids.is_empty() {
+                self.by_length.remove(&entry.length);
+            }
+        }
+        self.live -= 1;
+        Some(entry.choice)
+    }
+
+    /// Replaces the entry with the given ID, keeping its ID, and returns the previous choice.
+    ///
+    /// Returns `None`, leaving the index unchanged, if there is no such entry.
+    pub fn update(&mut self, id: EntryId, choice: &str) -> Option<String> {
+        let previous = self.remove(id)?;
+        self.insert_entry(id, choice);
+        Some(previous)
+    }
+
+    /// Releases the memory left over by removed entries.
+    ///
+    /// Removals take effect immediately, so this does not change the results of searches. IDs are unchanged.
+    pub fn compact(&mut self) {
+        for posting in self.postings.values_mut() {
+            posting.shrink_to_fit();
+        }
+        for ids in self.by_length.values_mut() {
+            ids.shrink_to_fit();
+        }
+    }
+
+    fn insert_entry(&mut self, id: EntryId, choice: &str) {
+        let processed = self.processor.process(choice).into_owned();
+        let length = processed.chars().count();
+        for (gram, count) in self.grams(&processed) {
+            let posting = self.postings.entry(gram).or_default();
+            // Updated entries keep their ID, so they are not necessarily the last one.
+            let at = posting
+                .binary_search_by_key(&id, |&(e, _)| e)
+                .unwrap_or_else(|at| at);
+            posting.insert(at, (id, count));
+        }
+        let ids = self.by_length.entry(length).or_default();
+        let at = ids.binary_search(&id).unwrap_or_else(|at| at);
+        ids.insert(at, id);
+        self.entries[id] = Some(Entry {
+            choice: choice.to_string(),
+            processed,
+            length,
+        });
+        self.live += 1;
+    }
+
+    /// Returns the IDs of the entries passing `filter` for `query`, in ID order.
+    ///
+    /// `score_cutoff` is only used by [Filter::Levenshtein].
+    pub fn candidates(&self, query: &str, score_cutoff: u8, filter: Filter) -> Vec<EntryId> {
+        let processed = self.processor.process(query);
+        self.candidates_processed(&processed, score_cutoff, filter)
+    }
+
+    /// Scores the candidates for `query` with `scorer` and returns those meeting `score_cutoff`, in ID order.
+    ///
+    /// Like [process::extract_without_order](crate::process::extract_without_order), the
+    /// query and the choices are processed before being scored. Only the candidates passing
+    /// `filter` are scored; see [Filter] for when this is guaranteed to find every entry
+    /// meeting the cutoff.
+    pub fn extract<S>(
+        &self,
+        query: &str,
+        scorer: S,
+        score_cutoff: u8,
+        filter: Filter,
+    ) -> Vec<Match<'_>>
+    where
+        S: Scorer<Score = u8>,
+    {
+        let processed = self.processor.process(query);
+        let query = scorer.preprocess(&processed);
+        let mut results = Vec::new();
+        for id in self.candidates_processed(&processed, score_cutoff, filter) {
+            let entry = self.entries[id].as_ref().expect("candidates are live");
+            let score = scorer.score_processed(&query, &scorer.preprocess(&entry.processed));
+            if score >= score_cutoff {
+                results.push(Match {
+                    id,
+                    choice: &entry.choice,
+                    score,
+                });
+            }
+        }
+        results
+    }
+
+    fn candidates_processed(
+        &self,
+        processed: &str,
+        score_cutoff: u8,
+        filter: Filter,
+    ) -> Vec<EntryId> {
+        if filter == Filter::MinOverlap(0) {
+            return self.live_ids().collect();
+        }
+        let query_length = processed.chars().count();
+        let mut overlaps: BTreeMap<EntryId, usize> = BTreeMap::new();
+        for (gram, query_count) in self.grams(processed) {
+            if let Some(posting) = self.postings.get(&gram) {
+                for &(id, count) in posting {
+                    *overlaps.entry(id).or_insert(0) += core::cmp::min(query_count, count) as usize;
+                }
+            }
+        }
+        match filter {
+            Filter::MinOverlap(min) => overlaps
+                .into_iter()
+                .filter(|&(_, overlap)| overlap >= min)
+                .map(|(id, _)| id)
+                .collect(),
+            Filter::Levenshtein => {
+                // The overlap an entry of the given length needs, or `None` if its length alone
+                // rules it out.
+                let required = |length: usize| {
+                    let longest = core::cmp::max(length, query_length);
+                    let max_distance = utils::max_distance_for_ratio(longest, score_cutoff);
+                    // The length difference alone is a lower bound on the distance.
+                    if longest - core::cmp::min(length, query_length) > max_distance {
+                        None
+                    } else {
+                        Some((longest + self.n - 1).saturating_sub(max_distance * self.n))
+                    }
+                };
+                // Entries sharing no n-gram with the query only pass if no overlap is required,
+                // in which case their whole length bucket does.
+                let mut candidates = Vec::new();
+                for (&length, ids) in self.by_length.iter() {
+                    if required(length) == Some(0) {
+                        candidates.extend(ids.iter().copied());
+                    }
+                }
+                for (id, overlap) in overlaps {
+                    let length = self.entries[id].as_ref().expect("postings are live").length;
+                    if let Some(required) = required(length) {
+                        if required > 0 && overlap >= required {
+                            candidates.push(id);
+                        }
+                    }
+                }
+                candidates.sort_unstable();
+                candidates
+            }
+        }
+    }
+
+    fn live_ids(&self) -> impl Iterator<Item = EntryId> + '_ {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.is_some())
+            .map(|(id, _)| id)
+    }
+
+    /// Counts the occurrences of each (hashed) n-gram of the padded string.
+    fn grams(&self, s: &str) -> BTreeMap<u64, u32> {
+        // Padding characters come from a private use area, so they are unlikely to appear in text.
+        const PADDING: char = '\u{10FFFD}';
+        let mut padded: Vec<char> = Vec::with_capacity(s.len() + 2 * (self.n - 1));
+        padded.extend(core::iter::repeat(PADDING).take(self.n - 1));
+        padded.extend(s.chars());
+        padded.extend(core::iter::repeat(PADDING).take(self.n - 1));
+        let mut grams = BTreeMap::new();
+        for window in padded.windows(self.n) {
+            *grams.entry(hash_chars(window)).or_insert(0) += 1;
+        }
+        grams
+    }
+}
+
+impl<P: Processor> Persist for NgramIndex<P> {
+    const KIND: &'static str = "NgramIndex";
+    /// The processor, which should be the one the index was built with.
+    type Context = P;
+
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_usize(self.n);
+        encoder.put_usize(self.entries.len());
+        for entry in self.entries.iter() {
+            encoder.put_bool(entry.is_some());
+            if let Some(entry) = entry {
+                encoder.put_str(&entry.choice);
+                encoder.put_str(&entry.processed);
+            }
+        }
+        encoder.put_usize(self.postings.len());
+        for (&gram, posting) in self.postings.iter() {
+            encoder.put_u64(gram);
+            encoder.put_usize(posting.len());
+            for &(id, count) in posting.iter() {
+                encoder.put_usize(id);
+                encoder.put_u32(count);
+            }
+        }
+    }
+
+    fn decode(decoder: &mut Decoder<'_>, processor: P) -> Result<Self, PersistError> {
+        let n = decoder.get_usize()?;
+        decoder.check(n > 0, "n-grams must have at least one character")?;
+        let mut index = NgramIndex::new(n, processor);
+        for id in 0..decoder.get_len()? {
+            let entry = if decoder.get_bool()? {
+                let choice = decoder.get_string()?;
+                let processed = decoder.get_string()?;
+                let length = processed.chars().count();
+                index.by_length.entry(length).or_default().push(id);
+                index.live += 1;
+                Some(Entry {
+                    choice,
+                    processed,
+                    length,
+                })
+            } else {
+                None
+            };
+            index.entries.push(entry);
+        }
+        for _ in 0..decoder.get_len()? {
+            let gram = decoder.get_u64()?;
+            let posting = (0..decoder.get_len()?)
+                .map(|_| Ok((decoder.get_usize()?, decoder.get_u32()?)))
+                .collect::<Result<Vec<_>, PersistError>>()?;
+            let valid = posting.windows(2).all(|w| w[0].0 < w[1].0)
+                && posting
+                    .iter()
+                    .all(|&(id, _)| index.entries.get(id).map_or(false, Option::is_some));
+            decoder.check(valid, "invalid posting list")?;
+            index.postings.insert(gram, posting);
+        }
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::process::extract_levenshtein;
+    use crate::processor::FullProcess;
+    use crate::scorer::LevenshteinRatio;
+    use rand::{Rng, SeedableRng};
+
+    fn random_words(seed: u64, count: usize) -> Vec<String> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        (0..count)
+            .map(|_| {
+                let len = rng.gen_range(0..12);
+                (0..len)
+                    .map(|_| ['a', 'b', 'c', ' ', 'é'][rng.gen_range(0..5)])
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn levenshtein_filter_misses_nothing() {
+        let words = random_words(32, 400);
+        let queries = random_words(132, 40);
+        for &n in [1, 2, 3].iter() {
+            let index = NgramIndex::from_choices(n, FullProcess::default(), &words);
+            for query in queries.iter() {
+                for &cutoff in [0u8, 40, 60, 75, 90, 100].iter() {
+                    let expected: Vec<(String, u8)> =
+                        extract_levenshtein(query, &words, FullProcess::default(), cutoff);
+                    let found: Vec<(String, u8)> = index
+                        .extract(query, LevenshteinRatio, cutoff, Filter::Levenshtein)
+                        .iter()
+                        .map(|m| (m.choice.to_string(), m.score))
+                        .collect();
+                    assert_eq!(
+                        found, expected,
+                        "n={} query={:?} cutoff={}",
+                        n, query, cutoff
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn updated_entries_keep_their_id() {
+        let mut index =
+            NgramIndex::from_choices(3, FullProcess::default(), &["kitten", "sitting", "mitten"]);
+        assert_eq!(index.update(0, "knitting"), Some("kitten".to_string()));
+        assert_eq!(index.update(3, "knitting"), None);
+        index.compact();
+        let found: Vec<(EntryId, u8)> = index
+            .extract("knitting", LevenshteinRatio, 70, Filter::Levenshtein)
+            .iter()
+            .map(|m| (m.id, m.score))
+            .collect();
+        assert_eq!(found, vec![(0, 100), (1, 75)]);
+    }
+
+    #[test]
+    fn removed_entries_are_not_found() {
+        let mut index = NgramIndex::new(2, FullProcess::default());
+        let mets = index.insert("new york mets");
+        let yankees = index.insert("new york yankees");
+        assert_eq!(
+            index.candidates("new york", 0, Filter::MinOverlap(1)),
+            vec![mets, yankees]
+        );
+        assert_eq!(index.remove(mets), Some("new york mets".to_string()));
+        assert_eq!(index.remove(mets), None);
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.get(mets), None);
+        assert_eq!(
+            index.candidates("new york", 0, Filter::MinOverlap(1)),
+            vec![yankees]
+        );
+        assert_eq!(
+            index.candidates("new york", 0, Filter::MinOverlap(0)),
+            vec![yankees]
+        );
+        assert_eq!(
+            index.candidates("new york", 0, Filter::Levenshtein),
+            vec![yankees]
+        );
+    }
+}
@@ -0,0 +1,565 @@
+//! A symmetric delete index for spelling correction.
+
+use super::{hash_chars, Metric};
+use crate::persist::{Decoder, Encoder, Persist, PersistError};
+use crate::primitives;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// A dictionary word close to a query, see [SymSpell::lookup].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Suggestion<'a> {
+    /// The dictionary word.
+    pub term: &'a str,
+    /// The edit distance between the query and the word.
+    pub distance: usize,
+    /// How many times the word was counted in the dictionary.
+    pub count: u64,
+}
+
+/// A correction of a whole multi-word query, see [SymSpell::lookup_compound].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Composition {
+    /// The corrected query, with words separated by single spaces.
+    pub text: String,
+    /// The number of edits made, counting added or removed spaces.
+    pub distance: usize,
+}
+
+/// A dictionary of words with frequency counts, for finding the words within a small edit distance of a query.
+///
+/// This is the symmetric delete algorithm of [SymSpell](https://github.com/wolfgarbe/SymSpell):
+/// every string obtained by deleting up to `max_distance` characters from a word is indexed
+/// ahead of time. Any two words at distance `k` or less have such a delete in common, so a
+/// lookup only needs to generate the deletes of the query, and verify the few words sharing one.
+/// Unlike a BK-tree, the cost of a lookup barely depends on the size of the dictionary.
+///
+/// Deletes are stored as 64 bit hashes: a collision only adds a candidate, which is then
+/// rejected by the verification, so no suggestion is ever missed.
+///
+/// Words are compared as they are given; lowercase or otherwise normalize them (and the
+/// queries) beforehand if needed.
+///
+/// ```
+/// # use fuzzywuzzy::index::{Metric, SymSpell};
+/// let dictionary = SymSpell::from_counts(
+///     2,
+///     Metric::DamerauLevenshtein,
+///     vec![("the", 500), ("then", 80), ("they", 120), ("tea", 30), ("hello", 40)],
+/// );
+/// let suggestions: Vec<(&str, usize)> = dictionary
+///     .lookup("teh", 1)
+///     .iter()
+///     .map(|s| (s.term, s.distance))
+///     .collect();
+/// // Ranked by distance, then by count.
+/// assert_eq!(suggestions, vec![("the", 1), ("tea", 1)]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SymSpell {
+    max_distance: usize,
+    metric: Metric,
+    /// Words by index, or `None` once removed.
+    words: Vec<Option<Word>>,
+    /// Index of each word in `words`.
+    ids: BTreeMap<String, usize>,
+    /// Words having each delete, keyed by the hash of the delete.
+    deletes: BTreeMap<u64, Vec<usize>>,
+    /// Length in chars of the longest word.
+    longest: usize,
+    /// Sum of the counts of all words.
+    total: u64,
+}
+
+#[derive(Clone, Debug)]
+struct Word {
+    term: String,
+    chars: Vec<char>,
+    count: u64,
+}
+
+impl SymSpell {
+    /// Creates an empty dictionary supporting lookups of up to `max_distance` edits.
+    ///
+    /// The index grows quickly with `max_distance`; 2 or 3 is usually plenty.
+    pub fn new(max_distance: usize, metric: Metric) -> SymSpell {
+        SymSpell {
+            max_distance,
+            metric,
+            words: Vec::new(),
+            ids: BTreeMap::new(),
+            deletes: BTreeMap::new(),
+            longest: 0,
+            total: 0,
+        }
+    }
+
+    /// Builds a dictionary from `(word, count)` pairs.
+    pub fn from_counts<I, T>(max_distance: usize, metric: Metric, counts: I) -> SymSpell
+    where
+        I: IntoIterator<Item = (T, u64)>,
+        T: AsRef<str>,
+    {
+        let mut dictionary = SymSpell::new(max_distance, metric);
+        for (word, count) in counts {
+            dictionary.insert(word.as_ref(), count);
+        }
+        dictionary
+    }
+
+    /// The largest distance supported by lookups.
+    pub fn max_distance(&self) -> usize {
+        self.max_distance
+    }
+
+    /// The metric used to compare words.
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// The number of distinct words in the dictionary.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Whether the dictionary has no words.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Returns how many times `word` was counted, if it is in the dictionary.
+    pub fn count(&self, word: &str) -> Option<u64> {
+        self.ids.get(word).map(|&id| self.word(id).count)
+    }
+
+    /// Adds `count` occurrences of `word` to the dictionary.
+    pub fn insert(&mut self, word: &str, count: u64) {
+        self.total = self.total.saturating_add(count);
+        if let Some(&id) = self.ids.get(word) {
+            let entry = self.words[id].as_mut().expect("indexed words are live");
+            entry.count = entry.count.saturating_add(count);
+            return;
+        }
+        let id = self.words.len();
+        let chars: Vec<char> = word.chars().collect();
+        for delete in deletes(&chars, self.max_distance) {
+            let ids = self.deletes.entry(hash_chars(&delete)).or_default();
+            // The same hash can come from distinct deletes of the same word.
+            if ids.last() != Some(&id) {
+                ids.push(id);
+            }
+        }
+        self.longest = core::cmp::max(self.longest, chars.len());
+        self.ids.insert(word.to_string(), id);
+        self.words.push(Some(Word {
+            term: word.to_string(),
+            chars,
+            count,
+        }));
+    }
+
+    /// Removes `word` from the dictionary, returning its count.
+    ///
+    /// Its deletes are unindexed right away; the space of the word itself is reclaimed by [compact](SymSpell::compact).
+    pub fn remove(&mut self, word: &str) -> Option<u64> {
+        let id = self.ids.remove(word)?;
+        let entry = self.words[id].take().expect("indexed words are live");
+        for delete in deletes(&entry.chars, self.max_distance) {
+            let hash = hash_chars(&delete);
+            if let Some(ids) = self.deletes.get_mut(&hash) {
+                if let Ok(at) = ids.binary_search(&id) {
+                    ids.remove(at);
+                }
+                if ids.is_empty() {
+                    self.deletes.remove(&hash);
+                }
+            }
+        }
+        // The total saturates, so it can be smaller than the sum of the counts.
+        self.total = self.total.saturating_sub(entry.count);
+        Some(entry.count)
+    }
+
+    /// Sets the count of `word`, adding it to the dictionary if needed, and returns its previous count.
+    pub fn set_count(&mut self, word: &str, count: u64) -> Option<u64> {
+        match self.ids.get(word) {
+            Some(&id) => {
+                let entry = self.words[id].as_mut().expect("indexed words are live");
+                let previous = core::mem::replace(&mut entry.count, count);
+                self.total = self.total.saturating_sub(previous).saturating_add(count);
+                Some(previous)
+            }
+            None => {
+                self.insert(word, count);
+                None
+            }
+        }
+    }
+
+    /// Rebuilds the index from the words still in the dictionary, reclaiming the space of removed words.
+    pub fn compact(&mut self) {
+        let words: Vec<Word> = self.words.drain(..).flatten().collect();
+        self.ids.clear();
+        self.deletes.clear();
+        self.longest = 0;
+        self.total = 0;
+        for word in words {
+            self.insert(&word.term, word.count);
+        }
+    }
+
+    fn word(&self, id: usize) -> &Word {
+        self.words[id].as_ref().expect("indexed words are live")
+    }
+
+    /// Returns the words within `max_distance` edits of `query`, ranked by distance, then by
+    /// decreasing count, then alphabetically.
+    ///
+    /// Panics if `max_distance` is larger than the dictionary's.
+    pub fn lookup(&self, query: &str, max_distance: usize) -> Vec<Suggestion<'_>> {
+        let query: Vec<char> = query.chars().collect();
+        self.lookup_chars(&query, max_distance)
+    }
+
+    /// Corrects a multi-word query, also fixing spaces missing inside, or wrongly inserted
+    /// between, its words.
+    ///
+    /// Each word of the query is replaced by its best suggestion, unless merging it with the
+    /// next word, or splitting it in two, needs fewer edits. Each added or removed space counts
+    /// as one edit, and ties are broken in favour of the most frequent words. Words without any suggestion are kept as they are, at a distance of
+    /// `max_distance + 1`.
+    ///
+    /// Panics if `max_distance` is larger than the dictionary's.
+    ///
+    /// ```
+    /// # use fuzzywuzzy::index::{Metric, SymSpell};
+    /// let dictionary = SymSpell::from_counts(
+    ///     2,
+    ///     Metric::Levenshtein,
+    ///     vec![("where", 50), ("is", 200), ("the", 500), ("love", 30), ("whereas", 5)],
+    /// );
+    /// let corrected = dictionary.lookup_compound("whereis th elove", 2);
+    /// assert_eq!(corrected.text, "where is the love");
+    /// assert_eq!(corrected.distance, 3);
+    /// ```
+    pub fn lookup_compound(&self, query: &str, max_distance: usize) -> Composition {
+        let tokens: Vec<Vec<char>> = query
+            .split_whitespace()
+            .map(|t| t.chars().collect())
+            .collect();
+        let mut words: Vec<String> = Vec::new();
+        let mut distance = 0;
+        let mut i = 0;
+        while i < tokens.len() {
+            let (single, single_distance, single_count) = self.correct(&tokens[i], max_distance);
+            if let Some(next) = tokens.get(i + 1) {
+                // A space wrongly inserted inside a word.
+                let (_, next_distance, next_count) = self.correct(next, max_distance);
+                let merged: Vec<char> = tokens[i].iter().chain(next.iter()).copied().collect();
+                if let Some(best) = self.lookup_chars(&merged, max_distance).first() {
+                    let merged_distance = best.distance + 1;
+                    let separate_distance = single_distance + next_distance;
+                    if merged_distance < separate_distance
+                        || (merged_distance == separate_distance
+                            && self.more_likely(&[best.count], &[single_count, next_count]))
+                    {
+                        words.push(best.term.to_string());
+                        distance += merged_distance;
+                        i += 2;
+                        continue;
+                    }
+                }
+            }
+            // A space missing between two words.
+            let mut best_split: Option<(Suggestion<'_>, Suggestion<'_>, usize)> = None;
+            if single_distance > 0 {
+                for at in 1..tokens[i].len() {
+                    let (left, right) = tokens[i].split_at(at);
+                    let left = self.lookup_chars(left, max_distance);
+                    let right = self.lookup_chars(right, max_distance);
+                    if let (Some(&left), Some(&right)) = (left.first(), right.first()) {
+                        let split_distance = left.distance + right.distance + 1;
+                        let (best_distance, best_counts) = match best_split {
+                            Some((l, r, d)) => (d, vec![l.count, r.count]),
+                            None => (single_distance, vec![single_count]),
+                        };
+                        if split_distance < best_distance
+                            || (split_distance == best_distance
+                                && self.more_likely(&[left.count, right.count], &best_counts))
+                        {
+                            best_split = Some((left, right, split_distance));
+                        }
+                    }
+                }
+            }
+            match best_split {
+                Some((left, right, split_distance)) => {
+                    words.push(left.term.to_string());
+                    words.push(right.term.to_string());
+                    distance += split_distance;
+                }
+                None => {
+                    words.push(single);
+                    distance += single_distance;
+                }
+            }
+            i += 1;
+        }
+        Composition {
+            text: words.join(" "),
+            distance,
+        }
+    }
+
+    /// The best correction of a single word with its count, or the word itself if there is none.
+    fn correct(&self, word: &[char], max_distance: usize) -> (String, usize, u64) {
+        match self.lookup_chars(word, max_distance).first() {
+            Some(best) => (best.term.to_string(), best.distance, best.count),
+            None => (word.iter().collect(), max_distance + 1, 0),
+        }
+    }
+
+    /// Whether the sequence of words with counts `a` is more likely than the one with counts `b`,
+    /// assuming words are independent.
+    ///
+    /// The probability of a sequence is the product of `count / total` over its words, so the
+    /// comparison is done on cross-multiplied integers instead.
+    fn more_likely(&self, a: &[u64], b: &[u64]) -> bool {
+        let product = |counts: &[u64], others: usize| -> u128 {
+            let total = u128::from(self.total);
+            let mut product: u128 = 1;
+            for &count in counts {
+                product = product.saturating_mul(u128::from(count));
+            }
+            for _ in 0..others {
+                product = product.saturating_mul(total);
+            }
+            product
+        };
+        product(a, b.len()) > product(b, a.len())
+    }
+
+    fn lookup_chars(&self, query: &[char], max_distance: usize) -> Vec<Suggestion<'_>> {
+        assert!(
+            max_distance <= self.max_distance,
+            "the dictionary only supports lookups of up to {} edits",
+            self.max_distance
+        );
+        let mut suggestions = Vec::new();
+        if query.len() > self.longest + max_distance {
+            return suggestions;
+        }
+        let mut candidates = BTreeSet::new();
+        for delete in deletes(query, max_distance) {
+            if let Some(ids) = self.deletes.get(&hash_chars(&delete)) {
+                candidates.extend(ids.iter().copied());
+            }
+        }
+        for id in candidates {
+            let word = self.word(id);
+            let (len, query_len) = (word.chars.len(), query.len());
+            if core::cmp::max(len, query_len) - core::cmp::min(len, query_len) > max_distance {
+                continue;
+            }
+            let distance = match self.metric {
+                Metric::Levenshtein => {
+                    primitives::bounded_levenshtein(query, &word.chars, max_distance)
+                }
+                Metric::DamerauLevenshtein => {
+                    Some(primitives::damerau_levenshtein(query, &word.chars))
+                        .filter(|&d| d <= max_distance)
+                }
+            };
+            if let Some(distance) = distance {
+                suggestions.push(Suggestion {
+                    term: &word.term,
+                    distance,
+                    count: word.count,
+                });
+            }
+        }
+        suggestions.sort_by(|a, b| {
+            a.distance
+                .cmp(&b.distance)
+                .then(b.count.cmp(&a.count))
+                .then(a.term.cmp(b.term))
+        });
+        suggestions
+    }
+}
+
+impl Persist for SymSpell {
+    const KIND: &'static str = "SymSpell";
+    type Context = ();
+
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_usize(self.max_distance);
+        self.metric.encode(encoder);
+        encoder.put_usize(self.words.len());
+        for word in self.words.iter() {
+            encoder.put_bool(word.is_some());
+            if let Some(word) = word {
+                encoder.put_str(&word.term);
+                encoder.put_u64(word.count);
+            }
+        }
+        encoder.put_usize(self.deletes.len());
+        for (&delete, ids) in self.deletes.iter() {
+            encoder.put_u64(delete);
+            encoder.put_usize(ids.len());
+            for &id in ids.iter() {
+                encoder.put_usize(id);
+            }
+        }
+    }
+
+    fn decode(decoder: &mut Decoder<'_>, _: ()) -> Result<Self, PersistError> {
+        let max_distance = decoder.get_usize()?;
+        let metric = Metric::decode(decoder)?;
+        let mut dictionary = SymSpell::new(max_distance, metric);
+        for id in 0..decoder.get_len()? {
+            let word = if decoder.get_bool()? {
+                let term = decoder.get_string()?;
+                let count = decoder.get_u64()?;
+                let chars: Vec<char> = term.chars().collect();
+                dictionary.longest = core::cmp::max(dictionary.longest, chars.len());
+                dictionary.total = dictionary.total.saturating_add(count);
+                let duplicate = dictionary.ids.insert(term.clone(), id).is_some();
+                decoder.check(!duplicate, "duplicate word")?;
+                Some(Word { term, chars, count })
+            } else {
+                None
+            };
+            dictionary.words.push(word);
+        }
+        for _ in 0..decoder.get_len()? {
+            let delete = decoder.get_u64()?;
+            let ids = (0..decoder.get_len()?)
+                .map(|_| decoder.get_usize())
+                .collect::<Result<Vec<_>, _>>()?;
+            let valid = ids.windows(2).all(|w| w[0] < w[1])
+                && ids
+                    .iter()
+                    .all(|&id| dictionary.words.get(id).map_or(false, Option::is_some));
+            decoder.check(valid, "invalid delete list")?;
+            dictionary.deletes.insert(delete, ids);
+        }
+        Ok(dictionary)
+    }
+}
+
+/// All the distinct strings obtained by deleting up to `max_deletes` chars from `chars`, including `chars` itself.
+fn deletes(chars: &[char], max_deletes: usize) -> BTreeSet<Vec<char>> {
+    let mut all = BTreeSet::new();
+    all.insert(chars.to_vec());
+    let mut frontier = vec![chars.to_vec()];
+    for _ in 0..max_deletes {
+        let mut next = Vec::new();
+        for s in frontier.iter() {
+            for i in 0..s.len() {
+                let mut delete = s.clone();
+                delete.remove(i);
+                if all.insert(delete.clone()) {
+                    next.push(delete);
+                }
+            }
+        }
+        frontier = next;
+    }
+    all
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn lookup_agrees_with_linear_scan() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(33);
+        let word = |rng: &mut rand::rngs::StdRng| -> String {
+            let len = rng.gen_range(0..8);
+            (0..len)
+                .map(|_| ['a', 'b', 'c', 'ß'][rng.gen_range(0..4)])
+                .collect()
+        };
+        let counts: Vec<(String, u64)> = (0..300)
+            .map(|_| (word(&mut rng), rng.gen_range(1..10)))
+            .collect();
+        let queries: Vec<String> = (0..50).map(|_| word(&mut rng)).collect();
+        for &metric in [Metric::Levenshtein, Metric::DamerauLevenshtein].iter() {
+            let dictionary = SymSpell::from_counts(3, metric, counts.iter().map(|(w, c)| (w, *c)));
+            for query in queries.iter() {
+                for max_distance in 0..=3 {
+                    let found: BTreeSet<(&str, usize)> = dictionary
+                        .lookup(query, max_distance)
+                        .iter()
+                        .map(|s| (s.term, s.distance))
+                        .collect();
+                    let expected: BTreeSet<(&str, usize)> = dictionary
+                        .words
+                        .iter()
+                        .flatten()
+                        .map(|w| (w.term.as_str(), metric.distance(query, &w.term)))
+                        .filter(|&(_, d)| d <= max_distance)
+                        .collect();
+                    assert_eq!(found, expected, "{:?} {:?} {}", metric, query, max_distance);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn counts_accumulate() {
+        let mut dictionary = SymSpell::new(1, Metric::Levenshtein);
+        dictionary.insert("cat", 2);
+        dictionary.insert("cat", 3);
+        dictionary.insert("car", 4);
+        assert_eq!(dictionary.len(), 2);
+        assert_eq!(dictionary.count("cat"), Some(5));
+        let suggestions: Vec<(&str, u64)> = dictionary
+            .lookup("cax", 1)
+            .iter()
+            .map(|s| (s.term, s.count))
+            .collect();
+        assert_eq!(suggestions, vec![("cat", 5), ("car", 4)]);
+    }
+
+    #[test]
+    fn removed_words_are_not_suggested() {
+        let mut dictionary = SymSpell::from_counts(
+            2,
+            Metric::Levenshtein,
+            vec![("cat", 5), ("car", 4), ("cart", 1)],
+        );
+        assert_eq!(dictionary.remove("cat"), Some(5));
+        assert_eq!(dictionary.remove("cat"), None);
+        assert_eq!(dictionary.set_count("cart", 9), Some(1));
+        let expected = vec![("cart", 1, 9), ("car", 1, 4)];
+        for _ in 0..2 {
+            let suggestions: Vec<(&str, usize, u64)> = dictionary
+                .lookup("cat", 1)
+                .iter()
+                .map(|s| (s.term, s.distance, s.count))
+                .collect();
+            assert_eq!(suggestions, expected);
+            assert_eq!(dictionary.len(), 2);
+            dictionary.compact();
+        }
+    }
+
+    #[test]
+    fn saturated_totals_do_not_overflow() {
+        let mut dictionary = SymSpell::new(1, Metric::Levenshtein);
+        dictionary.insert("cat", u64::MAX - 1);
+        dictionary.insert("car", 5);
+        assert_eq!(dictionary.total, u64::MAX);
+        assert_eq!(dictionary.set_count("car", 10), Some(5));
+        assert_eq!(dictionary.total, u64::MAX);
+        assert_eq!(dictionary.set_count("cat", u64::MAX), Some(u64::MAX - 1));
+        assert_eq!(dictionary.remove("cat"), Some(u64::MAX));
+        assert_eq!(dictionary.remove("car"), Some(10));
+        assert_eq!(dictionary.total, 0);
+    }
+}
@@ -0,0 +1,163 @@
+//! Interning of segments into dense integer IDs.
+//!
+//! Comparing segments such as graphemes or words means comparing strings, over and over,
+//! every time two strings are scored. An [Interner] maps each distinct segment of a corpus
+//! to a dense `u32` ID once, after which strings can be scored as ID slices with
+//! [primitives](crate::primitives) functions: comparing two IDs is a single integer
+//! comparison, and IDs can be hashed or used to index arrays.
+//!
+//! ```
+//! # use fuzzywuzzy::interner::Interner;
+//! # use fuzzywuzzy::primitives::ratio;
+//! # use fuzzywuzzy::segmentation::{GraphemeSegmenter, WhitespaceSegmenter};
+//! let corpus = ["new york mets", "new york yankees", "atlanta braves"];
+//! let mut words = Interner::new();
+//! let interned: Vec<Vec<u32>> = corpus
+//!     .iter()
+//!     .map(|s| words.segment(&WhitespaceSegmenter, s))
+//!     .collect();
+//! assert_eq!(interned[0], vec![0, 1, 2]);
+//! assert_eq!(interned[1], vec![0, 1, 3]);
+//! let query = words.segment(&WhitespaceSegmenter, "york mets");
+//! assert_eq!(ratio(&query, &interned[0]), 80);
+//!
+//! // Scoring graphemes by ID gives the same result as scoring the graphemes themselves.
+//! let mut graphemes = Interner::new();
+//! let a = graphemes.segment(&GraphemeSegmenter, "new york mets");
+//! let b = graphemes.segment(&GraphemeSegmenter, "new YORK mets");
+//! assert_eq!(ratio(&a, &b), 69);
+//! ```
+
+use crate::segmentation::Segmenter;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+/// Maps segments to dense `u32` IDs, in order of first appearance.
+///
+/// IDs are only meaningful within the interner that produced them, so queries must be
+/// interned with the same interner as the corpus they are compared to. Interning a query
+/// adds its previously unseen segments, which never match any corpus segment.
+///
+/// Segments can be borrowed (e.g. `&str` graphemes of the corpus) or owned.
+#[derive(Debug, Clone)]
+pub struct Interner<T> {
+    ids: BTreeMap<T, u32>,
+    segments: Vec<T>,
+}
+
+impl<T: Ord + Clone> Default for Interner<T> {
+    fn default() -> Self {
+        Interner::new()
+    }
+}
+
+impl<T: Ord + Clone> Interner<T> {
+    /// Creates an empty interner.
+    pub fn new() -> Interner<T> {
+        Interner {
+            ids: BTreeMap::new(),
+            segments: Vec::new(),
+        }
+    }
+
+    /// Returns the ID of `segment`, assigning the next unused ID if it has not been seen.
+    ///
+    /// Panics if more than `u32::MAX` distinct segments are interned.
+    ///
+    /// ```
+    /// # use fuzzywuzzy::interner::Interner;
+    /// let mut interner = Interner::new();
+    /// assert_eq!(interner.intern("a"), 0);
+    /// assert_eq!(interner.intern("b"), 1);
+    /// assert_eq!(interner.intern("a"), 0);
+    /// ```
+    pub fn intern(&mut self, segment: T) -> u32 {
+        if let Some(&id) = self.ids.get(&segment) {
+            return id;
+        }
+        let id = u32::try_from(self.segments.len()).expect("too many distinct segments");
+        self.segments.push(segment.clone());
+        self.ids.insert(segment, id);
+        id
+    }
+
+    /// Interns every segment of a sequence, returning their IDs in order.
+    pub fn intern_all<I: IntoIterator<Item = T>>(&mut self, segments: I) -> Vec<u32> {
+        segments.into_iter().map(|s| self.intern(s)).collect()
+    }
+
+    /// Segments `s` with `segmenter` and interns the resulting segments.
+    ///
+    /// ```
+    /// # use fuzzywuzzy::interner::Interner;
+    /// # use fuzzywuzzy::segmentation::CodePointSegmenter;
+    /// let mut interner = Interner::new();
+    /// assert_eq!(interner.segment(&CodePointSegmenter, "abca"), vec![0, 1, 2, 0]);
+    /// ```
+    pub fn segment<'a, S>(&mut self, segmenter: &S, s: &'a str) -> Vec<u32>
+    where
+        S: Segmenter<'a, Output = T>,
+    {
+        self.intern_all(segmenter.segment(s))
+    }
+
+    /// Returns the ID of `segment` without interning it.
+    pub fn get(&self, segment: &T) -> Option<u32> {
+        self.ids.get(segment).copied()
+    }
+
+    /// Returns the segment with the given ID.
+    ///
+    /// ```
+    /// # use fuzzywuzzy::interner::Interner;
+    /// let mut interner = Interner::new();
+    /// let id = interner.intern("york");
+    /// assert_eq!(interner.resolve(id), Some(&"york"));
+    /// assert_eq!(interner.resolve(id + 1), None);
+    /// ```
+    pub fn resolve(&self, id: u32) -> Option<&T> {
+        self.segments.get(id as usize)
+    }
+
+    /// The number of distinct segments interned so far.
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Whether no segments have been interned yet.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fuzz;
+    use crate::primitives::ratio;
+    use crate::segmentation::CodePointSegmenter;
+
+    #[test]
+    fn interned_ratio_matches_fuzz_ratio() {
+        let corpus = [
+            "new york mets",
+            "the wonderful new york mets",
+            "スマホでchance",
+            "chance",
+            "fòllòwbáck",
+            "followback",
+            "",
+        ];
+        let mut interner = Interner::new();
+        let interned: Vec<Vec<u32>> = corpus
+            .iter()
+            .map(|s| interner.segment(&CodePointSegmenter, s))
+            .collect();
+        for (a, ia) in corpus.iter().zip(interned.iter()) {
+            for (b, ib) in corpus.iter().zip(interned.iter()) {
+                assert_eq!(ratio(ia, ib), fuzz::ratio(a, b), "{} {}", a, b);
+            }
+        }
+    }
+}
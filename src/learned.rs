@@ -0,0 +1,410 @@
+//! A composite scorer whose weights are learned from labeled pairs. Requires default feature "std".
+//!
+//! Where [fuzz::wratio] blends its sub-scores with fixed constants, a [LogisticScorer] combines
+//! [Feature]s of a pair of strings with logistic regression weights fitted to pairs labeled as
+//! matches or not. It scores the estimated probability of a match, from 0 to 100, and can be
+//! [persisted](crate::persist) once trained.
+//!
+//! ```
+//! # use fuzzywuzzy::learned::{Feature, LogisticScorer};
+//! # use fuzzywuzzy::persist::{self, Pipeline};
+//! # use fuzzywuzzy::process;
+//! # use fuzzywuzzy::processor::FullProcess;
+//! let pairs = [
+//!     ("John Smith", "Smith, John", true),
+//!     ("Jon Smith", "John Smith", true),
+//!     ("Acme Corp", "ACME Corporation", true),
+//!     ("Catherine Jones", "Katherine Jones", true),
+//!     ("John Smith", "Jane Smith", false),
+//!     ("Acme Corp", "Apex Corp", false),
+//!     ("Globex", "Initech", false),
+//!     ("Catherine Jones", "Catherine Zeta", false),
+//! ];
+//! let scorer = LogisticScorer::fit(Feature::ALL.to_vec(), &pairs, FullProcess::default(), 0.1);
+//! let choices = ["Acme Corporation", "Apex Corporation", "Globex"];
+//! let (best, score) =
+//!     process::extract_one("acme corp.", &choices, FullProcess::default(), scorer.clone(), 50).unwrap();
+//! assert_eq!(best, "Acme Corporation");
+//! assert!(score > 50);
+//!
+//! let pipeline = Pipeline::current("full_process");
+//! let bytes = persist::to_bytes(&scorer, &pipeline);
+//! let loaded: LogisticScorer = persist::from_bytes(&bytes, &pipeline, ()).unwrap();
+//! assert_eq!(loaded, scorer);
+//! ```
+
+use crate::fuzz;
+use crate::persist::{Decoder, Encoder, Persist, PersistError};
+use crate::primitives;
+use crate::processor::Processor;
+use crate::scorer::Scorer;
+use crate::utils;
+use alloc::vec::Vec;
+
+/// The maximum number of Newton steps taken by [LogisticScorer::fit].
+const MAX_ITERATIONS: usize = 100;
+
+/// Added to the diagonal of the Hessian, so that Newton steps stay defined when features are
+/// constant or collinear. It damps the steps without moving the optimum.
+const DAMPING: f64 = 1e-6;
+
+/// A similarity of two strings, between 0 and 1, used as an input of a [LogisticScorer].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// [fuzz::ratio].
+    Ratio,
+    /// [fuzz::partial_ratio].
+    PartialRatio,
+    /// [fuzz::token_sort_ratio], without processing.
+    TokenSortRatio,
+    /// [fuzz::token_set_ratio], without processing.
+    TokenSetRatio,
+    /// [primitives::jaro_winkler] over `char`s.
+    JaroWinkler,
+    /// The length of the shorter string over that of the longer one, in `char`s.
+    LengthRatio,
+}
+
+impl Feature {
+    /// Every feature.
+    pub const ALL: [Feature; 6] = [
+        Feature::Ratio,
+        Feature::PartialRatio,
+        Feature::TokenSortRatio,
+        Feature::TokenSetRatio,
+        Feature::JaroWinkler,
+        Feature::LengthRatio,
+    ];
+
+    /// The value of the feature for two strings.
+    pub fn value(self, a: &str, b: &str) -> f64 {
+        let percent = |score: u8| f64::from(score) / 100.0;
+        match self {
+            Feature::Ratio => percent(fuzz::ratio(a, b)),
+            Feature::PartialRatio => percent(fuzz::partial_ratio(a, b)),
+            Feature::TokenSortRatio => percent(fuzz::token_sort_ratio(a, b, false, false)),
+            Feature::TokenSetRatio => percent(fuzz::token_set_ratio(a, b, false, false)),
+            Feature::JaroWinkler => {
+                let a: Vec<char> = a.chars().collect();
+                let b: Vec<char> = b.chars().collect();
+                primitives::jaro_winkler(&a, &b)
+            }
+            Feature::LengthRatio => {
+                let (a, b) = (a.chars().count(), b.chars().count());
+                if a == b {
+                    1.0
+                } else {
+                    a.min(b) as f64 / a.max(b) as f64
+                }
+            }
+        }
+    }
+
+    fn tag(self) -> u8 {
+        match self {
+            Feature::Ratio => 0,
+            Feature::PartialRatio => 1,
+            Feature::TokenSortRatio => 2,
+            Feature::TokenSetRatio => 3,
+            Feature::JaroWinkler => 4,
+            Feature::LengthRatio => 5,
+        }
+    }
+}
+
+/// Scores pairs by the logistic regression of their [Feature]s: `100 / (1 + e^-z)` where `z` is
+/// the bias plus the weighted sum of the features.
+///
+/// Strings are compared as they are given, so the processor used for training should also be
+/// passed to the [process](crate::process) functions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogisticScorer {
+    features: Vec<Feature>,
+    weights: Vec<f64>,
+    bias: f64,
+}
+
+impl LogisticScorer {
+    /// A scorer with the given weight for each feature.
+    ///
+    /// Panics if there is not one weight per feature, or if a weight is not finite.
+    pub fn new(features: Vec<Feature>, weights: Vec<f64>, bias: f64) -> LogisticScorer {
+        assert_eq!(
+            features.len(),
+            weights.len(),
+            "expected one weight per feature"
+        );
+        assert!(
+            weights.iter().chain(Some(&bias)).all(|w| w.is_finite()),
+            "weights must be finite"
+        );
+        LogisticScorer {
+            features,
+            weights,
+            bias,
+        }
+    }
+
+    /// Fits the weights of `features` to labeled `(a, b, is_match)` pairs, processed with
+    /// `processor`, by maximizing their likelihood with Newton's method.
+    ///
+    /// The weights, but not the bias, are penalized by `regularization` times their squared
+    /// norm, which keeps them finite when the labels are perfectly separable.
+    ///
+    /// Panics if `regularization` is negative or not finite.
+    pub fn fit<A, B, P>(
+        features: Vec<Feature>,
+        pairs: &[(A, B, bool)],
+        processor: P,
+        regularization: f64,
+    ) -> LogisticScorer
+    where
+        A: AsRef<str>,
+        B: AsRef<str>,
+        P: Processor,
+    {
+        assert!(
+            regularization.is_finite() && regularization >= 0.0,
+            "invalid regularization {}",
+            regularization
+        );
+        let k = features.len();
+        // The feature values of each pair, with a constant 1 for the bias last.
+        let samples: Vec<(Vec<f64>, f64)> = pairs
+            .iter()
+            .map(|(a, b, is_match)| {
+                let (a, b) = (processor.process(a.as_ref()), processor.process(b.as_ref()));
+                let mut x: Vec<f64> = features.iter().map(|f| f.value(&a, &b)).collect();
+                x.push(1.0);
+                (x, if *is_match { 1.0 } else { 0.0 })
+            })
+            .collect();
+        let mut theta = vec![0.0; k + 1];
+        for _ in 0..MAX_ITERATIONS {
+            let mut gradient: Vec<f64> = (0..=k)
+                .map(|i| {
+                    if i < k {
+                        regularization * theta[i]
+                    } else {
+                        0.0
+                    }
+                })
+                .collect();
+            let mut hessian = vec![vec![0.0; k + 1]; k + 1];
+            for (i, row) in hessian.iter_mut().enumerate() {
+                row[i] = DAMPING + if i < k { regularization } else { 0.0 };
+            }
+            for (x, y) in samples.iter() {
+                let p = logistic(dot(&theta, x));
+                let curvature = p * (1.0 - p);
+                for i in 0..=k {
+                    gradient[i] += (p - y) * x[i];
+                    for j in 0..=k {
+                        hessian[i][j] += curvature * x[i] * x[j];
+                    }
+                }
+            }
+            let step = match solve(hessian, gradient) {
+                Some(step) => step,
+                None => break,
+            };
+            for (t, s) in theta.iter_mut().zip(step.iter()) {
+                *t -= s;
+            }
+            if step.iter().all(|s| s.abs() < 1e-9) {
+                break;
+            }
+        }
+        let bias = theta.pop().expect("the bias is last");
+        LogisticScorer::new(features, theta, bias)
+    }
+
+    /// The features, in the order of the weights.
+    pub fn features(&self) -> &[Feature] {
+        &self.features
+    }
+
+    /// The weight of each feature.
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    /// The bias, or intercept.
+    pub fn bias(&self) -> f64 {
+        self.bias
+    }
+
+    /// The estimated probability that `a` and `b` match.
+    pub fn probability(&self, a: &str, b: &str) -> f64 {
+        let x: Vec<f64> = self.features.iter().map(|f| f.value(a, b)).collect();
+        logistic(self.bias + dot(&self.weights, &x))
+    }
+}
+
+impl Scorer for LogisticScorer {
+    type Score = u8;
+
+    fn score_processed(&self, a: &str, b: &str) -> u8 {
+        utils::round_to_u8(100.0 * self.probability(a, b))
+    }
+}
+
+impl Persist for LogisticScorer {
+    const KIND: &'static str = "LogisticScorer";
+    type Context = ();
+
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_usize(self.features.len());
+        for (feature, weight) in self.features.iter().zip(self.weights.iter()) {
+            encoder.put_u8(feature.tag());
+            encoder.put_u64(weight.to_bits());
+        }
+        encoder.put_u64(self.bias.to_bits());
+    }
+
+    fn decode(decoder: &mut Decoder<'_>, _: ()) -> Result<Self, PersistError> {
+        let mut features = Vec::new();
+        let mut weights = Vec::new();
+        for _ in 0..decoder.get_len()? {
+            let tag = usize::from(decoder.get_u8()?);
+            decoder.check(tag < Feature::ALL.len(), "invalid feature")?;
+            features.push(Feature::ALL[tag]);
+            weights.push(f64::from_bits(decoder.get_u64()?));
+        }
+        let bias = f64::from_bits(decoder.get_u64()?);
+        decoder.check(
+            weights.iter().chain(Some(&bias)).all(|w| w.is_finite()),
+            "weight not finite",
+        )?;
+        Ok(LogisticScorer {
+            features,
+            weights,
+            bias,
+        })
+    }
+}
+
+fn logistic(z: f64) -> f64 {
+    1.0 / (1.0 + (-z).exp())
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Solves `matrix * x = rhs` by Gaussian elimination with partial pivoting, or returns `None`
+/// if the matrix is singular.
+fn solve(mut matrix: Vec<Vec<f64>>, mut rhs: Vec<f64>) -> Option<Vec<f64>> {
+    let n = rhs.len();
+    for column in 0..n {
+        let pivot = (column..n).max_by(|&i, &j| {
+            matrix[i][column]
+                .abs()
+                .partial_cmp(&matrix[j][column].abs())
+                .unwrap_or(core::cmp::Ordering::Equal)
+        })?;
+        if matrix[pivot][column].abs() < 1e-12 {
+            return None;
+        }
+        matrix.swap(column, pivot);
+        rhs.swap(column, pivot);
+        let (upper, lower) = matrix.split_at_mut(column + 1);
+        let pivot_row = &upper[column];
+        for (offset, row) in lower.iter_mut().enumerate() {
+            let factor = row[column] / pivot_row[column];
+            for (x, p) in row[column..].iter_mut().zip(&pivot_row[column..]) {
+                *x -= factor * p;
+            }
+            rhs[column + 1 + offset] -= factor * rhs[column];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let known: f64 = (row + 1..n).map(|j| matrix[row][j] * x[j]).sum();
+        x[row] = (rhs[row] - known) / matrix[row][row];
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::normalization::PassthroughNormalizer;
+    use crate::persist::{self, Pipeline};
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn fit_maximizes_the_likelihood() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(50);
+        let letters = ['a', 'b', 'c', 'd', 'e', ' '];
+        let mut pairs = Vec::new();
+        for _ in 0..300 {
+            let len = rng.gen_range(3..10);
+            let a: String = (0..len).map(|_| letters[rng.gen_range(0..6)]).collect();
+            let mut b: Vec<char> = a.chars().collect();
+            let is_match = rng.gen_bool(0.5);
+            let edits = if is_match { 1 } else { len / 2 };
+            for _ in 0..edits {
+                let i = rng.gen_range(0..b.len());
+                b[i] = letters[rng.gen_range(0..6)];
+            }
+            // Some noise, so the labels are not separable.
+            let is_match = is_match ^ rng.gen_bool(0.1);
+            pairs.push((a, b.into_iter().collect::<String>(), is_match));
+        }
+        let scorer = LogisticScorer::fit(Feature::ALL.to_vec(), &pairs, PassthroughNormalizer, 0.0);
+
+        // At the maximum, the gradient of the likelihood vanishes.
+        let mut gradient = vec![0.0; Feature::ALL.len() + 1];
+        for (a, b, is_match) in pairs.iter() {
+            let error = scorer.probability(a, b) - if *is_match { 1.0 } else { 0.0 };
+            for (g, feature) in gradient.iter_mut().zip(Feature::ALL.iter()) {
+                *g += error * feature.value(a, b);
+            }
+            gradient[Feature::ALL.len()] += error;
+        }
+        assert!(gradient.iter().all(|g| g.abs() < 1e-6), "{:?}", gradient);
+        let close = scorer.score("abcde", "abcdd");
+        let far = scorer.score("abcde", "ecbda");
+        assert!(close > 50 && far < close, "{} {}", close, far);
+
+        // Regularization keeps perfectly separable labels finite.
+        let separable = [("abc", "abc", true), ("abc", "xyz", false)];
+        let scorer =
+            LogisticScorer::fit(vec![Feature::Ratio], &separable, PassthroughNormalizer, 1.0);
+        assert!(scorer.weights()[0] > 0.0 && scorer.weights()[0].is_finite());
+    }
+
+    #[test]
+    fn decoding_checks_features_and_weights() {
+        let pipeline = Pipeline::current("");
+        let scorer = LogisticScorer::new(vec![Feature::JaroWinkler], vec![4.0], -2.0);
+        let bytes = persist::to_bytes(&scorer, &pipeline);
+        let loaded: LogisticScorer = persist::from_bytes(&bytes, &pipeline, ()).unwrap();
+        assert_eq!(loaded, scorer);
+
+        let corrupt = |value: &dyn Fn(&mut Encoder)| {
+            let bytes = persist::write(LogisticScorer::KIND, &pipeline, |encoder| {
+                encoder.put_usize(1);
+                value(encoder);
+            });
+            persist::from_bytes::<LogisticScorer>(&bytes, &pipeline, ())
+        };
+        assert!(matches!(
+            corrupt(&|e| {
+                e.put_u8(6);
+                e.put_u64(0);
+                e.put_u64(0);
+            }),
+            Err(PersistError::Corrupt("invalid feature"))
+        ));
+        assert!(matches!(
+            corrupt(&|e| {
+                e.put_u8(0);
+                e.put_u64(f64::NAN.to_bits());
+                e.put_u64(0);
+            }),
+            Err(PersistError::Corrupt("weight not finite"))
+        ));
+    }
+}
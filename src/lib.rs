@@ -1,13 +1,37 @@
 #![deny(warnings)]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 //! Fuzzy string matching like
 //! [FuzzyWuzzy.py](https://github.com/seatgeek/fuzzywuzzy) like a boss. It uses
 //! [Levenshtein Distance](https://en.wikipedia.org/wiki/Levenshtein_distance)
 //! to calculate the differences between sequences in a simple-to-use package.
+//!
+//! The crate is `no_std` compatible: disabling the default `std` feature builds
+//! the scorers, [primitives], [segmentation] and [normalization] against `alloc`
+//! only.
+
+#[macro_use]
+extern crate alloc;
 
 #[macro_use]
 pub mod utils;
+mod ascii;
+pub mod assignment;
+pub mod blocking;
+pub mod context;
+pub mod evaluation;
 pub mod fuzz;
+pub mod index;
+pub mod interner;
+#[cfg(feature = "std")]
+pub mod learned;
+#[cfg(feature = "std")]
+pub mod linkage;
 pub mod normalization;
+pub mod persist;
 pub mod primitives;
 pub mod process;
+pub mod processor;
+pub mod record;
+pub mod scorer;
+pub mod search;
 pub mod segmentation;
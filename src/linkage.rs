@@ -0,0 +1,335 @@
+//! Probabilistic record linkage with the Fellegi-Sunter model. Requires default feature "std".
+//!
+//! Rather than thresholding a combined score, each field score of a pair of records is
+//! discretized into a similarity level, and the resulting agreement pattern is weighed by how
+//! much more likely it is among matching pairs than among non-matching ones. These `m` and `u`
+//! probabilities are estimated with expectation-maximization from unlabeled candidate pairs,
+//! assuming fields agree independently of each other within each class.
+//!
+//! ```
+//! # use fuzzywuzzy::linkage::FellegiSunter;
+//! # use fuzzywuzzy::processor::FullProcess;
+//! # use fuzzywuzzy::record::{Field, RecordMatcher};
+//! # use fuzzywuzzy::scorer::{Ratio, TokenSortRatio};
+//! let matcher = RecordMatcher::new(vec![
+//!     Field::new("name", TokenSortRatio::default(), FullProcess::default(), 1.0),
+//!     Field::new("city", Ratio, FullProcess::default(), 1.0),
+//! ]);
+//! let people = [
+//!     [Some("John Smith"), Some("Boston")],
+//!     [Some("Smith, John"), Some("Boston")],
+//!     [Some("Jane Doe"), Some("Chicago")],
+//!     [Some("Jane Doe"), Some("Chicgo")],
+//!     [Some("Bob Stone"), Some("Boston")],
+//!     [Some("Alice Wong"), None],
+//! ];
+//! // Exact and close names or cities, and anything else.
+//! let mut model = FellegiSunter::new(vec![vec![100, 80], vec![100, 80]]);
+//! let mut patterns = Vec::new();
+//! for i in 0..people.len() {
+//!     for j in i + 1..people.len() {
+//!         patterns.push(model.pattern(&matcher.score(&people[i], &people[j]).fields));
+//!     }
+//! }
+//! model.estimate(&patterns, 100, 1e-6);
+//!
+//! let same = model.pattern(&matcher.score(&people[0], &people[1]).fields);
+//! let other = model.pattern(&matcher.score(&people[0], &people[4]).fields);
+//! assert_eq!(same, vec![Some(0), Some(0)]);
+//! assert_eq!(other, vec![Some(2), Some(0)]);
+//! assert!(model.match_weight(&same) > 0.0 && model.match_weight(&other) < 0.0);
+//! assert!(model.probability(&same) > 0.9 && model.probability(&other) < 0.1);
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+/// The similarity level of each field, or `None` where a field is missing.
+pub type Pattern = Vec<Option<usize>>;
+
+/// Probabilities below this are raised to it, so that no level rules a class out entirely.
+const MIN_PROBABILITY: f64 = 1e-6;
+
+/// A Fellegi-Sunter model of the agreement patterns of matching and non-matching pairs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FellegiSunter {
+    thresholds: Vec<Vec<u8>>,
+    m: Vec<Vec<f64>>,
+    u: Vec<Vec<f64>>,
+    prior: f64,
+}
+
+impl FellegiSunter {
+    /// A model of fields whose scores are discretized by `thresholds`, one list per field.
+    ///
+    /// A score meeting the `l`-th threshold of its field, but none before, is at level `l`; a
+    /// score meeting none is at the last level, so a field with `k` thresholds has `k + 1`
+    /// levels, from the most to the least similar. The parameters start out favoring higher
+    /// levels among matches and lower ones among non-matches, with one match in ten pairs.
+    ///
+    /// Panics if the thresholds of a field are not strictly decreasing.
+    pub fn new(thresholds: Vec<Vec<u8>>) -> FellegiSunter {
+        for field in thresholds.iter() {
+            assert!(
+                field.windows(2).all(|pair| pair[0] > pair[1]),
+                "thresholds must be strictly decreasing"
+            );
+        }
+        let initial = |levels: usize, matches: bool| -> Vec<f64> {
+            let weights: Vec<f64> = (0..levels)
+                .map(|level| {
+                    let rank = if matches { levels - level } else { level + 1 };
+                    rank as f64
+                })
+                .collect();
+            let total: f64 = weights.iter().sum();
+            weights.into_iter().map(|weight| weight / total).collect()
+        };
+        FellegiSunter {
+            m: thresholds
+                .iter()
+                .map(|t| initial(t.len() + 1, true))
+                .collect(),
+            u: thresholds
+                .iter()
+                .map(|t| initial(t.len() + 1, false))
+                .collect(),
+            thresholds,
+            prior: 0.1,
+        }
+    }
+
+    /// The number of fields.
+    pub fn fields(&self) -> usize {
+        self.thresholds.len()
+    }
+
+    /// The number of levels of `field`.
+    pub fn levels(&self, field: usize) -> usize {
+        self.thresholds[field].len() + 1
+    }
+
+    /// The probability of each level of `field` among matching pairs.
+    pub fn m(&self, field: usize) -> &[f64] {
+        &self.m[field]
+    }
+
+    /// The probability of each level of `field` among non-matching pairs.
+    pub fn u(&self, field: usize) -> &[f64] {
+        &self.u[field]
+    }
+
+    /// The proportion of matching pairs.
+    pub fn prior(&self) -> f64 {
+        self.prior
+    }
+
+    /// The agreement pattern of the field scores of a pair, such as the
+    /// [fields](crate::record::RecordScore::fields) of a record score.
+    ///
+    /// Panics if there is not one score per field.
+    pub fn pattern(&self, scores: &[Option<u8>]) -> Pattern {
+        assert_eq!(scores.len(), self.fields(), "expected one score per field");
+        scores
+            .iter()
+            .zip(self.thresholds.iter())
+            .map(|(score, thresholds)| {
+                score.map(|score| {
+                    thresholds
+                        .iter()
+                        .position(|&threshold| score >= threshold)
+                        .unwrap_or(thresholds.len())
+                })
+            })
+            .collect()
+    }
+
+    /// The weight of `level` of `field`: `log2(m / u)`, positive if it is evidence of a match.
+    pub fn level_weight(&self, field: usize, level: usize) -> f64 {
+        (self.m[field][level] / self.u[field][level]).log2()
+    }
+
+    /// The total weight of the evidence in `pattern`, missing fields weighing nothing.
+    pub fn match_weight(&self, pattern: &[Option<usize>]) -> f64 {
+        self.check(pattern);
+        pattern
+            .iter()
+            .enumerate()
+            .filter_map(|(field, level)| level.map(|level| self.level_weight(field, level)))
+            .sum()
+    }
+
+    /// The probability that a pair with this `pattern` is a match.
+    pub fn probability(&self, pattern: &[Option<usize>]) -> f64 {
+        let log_odds = (self.prior / (1.0 - self.prior)).log2() + self.match_weight(pattern);
+        1.0 / (1.0 + (-log_odds).exp2())
+    }
+
+    /// Estimates the parameters from the patterns of unlabeled candidate pairs by
+    /// expectation-maximization, starting from the current ones.
+    ///
+    /// Stops after `max_iterations`, or once no parameter changes by more than `tolerance`,
+    /// and returns the number of iterations run.
+    pub fn estimate(
+        &mut self,
+        patterns: &[Pattern],
+        max_iterations: usize,
+        tolerance: f64,
+    ) -> usize {
+        let mut counts: BTreeMap<&[Option<usize>], f64> = BTreeMap::new();
+        for pattern in patterns {
+            self.check(pattern);
+            *counts.entry(pattern).or_insert(0.0) += 1.0;
+        }
+        if counts.is_empty() {
+            return 0;
+        }
+        for iteration in 1..=max_iterations {
+            let mut m: Vec<Vec<f64>> = self.m.iter().map(|m| vec![0.0; m.len()]).collect();
+            let mut u = m.clone();
+            let mut matches = 0.0;
+            for (pattern, &count) in counts.iter() {
+                let (mut match_likelihood, mut other_likelihood) = (self.prior, 1.0 - self.prior);
+                for (field, level) in pattern.iter().enumerate() {
+                    if let Some(level) = *level {
+                        match_likelihood *= self.m[field][level];
+                        other_likelihood *= self.u[field][level];
+                    }
+                }
+                let posterior = match_likelihood / (match_likelihood + other_likelihood);
+                matches += count * posterior;
+                for (field, level) in pattern.iter().enumerate() {
+                    if let Some(level) = *level {
+                        m[field][level] += count * posterior;
+                        u[field][level] += count * (1.0 - posterior);
+                    }
+                }
+            }
+            let prior = (matches / patterns.len() as f64)
+                .max(MIN_PROBABILITY)
+                .min(1.0 - MIN_PROBABILITY);
+            for probabilities in m.iter_mut().chain(u.iter_mut()) {
+                normalize(probabilities);
+            }
+            let change = self
+                .m
+                .iter()
+                .chain(self.u.iter())
+                .flatten()
+                .zip(m.iter().chain(u.iter()).flatten())
+                .map(|(old, new)| (old - new).abs())
+                .fold((self.prior - prior).abs(), f64::max);
+            self.m = m;
+            self.u = u;
+            self.prior = prior;
+            if change <= tolerance {
+                return iteration;
+            }
+        }
+        max_iterations
+    }
+
+    fn check(&self, pattern: &[Option<usize>]) {
+        assert_eq!(pattern.len(), self.fields(), "expected one level per field");
+        for (field, level) in pattern.iter().enumerate() {
+            if let Some(level) = *level {
+                assert!(level < self.levels(field), "level {} out of bounds", level);
+            }
+        }
+    }
+}
+
+/// Scales `probabilities` to sum to one, keeping each of them at least [MIN_PROBABILITY].
+/// Fields never observed get uniform probabilities.
+fn normalize(probabilities: &mut [f64]) {
+    let (total, levels) = (
+        probabilities.iter().sum::<f64>(),
+        probabilities.len() as f64,
+    );
+    for p in probabilities.iter_mut() {
+        *p = if total > 0.0 {
+            *p / total
+        } else {
+            1.0 / levels
+        };
+    }
+    for p in probabilities.iter_mut() {
+        *p = p.max(MIN_PROBABILITY);
+    }
+    let total: f64 = probabilities.iter().sum();
+    for p in probabilities.iter_mut() {
+        *p /= total;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn estimate_recovers_the_parameters() {
+        let m = [vec![0.7, 0.2, 0.1], vec![0.9, 0.1], vec![0.6, 0.3, 0.1]];
+        let u = [vec![0.05, 0.15, 0.8], vec![0.2, 0.8], vec![0.1, 0.3, 0.6]];
+        let prior = 0.2;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(47);
+        let sample = |rng: &mut rand::rngs::StdRng, probabilities: &[f64]| {
+            let mut x: f64 = rng.gen();
+            for (level, &p) in probabilities.iter().enumerate() {
+                if x < p {
+                    return level;
+                }
+                x -= p;
+            }
+            probabilities.len() - 1
+        };
+        let patterns: Vec<Pattern> = (0..20_000)
+            .map(|_| {
+                let matches = rng.gen_bool(prior);
+                (0..3)
+                    .map(|field| {
+                        if rng.gen_bool(0.05) {
+                            None
+                        } else if matches {
+                            Some(sample(&mut rng, &m[field]))
+                        } else {
+                            Some(sample(&mut rng, &u[field]))
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut model = FellegiSunter::new(vec![vec![95, 80], vec![90], vec![100, 50]]);
+        let iterations = model.estimate(&patterns, 500, 1e-9);
+        assert!(iterations < 500);
+        assert!((model.prior() - prior).abs() < 0.02, "{}", model.prior());
+        for field in 0..3 {
+            for level in 0..model.levels(field) {
+                assert!((model.m(field)[level] - m[field][level]).abs() < 0.03);
+                assert!((model.u(field)[level] - u[field][level]).abs() < 0.03);
+            }
+        }
+        // Agreeing more is more evidence of a match.
+        for field in 0..3 {
+            for level in 1..model.levels(field) {
+                assert!(model.level_weight(field, level - 1) > model.level_weight(field, level));
+            }
+        }
+        let certain = model.probability(&[Some(0), Some(0), Some(0)]);
+        let unknown = model.probability(&[None, None, None]);
+        assert!(certain > 0.95);
+        assert!((unknown - model.prior()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn scores_are_discretized_by_thresholds() {
+        let model = FellegiSunter::new(vec![vec![100, 80], vec![]]);
+        assert_eq!(model.pattern(&[Some(100), Some(0)]), vec![Some(0), Some(0)]);
+        assert_eq!(model.pattern(&[Some(99), None]), vec![Some(1), None]);
+        assert_eq!(
+            model.pattern(&[Some(79), Some(100)]),
+            vec![Some(2), Some(0)]
+        );
+    }
+}
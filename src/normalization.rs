@@ -17,6 +17,11 @@
 //! assert_eq!(multiple_normalizers.normalize(a3), a1);
 //! ```
 
+use alloc::borrow::{Cow, ToOwned};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
 /// Represents a strategy for normalizing string characters into a canonical value of their equivalence class.
 ///
 /// i.e., in a case-insensitive context, 'a' might be the canonical value for the equivalence class of ASCII A's: `['a', 'A']`.
@@ -32,6 +37,30 @@
 /// ```
 pub trait Normalizer {
     fn normalize(&self, s: &str) -> String;
+
+    /// Like [normalize](Normalizer::normalize), but borrows `s` if it is already normalized.
+    ///
+    /// The default always allocates; the normalizers of this module override it.
+    fn normalize_cow<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        Cow::Owned(self.normalize(s))
+    }
+}
+
+/// Applies `next` to the result of a previous stage, reusing its allocation if `next` borrows.
+pub(crate) fn then<'a, F>(previous: Cow<'a, str>, next: F) -> Cow<'a, str>
+where
+    F: for<'b> FnOnce(&'b str) -> Cow<'b, str>,
+{
+    match previous {
+        Cow::Borrowed(s) => next(s),
+        Cow::Owned(s) => {
+            let changed = match next(&s) {
+                Cow::Borrowed(_) => None,
+                Cow::Owned(changed) => Some(changed),
+            };
+            Cow::Owned(changed.unwrap_or(s))
+        }
+    }
 }
 
 impl<F: Fn(&str) -> String> Normalizer for F {
@@ -57,6 +86,10 @@ impl Normalizer for PassthroughNormalizer {
     fn normalize(&self, s: &str) -> String {
         s.into()
     }
+
+    fn normalize_cow<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(s)
+    }
 }
 
 /// Compose a sequence of [Normalizer]s together into one [Normalizer].
@@ -88,6 +121,14 @@ impl Normalizer for ComposedNormalizer {
         }
         current
     }
+
+    fn normalize_cow<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        self.normalizers
+            .iter()
+            .fold(Cow::Borrowed(s), |current, normalizer| {
+                then(current, |s| normalizer.normalize_cow(s))
+            })
+    }
 }
 
 /// Normalizes strings by lower-casing all letters.
@@ -102,6 +143,20 @@ impl Normalizer for LowerCaseNormalizer {
     fn normalize(&self, s: &str) -> String {
         s.to_lowercase()
     }
+
+    fn normalize_cow<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        if s.chars().all(is_lowercase) {
+            Cow::Borrowed(s)
+        } else {
+            Cow::Owned(s.to_lowercase())
+        }
+    }
+}
+
+/// Whether lower-casing leaves `c` unchanged.
+pub(crate) fn is_lowercase(c: char) -> bool {
+    let mut lower = c.to_lowercase();
+    lower.next() == Some(c) && lower.next().is_none()
 }
 
 /// Removes non-ASCII codepoints.
@@ -119,16 +174,39 @@ impl Normalizer for AsciiOnlyNormalizer {
     fn normalize(&self, s: &str) -> String {
         s.chars().filter(char::is_ascii).collect()
     }
+
+    fn normalize_cow<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        if s.is_ascii() {
+            Cow::Borrowed(s)
+        } else {
+            Cow::Owned(self.normalize(s))
+        }
+    }
 }
 
-#[cfg(feature = "segmentation")]
+#[cfg(feature = "normalization")]
 pub use self::unicode_normalizers::*;
 
-#[cfg(feature = "segmentation")]
+#[cfg(feature = "normalization")]
 mod unicode_normalizers {
     use super::Normalizer;
+    use alloc::borrow::Cow;
+    use alloc::string::String;
     use unicode_normalization::UnicodeNormalization;
 
+    /// Borrows `s` if `is_normalized`, otherwise normalizes it.
+    fn normalize_unless<'a, N: Normalizer + ?Sized>(
+        normalizer: &N,
+        s: &'a str,
+        is_normalized: fn(&str) -> bool,
+    ) -> Cow<'a, str> {
+        if is_normalized(s) {
+            Cow::Borrowed(s)
+        } else {
+            Cow::Owned(normalizer.normalize(s))
+        }
+    }
+
     /// Performs Unicode Normalization Form C (canonical decomposition followed by canonical composition). Requires default feature "normalization".
     ///
     /// This just delegates to [unicode_normalization::UnicodeNormalization::nfc].
@@ -146,6 +224,10 @@ mod unicode_normalizers {
         fn normalize(&self, s: &str) -> String {
             s.nfc().collect()
         }
+
+        fn normalize_cow<'a>(&self, s: &'a str) -> Cow<'a, str> {
+            normalize_unless(self, s, unicode_normalization::is_nfc)
+        }
     }
 
     /// Performs Unicode Normalization Form KC (compatibility decomposition followed by canonical composition). Requires default feature "normalization".
@@ -165,6 +247,10 @@ mod unicode_normalizers {
         fn normalize(&self, s: &str) -> String {
             s.nfkc().collect()
         }
+
+        fn normalize_cow<'a>(&self, s: &'a str) -> Cow<'a, str> {
+            normalize_unless(self, s, unicode_normalization::is_nfkc)
+        }
     }
 
     /// Performs Unicode Normalization Form D (canonical decomposition). Requires default feature "normalization".
@@ -181,6 +267,10 @@ mod unicode_normalizers {
         fn normalize(&self, s: &str) -> String {
             s.nfd().collect()
         }
+
+        fn normalize_cow<'a>(&self, s: &'a str) -> Cow<'a, str> {
+            normalize_unless(self, s, unicode_normalization::is_nfd)
+        }
     }
 
     /// Performs Unicode Normalization Form KD (compatibility decomposition). Requires default feature "normalization".
@@ -197,6 +287,10 @@ mod unicode_normalizers {
         fn normalize(&self, s: &str) -> String {
             s.nfkd().collect()
         }
+
+        fn normalize_cow<'a>(&self, s: &'a str) -> Cow<'a, str> {
+            normalize_unless(self, s, unicode_normalization::is_nfkd)
+        }
     }
 
     /// Performs CJK Compatibility Ideograph-to-Standarized Variation Sequence normalization. Requires default feature "normalization".
@@ -209,6 +303,10 @@ mod unicode_normalizers {
         fn normalize(&self, s: &str) -> String {
             s.cjk_compat_variants().collect()
         }
+
+        fn normalize_cow<'a>(&self, s: &'a str) -> Cow<'a, str> {
+            normalize_unless(self, s, str::is_ascii)
+        }
     }
 
     /// Decomposes a string, then removes non-ascii code points. Requires default feature "normalization".
@@ -231,5 +329,9 @@ mod unicode_normalizers {
         fn normalize(&self, s: &str) -> String {
             s.nfd().filter(char::is_ascii).collect()
         }
+
+        fn normalize_cow<'a>(&self, s: &'a str) -> Cow<'a, str> {
+            normalize_unless(self, s, str::is_ascii)
+        }
     }
 }
@@ -0,0 +1,669 @@
+//! A versioned binary format to save prebuilt indexes and preprocessed corpora, and load them back.
+//!
+//! Every saved value is wrapped in a header recording the format version, the type of the value
+//! and the [Pipeline] it was built with, and followed by a checksum of everything before it.
+//! Loading fails with a [PersistError] rather than returning a value which would give different
+//! results than rebuilding it: when the file is damaged, was written by an incompatible version
+//! of the format, or was built by a different processing pipeline.
+//!
+//! ```
+//! # use fuzzywuzzy::index::{BkTree, Metric};
+//! # use fuzzywuzzy::persist::{self, Pipeline, PersistError};
+//! let tree = BkTree::from_choices(Metric::Levenshtein, vec!["kitten", "sitting", "mitten"]);
+//! let pipeline = Pipeline::current("raw choices");
+//! let bytes = persist::to_bytes(&tree, &pipeline);
+//!
+//! let loaded: BkTree = persist::from_bytes(&bytes, &pipeline, ()).unwrap();
+//! assert_eq!(loaded.find_within("kitten", 1).len(), 2);
+//!
+//! // Values built by another pipeline are rejected.
+//! let other = Pipeline::current("full_process");
+//! assert!(matches!(
+//!     persist::from_bytes::<BkTree>(&bytes, &other, ()),
+//!     Err(PersistError::PipelineMismatch { .. })
+//! ));
+//! ```
+
+use crate::index::FnvHasher;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::hash::Hasher;
+
+/// Identifies the format, at the start of every saved value.
+pub const MAGIC: [u8; 8] = *b"FUZZYWZY";
+
+/// The version of the format written by this version of the crate. Only this version can be loaded.
+pub const FORMAT_VERSION: u16 = 1;
+
+/// Describes everything which turns choices into the data stored in an index.
+///
+/// Processing depends on the version of this crate and on the Unicode tables of the
+/// normalization and segmentation crates. Processors and scorers are arbitrary functions, so
+/// they are described by a `tag` chosen by the caller, such as `"full_process, force_ascii"`.
+/// A saved value is only loaded by an identical pipeline.
+///
+/// The Unicode version of `char` methods such as `to_lowercase` comes with the compiler and
+/// cannot be queried on every supported Rust version, so it is not recorded. Mention the
+/// compiler in the `tag` when loading values built with another one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pipeline {
+    /// The version of this crate.
+    pub crate_version: String,
+    /// The Unicode version of the normalization tables, if enabled.
+    pub normalization_unicode_version: Option<(u8, u8, u8)>,
+    /// The Unicode version of the segmentation tables, if enabled.
+    pub segmentation_unicode_version: Option<(u8, u8, u8)>,
+    /// A description of the processing done by the caller.
+    pub tag: String,
+}
+
+impl Pipeline {
+    /// The pipeline of this build of the crate, with the given description of the caller's processing.
+    pub fn current(tag: &str) -> Pipeline {
+        Pipeline {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            #[cfg(feature = "normalization")]
+            normalization_unicode_version: Some(unicode_normalization::UNICODE_VERSION),
+            #[cfg(not(feature = "normalization"))]
+            normalization_unicode_version: None,
+            #[cfg(feature = "segmentation")]
+            segmentation_unicode_version: {
+                let (major, minor, update) = unicode_segmentation::UNICODE_VERSION;
+                Some((major as u8, minor as u8, update as u8))
+            },
+            #[cfg(not(feature = "segmentation"))]
+            segmentation_unicode_version: None,
+            tag: tag.to_string(),
+        }
+    }
+
+    fn encode(&self, encoder: &mut Encoder) {
+        let version = |encoder: &mut Encoder, (major, minor, update): (u8, u8, u8)| {
+            encoder.put_u8(major);
+            encoder.put_u8(minor);
+            encoder.put_u8(update);
+        };
+        encoder.put_str(&self.crate_version);
+        for optional in [
+            self.normalization_unicode_version,
+            self.segmentation_unicode_version,
+        ]
+        .iter()
+        {
+            encoder.put_bool(optional.is_some());
+            if let Some(v) = optional {
+                version(encoder, *v);
+            }
+        }
+        encoder.put_str(&self.tag);
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Pipeline, PersistError> {
+        fn version(decoder: &mut Decoder<'_>) -> Result<(u8, u8, u8), PersistError> {
+            Ok((decoder.get_u8()?, decoder.get_u8()?, decoder.get_u8()?))
+        }
+        fn optional(decoder: &mut Decoder<'_>) -> Result<Option<(u8, u8, u8)>, PersistError> {
+            if decoder.get_bool()? {
+                Ok(Some(version(decoder)?))
+            } else {
+                Ok(None)
+            }
+        }
+        Ok(Pipeline {
+            crate_version: decoder.get_string()?,
+            normalization_unicode_version: optional(decoder)?,
+            segmentation_unicode_version: optional(decoder)?,
+            tag: decoder.get_string()?,
+        })
+    }
+}
+
+/// The ways loading a saved value can fail.
+#[derive(Debug)]
+pub enum PersistError {
+    /// Reading or writing failed.
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// The data does not start with [MAGIC], so it was not saved by this crate.
+    BadMagic,
+    /// The data was saved in another version of the format.
+    UnsupportedVersion {
+        /// The version of the data.
+        found: u16,
+        /// [FORMAT_VERSION].
+        expected: u16,
+    },
+    /// The data was damaged: its checksum does not match its contents.
+    ChecksumMismatch,
+    /// The data is a saved value of another type.
+    KindMismatch {
+        /// The type of the saved value.
+        found: String,
+        /// The type being loaded.
+        expected: &'static str,
+    },
+    /// The data was built by another pipeline, so it may not match what the current one would build.
+    PipelineMismatch {
+        /// The pipeline the data was built by.
+        found: Box<Pipeline>,
+        /// The pipeline loading it.
+        expected: Box<Pipeline>,
+    },
+    /// The data ends before the value does.
+    Truncated,
+    /// The data has a valid checksum but does not describe a valid value.
+    Corrupt(&'static str),
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            PersistError::Io(e) => write!(f, "I/O error: {}", e),
+            PersistError::BadMagic => write!(f, "not a saved fuzzywuzzy value"),
+            PersistError::UnsupportedVersion { found, expected } => write!(
+                f,
+                "saved in format version {}, but only version {} is supported",
+                found, expected
+            ),
+            PersistError::ChecksumMismatch => write!(f, "checksum mismatch, the data is damaged"),
+            PersistError::KindMismatch { found, expected } => {
+                write!(f, "expected a saved {}, found a saved {}", expected, found)
+            }
+            PersistError::PipelineMismatch { found, expected } => write!(
+                f,
+                "built by pipeline {:?}, which does not match the current pipeline {:?}; rebuild it",
+                found, expected
+            ),
+            PersistError::Truncated => write!(f, "the data is truncated"),
+            PersistError::Corrupt(reason) => write!(f, "invalid data: {}", reason),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PersistError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PersistError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for PersistError {
+    fn from(e: std::io::Error) -> Self {
+        PersistError::Io(e)
+    }
+}
+
+/// A value which can be saved with [to_bytes] and loaded with [from_bytes].
+pub trait Persist: Sized {
+    /// The name of the type, recorded in the header so a value is only loaded as its own type.
+    const KIND: &'static str;
+    /// What is needed to rebuild the value besides its saved data, such as a processor function.
+    type Context;
+    /// Writes the value.
+    fn encode(&self, encoder: &mut Encoder);
+    /// Reads back a value written by [encode](Persist::encode).
+    fn decode(decoder: &mut Decoder<'_>, context: Self::Context) -> Result<Self, PersistError>;
+}
+
+/// Writes values in the format: integers as little endian, and `usize` as 64 bits.
+#[derive(Debug, Default)]
+pub struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    /// Creates an empty encoder.
+    pub fn new() -> Encoder {
+        Encoder::default()
+    }
+
+    /// Returns the bytes written.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Writes a `u8`.
+    pub fn put_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    /// Writes a `bool` as a byte.
+    pub fn put_bool(&mut self, value: bool) {
+        self.put_u8(u8::from(value));
+    }
+
+    /// Writes a `u16`.
+    pub fn put_u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Writes a `u32`.
+    pub fn put_u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Writes a `u64`.
+    pub fn put_u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Writes a `usize` as a `u64`.
+    pub fn put_usize(&mut self, value: usize) {
+        self.put_u64(value as u64);
+    }
+
+    /// Writes a `char` as a `u32`.
+    pub fn put_char(&mut self, value: char) {
+        self.put_u32(value as u32);
+    }
+
+    /// Writes raw bytes, preceded by their length.
+    pub fn put_bytes(&mut self, value: &[u8]) {
+        self.put_usize(value.len());
+        self.bytes.extend_from_slice(value);
+    }
+
+    /// Writes a string, preceded by its length in bytes.
+    pub fn put_str(&mut self, value: &str) {
+        self.put_bytes(value.as_bytes());
+    }
+
+    /// Writes an optional `usize`, with `u64::MAX` for `None`.
+    pub fn put_option_usize(&mut self, value: Option<usize>) {
+        self.put_u64(value.map_or(u64::MAX, |v| v as u64));
+    }
+}
+
+/// Reads values written by an [Encoder].
+#[derive(Debug)]
+pub struct Decoder<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    /// Creates a decoder reading `bytes`.
+    pub fn new(bytes: &'a [u8]) -> Decoder<'a> {
+        Decoder { bytes }
+    }
+
+    /// The number of bytes left to read.
+    pub fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns an error unless `condition` holds, to reject invalid values.
+    pub fn check(&self, condition: bool, reason: &'static str) -> Result<(), PersistError> {
+        if condition {
+            Ok(())
+        } else {
+            Err(PersistError::Corrupt(reason))
+        }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], PersistError> {
+        if n > self.bytes.len() {
+            return Err(PersistError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    /// Reads a `u8`.
+    pub fn get_u8(&mut self) -> Result<u8, PersistError> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Reads a `bool`.
+    pub fn get_bool(&mut self) -> Result<bool, PersistError> {
+        match self.get_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(PersistError::Corrupt("invalid bool")),
+        }
+    }
+
+    /// Reads a `u16`.
+    pub fn get_u16(&mut self) -> Result<u16, PersistError> {
+        let mut bytes = [0; 2];
+        bytes.copy_from_slice(self.take(2)?);
+        Ok(u16::from_le_bytes(bytes))
+    }
+
+    /// Reads a `u32`.
+    pub fn get_u32(&mut self) -> Result<u32, PersistError> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    /// Reads a `u64`.
+    pub fn get_u64(&mut self) -> Result<u64, PersistError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    /// Reads a `usize`, failing if it does not fit this platform.
+    pub fn get_usize(&mut self) -> Result<usize, PersistError> {
+        usize::try_from(self.get_u64()?).map_err(|_| PersistError::Corrupt("integer too large"))
+    }
+
+    /// Reads the length of a collection whose elements take at least one byte each.
+    ///
+    /// Lengths larger than the rest of the data are rejected, so a damaged length cannot
+    /// cause a huge allocation.
+    pub fn get_len(&mut self) -> Result<usize, PersistError> {
+        let len = self.get_usize()?;
+        if len > self.remaining() {
+            return Err(PersistError::Truncated);
+        }
+        Ok(len)
+    }
+
+    /// Reads a `char`.
+    pub fn get_char(&mut self) -> Result<char, PersistError> {
+        core::char::from_u32(self.get_u32()?).ok_or(PersistError::Corrupt("invalid char"))
+    }
+
+    /// Reads raw bytes written by [Encoder::put_bytes].
+    pub fn get_bytes(&mut self) -> Result<&'a [u8], PersistError> {
+        let len = self.get_len()?;
+        self.take(len)
+    }
+
+    /// Reads a string written by [Encoder::put_str], borrowing it from the data.
+    pub fn get_str(&mut self) -> Result<&'a str, PersistError> {
+        core::str::from_utf8(self.get_bytes()?).map_err(|_| PersistError::Corrupt("invalid UTF-8"))
+    }
+
+    /// Reads a string written by [Encoder::put_str].
+    pub fn get_string(&mut self) -> Result<String, PersistError> {
+        self.get_str().map(|s| s.to_string())
+    }
+
+    /// Reads an optional `usize` written by [Encoder::put_option_usize].
+    pub fn get_option_usize(&mut self) -> Result<Option<usize>, PersistError> {
+        match self.get_u64()? {
+            u64::MAX => Ok(None),
+            value => usize::try_from(value)
+                .map(Some)
+                .map_err(|_| PersistError::Corrupt("integer too large")),
+        }
+    }
+}
+
+/// Saves `value`, built by `pipeline`, to bytes.
+pub fn to_bytes<T: Persist>(value: &T, pipeline: &Pipeline) -> Vec<u8> {
+    write(T::KIND, pipeline, |encoder| value.encode(encoder))
+}
+
+/// Writes a value of type `kind` with `encode`, wrapped in the header and checksum.
+pub(crate) fn write<F: FnOnce(&mut Encoder)>(
+    kind: &str,
+    pipeline: &Pipeline,
+    encode: F,
+) -> Vec<u8> {
+    let mut encoder = Encoder::new();
+    encoder.bytes.extend_from_slice(&MAGIC);
+    encoder.put_u16(FORMAT_VERSION);
+    encoder.put_str(kind);
+    pipeline.encode(&mut encoder);
+    encode(&mut encoder);
+    let checksum = checksum(&encoder.bytes);
+    encoder.put_u64(checksum);
+    encoder.into_bytes()
+}
+
+/// Loads a value saved by [to_bytes], checking it was built by `pipeline`.
+pub fn from_bytes<T: Persist>(
+    bytes: &[u8],
+    pipeline: &Pipeline,
+    context: T::Context,
+) -> Result<T, PersistError> {
+    let mut decoder = open(bytes, T::KIND, pipeline)?;
+    let value = T::decode(&mut decoder, context)?;
+    decoder.check(decoder.remaining() == 0, "trailing data")?;
+    Ok(value)
+}
+
+/// Checks the header and checksum of saved data, and returns a decoder over the value.
+pub(crate) fn open<'a>(
+    bytes: &'a [u8],
+    kind: &'static str,
+    pipeline: &Pipeline,
+) -> Result<Decoder<'a>, PersistError> {
+    if bytes.len() < MAGIC.len() || bytes[..MAGIC.len()] != MAGIC {
+        return Err(PersistError::BadMagic);
+    }
+    let mut decoder = Decoder::new(&bytes[MAGIC.len()..]);
+    let version = decoder.get_u16()?;
+    if version != FORMAT_VERSION {
+        return Err(PersistError::UnsupportedVersion {
+            found: version,
+            expected: FORMAT_VERSION,
+        });
+    }
+    if bytes.len() < MAGIC.len() + 2 + 8 {
+        return Err(PersistError::Truncated);
+    }
+    let (contents, stored) = bytes.split_at(bytes.len() - 8);
+    let mut stored_checksum = [0; 8];
+    stored_checksum.copy_from_slice(stored);
+    if checksum(contents) != u64::from_le_bytes(stored_checksum) {
+        return Err(PersistError::ChecksumMismatch);
+    }
+    let mut decoder = Decoder::new(&contents[MAGIC.len() + 2..]);
+    let found = decoder.get_str()?;
+    if found != kind {
+        return Err(PersistError::KindMismatch {
+            found: found.to_string(),
+            expected: kind,
+        });
+    }
+    let found = Pipeline::decode(&mut decoder)?;
+    if &found != pipeline {
+        return Err(PersistError::PipelineMismatch {
+            found: Box::new(found),
+            expected: Box::new(pipeline.clone()),
+        });
+    }
+    Ok(decoder)
+}
+
+fn checksum(bytes: &[u8]) -> u64 {
+    let mut hasher = FnvHasher::default();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// Saves `value`, built by `pipeline`, to `writer`.
+#[cfg(feature = "std")]
+pub fn save<T: Persist, W: std::io::Write>(
+    value: &T,
+    pipeline: &Pipeline,
+    mut writer: W,
+) -> Result<(), PersistError> {
+    writer.write_all(&to_bytes(value, pipeline))?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Loads a value saved by [save] from `reader`, checking it was built by `pipeline`.
+#[cfg(feature = "std")]
+pub fn load<T: Persist, R: std::io::Read>(
+    mut reader: R,
+    pipeline: &Pipeline,
+    context: T::Context,
+) -> Result<T, PersistError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    from_bytes(&bytes, pipeline, context)
+}
+
+/// A read-only memory map of a file, to load [mapped](crate::index::MappedDictionary) values without copying them.
+///
+/// The pages of the file are shared by every process mapping it, and only read from disk when
+/// first used.
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub struct MappedFile(memmap2::Mmap);
+
+#[cfg(feature = "mmap")]
+impl MappedFile {
+    /// Maps the file at `path`.
+    ///
+    /// The file must not be modified while it is mapped: the format is checked when a value is
+    /// opened, not every time it is read.
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<MappedFile, PersistError> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: the map is read-only, and the caller is required not to modify the file while mapped.
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(MappedFile(map))
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl core::ops::Deref for MappedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::index::{BkTree, FuzzyDictionary, Metric, MinHashLsh, NgramIndex, SymSpell};
+    use crate::process::Corpus;
+    use crate::processor::FullProcess;
+    use crate::scorer::WRatio;
+    use crate::segmentation::CodePointSegmenter;
+
+    const CHOICES: [&str; 6] = [
+        "new york mets",
+        "new york yankees",
+        "atlanta braves",
+        "chicago cubs",
+        "chicago white sox",
+        "new york mets",
+    ];
+
+    fn round_trip<T: Persist>(value: &T, context: T::Context) -> T {
+        let pipeline = Pipeline::current("test");
+        let bytes = to_bytes(value, &pipeline);
+        #[cfg(feature = "std")]
+        {
+            let mut saved = Vec::new();
+            save(value, &pipeline, &mut saved).unwrap();
+            assert_eq!(bytes, saved);
+            load(&bytes[..], &pipeline, context).unwrap()
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            from_bytes(&bytes, &pipeline, context).unwrap()
+        }
+    }
+
+    #[test]
+    fn indexes_round_trip() {
+        let mut tree = BkTree::from_choices(Metric::DamerauLevenshtein, CHOICES.iter());
+        tree.remove(1);
+        assert_eq!(
+            round_trip(&tree, ()).find_within("new york", 6),
+            tree.find_within("new york", 6)
+        );
+
+        let mut ngrams = NgramIndex::from_choices(3, FullProcess::default(), CHOICES.iter());
+        ngrams.update(2, "atlanta falcons");
+        let loaded = round_trip(&ngrams, FullProcess::default());
+        let filter = crate::index::Filter::MinOverlap(2);
+        assert_eq!(
+            loaded.extract("new york", WRatio::default(), 0, filter),
+            ngrams.extract("new york", WRatio::default(), 0, filter)
+        );
+
+        let mut symspell =
+            SymSpell::from_counts(2, Metric::Levenshtein, CHOICES.iter().map(|c| (c, 1)));
+        symspell.remove("chicago cubs");
+        let loaded = round_trip(&symspell, ());
+        assert_eq!(
+            loaded.lookup("new yrok mets", 2),
+            symspell.lookup("new yrok mets", 2)
+        );
+        assert_eq!(loaded.count("new york mets"), Some(2));
+
+        let mut dictionary = FuzzyDictionary::from_words(CHOICES.iter());
+        dictionary.remove(0);
+        let loaded = round_trip(&dictionary, ());
+        assert_eq!(
+            loaded.extract("new york", 50),
+            dictionary.extract("new york", 50)
+        );
+        assert_eq!(loaded.len(), dictionary.len());
+
+        let mut lsh = MinHashLsh::new(CodePointSegmenter, 2, 8, 2);
+        for choice in CHOICES.iter() {
+            lsh.insert(choice);
+        }
+        let loaded = round_trip(&lsh, CodePointSegmenter);
+        assert_eq!(loaded.candidate_pairs(), lsh.candidate_pairs());
+
+        let corpus = Corpus::new(CHOICES.iter(), FullProcess::default());
+        assert_eq!(round_trip(&corpus, ()), corpus);
+    }
+
+    #[test]
+    fn invalid_data_is_rejected() {
+        let pipeline = Pipeline::current("test");
+        let corpus = Corpus::new(CHOICES.iter(), FullProcess::default());
+        let bytes = to_bytes(&corpus, &pipeline);
+
+        let error = from_bytes::<Corpus>(b"not an index", &pipeline, ()).unwrap_err();
+        assert!(matches!(error, PersistError::BadMagic));
+
+        let mut damaged = bytes.clone();
+        let middle = damaged.len() / 2;
+        damaged[middle] ^= 1;
+        let error = from_bytes::<Corpus>(&damaged, &pipeline, ()).unwrap_err();
+        assert!(matches!(error, PersistError::ChecksumMismatch));
+
+        let error = from_bytes::<Corpus>(&bytes[..bytes.len() - 1], &pipeline, ()).unwrap_err();
+        assert!(matches!(error, PersistError::ChecksumMismatch));
+
+        let mut newer = bytes.clone();
+        newer[MAGIC.len()] += 1;
+        let error = from_bytes::<Corpus>(&newer, &pipeline, ()).unwrap_err();
+        assert!(matches!(
+            error,
+            PersistError::UnsupportedVersion {
+                found: 2,
+                expected: 1
+            }
+        ));
+
+        let error = from_bytes::<BkTree>(&bytes, &pipeline, ()).unwrap_err();
+        assert!(matches!(
+            error,
+            PersistError::KindMismatch {
+                expected: "BkTree",
+                ..
+            }
+        ));
+
+        let mut older = pipeline.clone();
+        older.crate_version = "0.0.1".to_string();
+        let error = from_bytes::<Corpus>(&bytes, &older, ()).unwrap_err();
+        assert!(matches!(error, PersistError::PipelineMismatch { .. }));
+        assert!(error.to_string().contains("rebuild"));
+    }
+}
@@ -1,5 +1,9 @@
 //! Contains comparison primitives used to build up the rest of the library.
 
+use crate::utils;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
 /// Returns list of triples describing matching sequences.
 ///
 /// The first number is the index in the first string of the beginning of the match.
@@ -19,8 +23,23 @@
 /// assert_eq!(get_matching_blocks(&CodePointSegmenter.segment("किमप"), &CodePointSegmenter.segment("किमपि")), vec![(0, 0, 4), (4, 5, 0)]);
 /// assert_eq!(get_matching_blocks(&GraphemeSegmenter.segment("किमप"), &GraphemeSegmenter.segment("किमपि")), vec![(0, 0, 2), (3, 3, 0)]);
 /// ```
-#[allow(clippy::many_single_char_names)]
 pub fn get_matching_blocks<T: Eq>(a: &[T], b: &[T]) -> Vec<(usize, usize, usize)> {
+    get_matching_blocks_with(a, b, find_longest_match)
+}
+
+/// [get_matching_blocks], using `find_longest_match` to find each block.
+///
+/// `find_longest_match` must behave like [find_longest_match]; this lets specialized
+/// implementations (such as the ASCII fast path) share the block bookkeeping.
+#[allow(clippy::many_single_char_names)]
+pub(crate) fn get_matching_blocks_with<T, F>(
+    a: &[T],
+    b: &[T],
+    find_longest_match: F,
+) -> Vec<(usize, usize, usize)>
+where
+    F: FnMut(&[T], &[T], usize, usize, usize, usize) -> MatchingStreak,
+{
     let flipped;
     let (shorter, len1, longer, len2) = {
         let a_len = a.len();
@@ -33,29 +52,14 @@ pub fn get_matching_blocks<T: Eq>(a: &[T], b: &[T]) -> Vec<(usize, usize, usize)
             (b, b_len, a, a_len)
         }
     };
-    // https://github.com/python-git/python/blob/master/Lib/difflib.py#L461
-    let mut queue = vec![(0, len1, 0, len2)];
     let mut matching_blocks = Vec::new();
-    while let Some((low1, high1, low2, high2)) = queue.pop() {
-        // TODO: I'd like to convert this function to use MatchingStreak's internally.
-        // It might make it more clear to be comparing low1 < streak.idx1 instead of low1 < i
-        let MatchingStreak {
-            idx1: i,
-            idx2: j,
-            size: k,
-        } = find_longest_match(shorter, longer, low1, high1, low2, high2);
-        debug_assert!(i <= shorter.len());
-        debug_assert!(j <= longer.len());
-        if k != 0 {
-            matching_blocks.push((i, j, k));
-            if low1 < i && low2 < j {
-                queue.push((low1, i, low2, j));
-            }
-            if i + k < high1 && j + k < high2 {
-                queue.push((i + k, high1, j + k, high2));
-            }
-        }
-    }
+    matching_blocks_into(
+        shorter,
+        longer,
+        find_longest_match,
+        &mut Vec::new(),
+        &mut matching_blocks,
+    );
     matching_blocks.sort_unstable();
     let (mut i1, mut j1, mut k1) = (0, 0, 0);
     let mut non_adjacent = Vec::new();
@@ -84,6 +88,65 @@ pub fn get_matching_blocks<T: Eq>(a: &[T], b: &[T]) -> Vec<(usize, usize, usize)
         .collect()
 }
 
+/// Collects the matching blocks of `shorter` in `longer` into `blocks`, neither sorted nor
+/// collapsed, using `queue` as scratch space.
+#[allow(clippy::many_single_char_names)]
+pub(crate) fn matching_blocks_into<T, F>(
+    shorter: &[T],
+    longer: &[T],
+    mut find_longest_match: F,
+    queue: &mut Vec<(usize, usize, usize, usize)>,
+    blocks: &mut Vec<(usize, usize, usize)>,
+) where
+    F: FnMut(&[T], &[T], usize, usize, usize, usize) -> MatchingStreak,
+{
+    // https://github.com/python-git/python/blob/master/Lib/difflib.py#L461
+    blocks.clear();
+    queue.clear();
+    queue.push((0, shorter.len(), 0, longer.len()));
+    while let Some((low1, high1, low2, high2)) = queue.pop() {
+        // TODO: I'd like to convert this function to use MatchingStreak's internally.
+        // It might make it more clear to be comparing low1 < streak.idx1 instead of low1 < i
+        let MatchingStreak {
+            idx1: i,
+            idx2: j,
+            size: k,
+        } = find_longest_match(shorter, longer, low1, high1, low2, high2);
+        debug_assert!(i <= shorter.len());
+        debug_assert!(j <= longer.len());
+        if k != 0 {
+            blocks.push((i, j, k));
+            if low1 < i && low2 < j {
+                queue.push((low1, i, low2, j));
+            }
+            if i + k < high1 && j + k < high2 {
+                queue.push((i + k, high1, j + k, high2));
+            }
+        }
+    }
+}
+
+/// Returns the ratio of the number of matching segments to the total number of segments, as a number between 0 and 100.
+///
+/// This is [fuzz::ratio](crate::fuzz::ratio) for already segmented strings, so it
+/// works on any [Segmenter](crate::segmentation::Segmenter) output, including the
+/// dense IDs produced by an [Interner](crate::interner::Interner).
+///
+/// ```
+/// # use fuzzywuzzy::segmentation::{Segmenter, CodePointSegmenter, GraphemeSegmenter};
+/// # use fuzzywuzzy::primitives::ratio;
+/// assert_eq!(ratio(&CodePointSegmenter.segment("cd"), &CodePointSegmenter.segment("abcd")), 67);
+/// assert_eq!(ratio(&CodePointSegmenter.segment("किमप"), &CodePointSegmenter.segment("किमपि")), 89);
+/// assert_eq!(ratio(&GraphemeSegmenter.segment("किमप"), &GraphemeSegmenter.segment("किमपि")), 67);
+/// assert_eq!(ratio::<char>(&[], &[]), 100);
+/// ```
+pub fn ratio<T: Eq>(a: &[T], b: &[T]) -> u8 {
+    check_trivial!(a, b);
+    let matches: usize = get_matching_blocks(a, b).iter().map(|&(_, _, s)| s).sum();
+    let sumlength: f32 = (a.len() + b.len()) as f32;
+    utils::round_to_u8(f64::from(100.0 * (2.0 * (matches as f32) / sumlength)))
+}
+
 /// Represents a matching streak of characters between two strings.
 ///
 /// See [find_longest_match] for details.
@@ -136,15 +199,17 @@ pub fn find_longest_match<T: Eq>(
     // and return the earliest match for a given block size and index.
     //
     // [1] - because of the calling context, we actually use `high1 - low1`
-    // for the length because we might be indexing into the middle of `shorter`
+    // for the length because we might be indexing into the middle of `shorter`.
+    // `shorter[low1..high1]` is not necessarily shorter than `longer[low2..high2]`
+    // though, so no streak can be longer than the smaller of the two ranges.
     debug_assert!(low1 <= high1);
     debug_assert!(low2 <= high2);
     debug_assert!(high1 <= shorter.len());
     debug_assert!(high2 <= longer.len());
-    debug_assert!(high1 - low1 <= high2 - low2);
     let longsub = &longer[low2..high2];
     let len = high1 - low1;
-    for size in (1..len + 1).rev() {
+    let max_size = core::cmp::min(len, high2 - low2);
+    for size in (1..max_size + 1).rev() {
         for start in 0..len - size + 1 {
             let shortsub = &shorter[low1 + start..low1 + start + size];
             for window_start in 0..((high2 - low2) - size + 1) {
@@ -165,3 +230,423 @@ pub fn find_longest_match<T: Eq>(
         size: 0,
     }
 }
+
+/// Returns the [Levenshtein distance](https://en.wikipedia.org/wiki/Levenshtein_distance)
+/// between two sequences: the minimum number of single-unit insertions, deletions and
+/// substitutions required to turn `a` into `b`.
+///
+/// ```
+/// # use fuzzywuzzy::segmentation::{Segmenter, CodePointSegmenter};
+/// # use fuzzywuzzy::primitives::levenshtein;
+/// let kitten = CodePointSegmenter.segment("kitten");
+/// let sitting = CodePointSegmenter.segment("sitting");
+/// assert_eq!(levenshtein(&kitten, &sitting), 3);
+/// assert_eq!(levenshtein(&kitten, &kitten), 0);
+/// assert_eq!(levenshtein(&kitten, &[]), 6);
+/// ```
+pub fn levenshtein<T: Eq>(a: &[T], b: &[T]) -> usize {
+    let max_distance = core::cmp::max(a.len(), b.len());
+    bounded_levenshtein(a, b, max_distance).expect("distance never exceeds the longer length")
+}
+
+/// Returns the Levenshtein distance between two sequences if it is at most `max_distance`.
+///
+/// Rather than filling the full `a.len() * b.len()` matrix, only the diagonal band
+/// of width `2 * max_distance + 1` is computed (Ukkonen's cutoff), and the computation
+/// stops as soon as every cell of a row exceeds `max_distance`. Common prefixes and
+/// suffixes are stripped before the band is computed.
+///
+/// Returns `None` if the distance is greater than `max_distance`.
+///
+/// ```
+/// # use fuzzywuzzy::segmentation::{Segmenter, CodePointSegmenter};
+/// # use fuzzywuzzy::primitives::bounded_levenshtein;
+/// let kitten = CodePointSegmenter.segment("kitten");
+/// let sitting = CodePointSegmenter.segment("sitting");
+/// assert_eq!(bounded_levenshtein(&kitten, &sitting, 3), Some(3));
+/// assert_eq!(bounded_levenshtein(&kitten, &sitting, 2), None);
+/// // The length difference alone exceeds the bound.
+/// assert_eq!(bounded_levenshtein(&kitten, &CodePointSegmenter.segment("kit"), 2), None);
+/// ```
+pub fn bounded_levenshtein<T: Eq>(a: &[T], b: &[T], max_distance: usize) -> Option<usize> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+    let (shorter, longer) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    let (n, m) = (shorter.len(), longer.len());
+    if m - n > max_distance {
+        return None;
+    }
+    if n == 0 {
+        return Some(m);
+    }
+    // The distance can never exceed `m`, so a wider band is wasted work.
+    let k = core::cmp::min(max_distance, m);
+    // Any value above `k` is equivalent, so cells outside of the band hold `k + 1`.
+    let outside = k + 1;
+    let mut prev: Vec<usize> = (0..=m).map(|j| if j <= k { j } else { outside }).collect();
+    let mut curr = vec![outside; m + 1];
+    for i in 1..=n {
+        let low = core::cmp::max(1, i.saturating_sub(k));
+        let high = core::cmp::min(m, i + k);
+        curr[low - 1] = if low == 1 && i <= k { i } else { outside };
+        let mut row_min = curr[low - 1];
+        for j in low..=high {
+            let substitution = prev[j - 1] + usize::from(shorter[i - 1] != longer[j - 1]);
+            let deletion = prev[j] + 1;
+            let insertion = curr[j - 1] + 1;
+            let cell = core::cmp::min(core::cmp::min(substitution, deletion), insertion);
+            let cell = core::cmp::min(cell, outside);
+            curr[j] = cell;
+            row_min = core::cmp::min(row_min, cell);
+        }
+        // The next row reads one cell past this row's band, which must read as outside.
+        if high < m {
+            curr[high + 1] = outside;
+        }
+        if row_min > k {
+            return None;
+        }
+        core::mem::swap(&mut prev, &mut curr);
+    }
+    let distance = prev[m];
+    if distance <= max_distance {
+        Some(distance)
+    } else {
+        None
+    }
+}
+
+/// Returns the (unrestricted) [Damerau-Levenshtein distance](https://en.wikipedia.org/wiki/Damerau%E2%80%93Levenshtein_distance)
+/// between two sequences: like [levenshtein], but transposing two adjacent units also counts as a single edit.
+///
+/// Unlike the "optimal string alignment" variant, substrings may be edited again after a
+/// transposition, which makes this a true metric (it satisfies the triangle inequality),
+/// as required by metric indexes.
+///
+/// ```
+/// # use fuzzywuzzy::segmentation::{Segmenter, CodePointSegmenter};
+/// # use fuzzywuzzy::primitives::{damerau_levenshtein, levenshtein};
+/// let ca = CodePointSegmenter.segment("ca");
+/// let abc = CodePointSegmenter.segment("abc");
+/// assert_eq!(damerau_levenshtein(&ca, &abc), 2);
+/// assert_eq!(levenshtein(&ca, &abc), 3);
+/// let teh = CodePointSegmenter.segment("teh");
+/// let the = CodePointSegmenter.segment("the");
+/// assert_eq!(damerau_levenshtein(&teh, &the), 1);
+/// ```
+pub fn damerau_levenshtein<T: Ord + Clone>(a: &[T], b: &[T]) -> usize {
+    let (n, m) = (a.len(), b.len());
+    let max_distance = n + m;
+    // `d[i + 1][j + 1]` is the distance between `a[..i]` and `b[..j]`; the extra
+    // first row and column hold `max_distance` as a sentinel for transpositions.
+    let width = m + 2;
+    let mut d = vec![0; (n + 2) * width];
+    d[0] = max_distance;
+    for i in 0..=n {
+        d[(i + 1) * width] = max_distance;
+        d[(i + 1) * width + 1] = i;
+    }
+    for j in 0..=m {
+        d[j + 1] = max_distance;
+        d[width + j + 1] = j;
+    }
+    // The last row of `a` in which each unit was seen.
+    let mut last_row: BTreeMap<T, usize> = BTreeMap::new();
+    for i in 1..=n {
+        // The last column of `b` in this row in which the units matched.
+        let mut last_match_col = 0;
+        for j in 1..=m {
+            let i1 = last_row.get(&b[j - 1]).copied().unwrap_or(0);
+            let j1 = last_match_col;
+            let cost = if a[i - 1] == b[j - 1] {
+                last_match_col = j;
+                0
+            } else {
+                1
+            };
+            let substitution = d[i * width + j] + cost;
+            let insertion = d[(i + 1) * width + j] + 1;
+            let deletion = d[i * width + j + 1] + 1;
+            let transposition = d[i1 * width + j1] + (i - i1 - 1) + 1 + (j - j1 - 1);
+            d[(i + 1) * width + j + 1] = core::cmp::min(
+                core::cmp::min(substitution, insertion),
+                core::cmp::min(deletion, transposition),
+            );
+        }
+        last_row.insert(a[i - 1].clone(), i);
+    }
+    d[(n + 1) * width + m + 1]
+}
+
+/// Returns the [Jaro similarity](https://en.wikipedia.org/wiki/Jaro%E2%80%93Winkler_distance)
+/// of two sequences, between 0 and 1.
+///
+/// Units match when they are equal and less than half the longer length apart; the similarity
+/// averages the fraction of each sequence which matches and the fraction of matches which are
+/// in the same order. Two empty sequences are identical.
+///
+/// ```
+/// # use fuzzywuzzy::segmentation::{Segmenter, CodePointSegmenter};
+/// # use fuzzywuzzy::primitives::jaro;
+/// let martha = CodePointSegmenter.segment("martha");
+/// let marhta = CodePointSegmenter.segment("marhta");
+/// assert_eq!((jaro(&martha, &marhta) * 1000.0).round(), 944.0);
+/// assert_eq!(jaro(&martha, &martha), 1.0);
+/// assert_eq!(jaro(&martha, &[]), 0.0);
+/// ```
+pub fn jaro<T: Eq>(a: &[T], b: &[T]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let window = (core::cmp::max(a.len(), b.len()) / 2).saturating_sub(1);
+    let mut b_matched = vec![false; b.len()];
+    // The units of `a` which match, in order.
+    let mut a_matches = Vec::new();
+    for (i, x) in a.iter().enumerate() {
+        let low = i.saturating_sub(window);
+        let high = core::cmp::min(b.len(), i + window + 1);
+        if let Some(j) = (low..high).find(|&j| !b_matched[j] && b[j] == *x) {
+            b_matched[j] = true;
+            a_matches.push(x);
+        }
+    }
+    let matches = a_matches.len();
+    if matches == 0 {
+        return 0.0;
+    }
+    let b_matches = b.iter().zip(b_matched).filter(|&(_, m)| m).map(|(y, _)| y);
+    let transpositions = a_matches
+        .iter()
+        .zip(b_matches)
+        .filter(|&(x, y)| *x != y)
+        .count()
+        / 2;
+    let matches = matches as f64;
+    (matches / a.len() as f64
+        + matches / b.len() as f64
+        + (matches - transpositions as f64) / matches)
+        / 3.0
+}
+
+/// Returns the Jaro-Winkler similarity of two sequences, between 0 and 1: their [jaro]
+/// similarity, raised towards 1 by a tenth of the remainder for each unit of common prefix,
+/// up to four.
+///
+/// ```
+/// # use fuzzywuzzy::segmentation::{Segmenter, CodePointSegmenter};
+/// # use fuzzywuzzy::primitives::jaro_winkler;
+/// let similarity = |a, b| {
+///     let s = jaro_winkler(&CodePointSegmenter.segment(a), &CodePointSegmenter.segment(b));
+///     (s * 1000.0).round()
+/// };
+/// assert_eq!(similarity("martha", "marhta"), 961.0);
+/// assert_eq!(similarity("dwayne", "duane"), 840.0);
+/// assert_eq!(similarity("dixon", "dicksonx"), 813.0);
+/// ```
+pub fn jaro_winkler<T: Eq>(a: &[T], b: &[T]) -> f64 {
+    let similarity = jaro(a, b);
+    let prefix = a.iter().zip(b).take(4).take_while(|(x, y)| x == y).count();
+    similarity + prefix as f64 * 0.1 * (1.0 - similarity)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The textbook full-matrix algorithm, used as a reference.
+    fn full_matrix_levenshtein(a: &[char], b: &[char]) -> usize {
+        let mut prev: Vec<usize> = (0..=b.len()).collect();
+        for i in 1..=a.len() {
+            let mut curr = vec![i; b.len() + 1];
+            for j in 1..=b.len() {
+                let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+                curr[j] = (prev[j - 1] + cost).min(prev[j] + 1).min(curr[j - 1] + 1);
+            }
+            prev = curr;
+        }
+        prev[b.len()]
+    }
+
+    /// The unrestricted Damerau-Levenshtein distances from `a` to every sequence over
+    /// `alphabet` within `depth` insertions, deletions, substitutions and transpositions of
+    /// adjacent units, found by breadth-first search. Used as a reference.
+    fn brute_force_damerau_levenshtein(
+        a: &[char],
+        alphabet: &[char],
+        depth: usize,
+    ) -> BTreeMap<Vec<char>, usize> {
+        let mut distances = BTreeMap::new();
+        distances.insert(a.to_vec(), 0);
+        let mut frontier = vec![a.to_vec()];
+        for distance in 1..=depth {
+            let mut neighbours = Vec::new();
+            for s in frontier.iter() {
+                for i in 0..=s.len() {
+                    for &c in alphabet {
+                        let mut t = s.clone();
+                        t.insert(i, c);
+                        neighbours.push(t);
+                    }
+                }
+                for i in 0..s.len() {
+                    let mut t = s.clone();
+                    t.remove(i);
+                    neighbours.push(t);
+                    for &c in alphabet {
+                        let mut t = s.clone();
+                        t[i] = c;
+                        neighbours.push(t);
+                    }
+                    if i + 1 < s.len() {
+                        let mut t = s.clone();
+                        t.swap(i, i + 1);
+                        neighbours.push(t);
+                    }
+                }
+            }
+            frontier.clear();
+            for t in neighbours {
+                if let alloc::collections::btree_map::Entry::Vacant(entry) = distances.entry(t) {
+                    frontier.push(entry.key().clone());
+                    entry.insert(distance);
+                }
+            }
+        }
+        distances
+    }
+
+    #[test]
+    fn get_matching_blocks_agrees_with_utils() {
+        use rand::{Rng, SeedableRng};
+        let mut rng = rand::rngs::StdRng::seed_from_u64(29);
+        // Includes ranges where the "shorter" side is longer than the "longer" side, e.g. "bcabb" vs "acbbb".
+        for _ in 0..2000 {
+            let mut random_string = |len: usize| -> String {
+                (0..len)
+                    .map(|_| ['a', 'b', 'c'][rng.gen_range(0..3)])
+                    .collect()
+            };
+            let (a, b) = (random_string(6), random_string(6));
+            let (ca, cb): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+            assert_eq!(
+                get_matching_blocks(&ca, &cb),
+                utils::get_matching_blocks(&a, &b),
+                "{} {}",
+                a,
+                b
+            );
+        }
+    }
+
+    #[test]
+    fn damerau_levenshtein_agrees_with_brute_force() {
+        use rand::{Rng, SeedableRng};
+        let alphabet = ['a', 'b', 'c'];
+        // Every sequence of up to four units over the alphabet.
+        let mut words = vec![Vec::new()];
+        let mut start = 0;
+        for _ in 0..4 {
+            let end = words.len();
+            for i in start..end {
+                for &c in alphabet.iter() {
+                    let mut word = words[i].clone();
+                    word.push(c);
+                    words.push(word);
+                }
+            }
+            start = end;
+        }
+        let distances: Vec<Vec<usize>> = words
+            .iter()
+            .map(|a| words.iter().map(|b| damerau_levenshtein(a, b)).collect())
+            .collect();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(31);
+        for _ in 0..20 {
+            let i = rng.gen_range(0..words.len());
+            // No two words are more than four edits apart.
+            let expected = brute_force_damerau_levenshtein(&words[i], &alphabet, 4);
+            for (j, b) in words.iter().enumerate() {
+                assert_eq!(distances[i][j], expected[b], "{:?} {:?}", words[i], b);
+            }
+        }
+        for a in 0..words.len() {
+            for b in 0..words.len() {
+                assert_eq!(distances[a][b], distances[b][a]);
+                for c in 0..words.len() {
+                    assert!(
+                        distances[a][c] <= distances[a][b] + distances[b][c],
+                        "{:?} {:?} {:?}",
+                        words[a],
+                        words[b],
+                        words[c]
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bounded_levenshtein_agrees_with_full_matrix() {
+        let words = [
+            "",
+            "a",
+            "ab",
+            "abc",
+            "kitten",
+            "sitting",
+            "saturday",
+            "sunday",
+            "rosettacode",
+            "raisethysword",
+            "スマホでchance",
+            "chance",
+            "fòllòwbáck",
+            "followback",
+        ];
+        for a in words.iter() {
+            for b in words.iter() {
+                let a: Vec<char> = a.chars().collect();
+                let b: Vec<char> = b.chars().collect();
+                let expected = full_matrix_levenshtein(&a, &b);
+                assert_eq!(levenshtein(&a, &b), expected);
+                for k in 0..expected + 2 {
+                    let bounded = bounded_levenshtein(&a, &b, k);
+                    if expected <= k {
+                        assert_eq!(bounded, Some(expected));
+                    } else {
+                        assert_eq!(bounded, None);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn jaro_is_a_symmetric_similarity() {
+        use rand::{Rng, SeedableRng};
+        let mut rng = rand::rngs::StdRng::seed_from_u64(50);
+        for _ in 0..2000 {
+            let random_string = |rng: &mut rand::rngs::StdRng| -> Vec<char> {
+                let len = rng.gen_range(0..8);
+                (0..len)
+                    .map(|_| ['a', 'b', 'c', 'd'][rng.gen_range(0..4)])
+                    .collect()
+            };
+            let (a, b) = (random_string(&mut rng), random_string(&mut rng));
+            let (j, jw) = (jaro(&a, &b), jaro_winkler(&a, &b));
+            assert!((j - jaro(&b, &a)).abs() < 1e-12, "{:?} {:?}", a, b);
+            assert!((0.0..=1.0).contains(&j) && (j..=1.0).contains(&jw));
+            assert_eq!(j == 1.0, a == b, "{:?} {:?}", a, b);
+        }
+    }
+}
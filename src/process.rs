@@ -1,14 +1,29 @@
 //! Convenience methods to process fuzzy matching queries for common use cases.
 
+use crate::ascii;
+use crate::normalization;
+use crate::persist::{Decoder, Encoder, Persist, PersistError};
+use crate::primitives;
+use crate::processor::Processor;
+use crate::scorer::Scorer;
+use crate::utils;
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
 /// Score multiple options against a base query string and return all exceeding a cutoff.
 ///
 /// Returns a Vec with the options and their match score if their score is above the cutoff.
 /// Results are configurable using custom text processors and scorers.
-/// Good default choices are `utils::full_process` as the processor, `fuzz:wratio` as the scorer, and zero as the score_cutoff.
+/// Good default choices are [FullProcess](crate::processor::FullProcess) as the processor, [WRatio](crate::scorer::WRatio) as the scorer, and zero as the score_cutoff.
+///
+/// The query is processed and [preprocessed](Scorer::preprocess) once, every choice once.
 ///
 /// ```
+/// # use fuzzywuzzy::normalization::PassthroughNormalizer;
 /// # use fuzzywuzzy::process::extract_without_order;
-/// # use fuzzywuzzy::fuzz::wratio;
+/// # use fuzzywuzzy::scorer::WRatio;
 /// let choices = vec![
 ///     "new york mets vs chicago cubs",
 ///     "chicago cubs vs chicago white sox",
@@ -25,8 +40,8 @@
 ///     extract_without_order(
 ///         "brave new cubs",
 ///         choices,
-///         |s, b| s.into(), // an alternative to full_process.
-///         &wratio,
+///         PassthroughNormalizer, // an alternative to FullProcess.
+///         WRatio::default(),
 ///         0),
 ///     expected_results);
 /// ```
@@ -35,15 +50,15 @@ pub fn extract_without_order<I, T, P, S>(
     choices: I,
     processor: P,
     scorer: S,
-    score_cutoff: u8,
-) -> Vec<(String, u8)>
+    score_cutoff: S::Score,
+) -> Vec<(String, S::Score)>
 where
     I: IntoIterator<Item = T>,
     T: AsRef<str>,
-    P: Fn(&str, bool) -> String,
-    S: Fn(&str, &str, bool, bool) -> u8,
+    P: Processor,
+    S: Scorer,
 {
-    let processed_query: String = processor(query, false);
+    let processed_query = processor.process(query);
     if processed_query.is_empty() {
         // TODO: Make warning configurable, instead of being printed by default.
         // println!("Applied processor reduces input query to empty string, all comparisons will have score 0. [Query: '{0}']", processed_query.as_str());
@@ -51,12 +66,12 @@ where
 
     // See: https://github.com/logannc/fuzzyrusty/issues/6
     // TODO: Check if scorer in list of known processor functions to avoid calling utils::full_process multiple times.
-    // TODO: Only process the query once instead of for every choice.
 
+    let processed_query = scorer.preprocess(&processed_query);
     let mut results = vec![];
     for choice in choices {
-        let processed: String = processor(choice.as_ref(), false);
-        let score: u8 = scorer(processed_query.as_str(), processed.as_str(), true, true);
+        let processed = processor.process(choice.as_ref());
+        let score = scorer.score_processed(&processed_query, &scorer.preprocess(&processed));
         if score >= score_cutoff {
             results.push((choice.as_ref().to_string(), score))
         }
@@ -73,8 +88,8 @@ where
 ///
 /// ```
 /// # use fuzzywuzzy::process::extract_one;
-/// use fuzzywuzzy::fuzz::wratio;
-/// use fuzzywuzzy::utils::full_process;
+/// use fuzzywuzzy::scorer::WRatio;
+/// use fuzzywuzzy::processor::FullProcess;
 /// let choices = vec![
 ///     "new york mets vs chicago cubs",
 ///     "chicago cubs vs chicago white sox",
@@ -84,8 +99,8 @@ where
 /// assert_eq!(
 ///    extract_one("brave new cubs",
 ///       choices.iter(),
-///       &full_process,
-///       &wratio,
+///       FullProcess::default(),
+///       WRatio::default(),
 ///       0).unwrap().0,
 ///    choices[0]
 /// );
@@ -93,8 +108,8 @@ where
 ///    extract_one(
 ///       "new york mets at atlanta braves",
 ///       choices.iter(),
-///       &full_process,
-///       &wratio,
+///       FullProcess::default(),
+///       WRatio::default(),
 ///       0).unwrap().0,
 ///    choices[3]
 /// );
@@ -102,8 +117,8 @@ where
 ///    extract_one(
 ///       "philadelphia phillies at atlanta braves",
 ///       choices.iter(),
-///       &full_process,
-///       &wratio,
+///       FullProcess::default(),
+///       WRatio::default(),
 ///       0).unwrap().0,
 ///    choices[2]
 /// );
@@ -111,8 +126,8 @@ where
 ///    extract_one(
 ///       "atlanta braves at philadelphia phillies",
 ///       choices.iter(),
-///       &full_process,
-///       &wratio,
+///       FullProcess::default(),
+///       WRatio::default(),
 ///       0).unwrap().0,
 ///    choices[2]
 /// );
@@ -120,8 +135,8 @@ where
 ///    extract_one(
 ///       "chicago cubs vs new york mets",
 ///       choices.iter(),
-///       &full_process,
-///       &wratio,
+///       FullProcess::default(),
+///       WRatio::default(),
 ///       0).unwrap().0,
 ///    choices[0]
 /// );
@@ -131,13 +146,13 @@ pub fn extract_one<I, T, P, S>(
     choices: I,
     processor: P,
     scorer: S,
-    score_cutoff: u8,
-) -> Option<(String, u8)>
+    score_cutoff: S::Score,
+) -> Option<(String, S::Score)>
 where
     I: IntoIterator<Item = T>,
     T: AsRef<str>,
-    P: Fn(&str, bool) -> String,
-    S: Fn(&str, &str, bool, bool) -> u8,
+    P: Processor,
+    S: Scorer,
 {
     let best = extract_without_order(query, choices, processor, scorer, score_cutoff);
     if best.is_empty() {
@@ -153,5 +168,846 @@ where
         // original ordering of `choices` is returned (as this is the behavior of fuzzywuzzy).
         .rev()
         .cloned()
-        .max_by(|(_, acc_score), (_, score)| acc_score.cmp(score))
+        .max_by(|(_, acc_score), (_, score)| compare(acc_score, score))
+}
+
+/// Score multiple options against a base query string by [levenshtein_ratio](crate::fuzz::levenshtein_ratio)
+/// and return all meeting a cutoff.
+///
+/// Returns the same results as
+/// `extract_without_order(query, choices, processor, LevenshteinRatio, score_cutoff)`,
+/// but the `score_cutoff` is translated into a maximum edit distance for each choice so only a diagonal
+/// band of the distance matrix is computed and choices are rejected as soon as they exceed it.
+/// The query is processed once rather than once per choice.
+///
+/// ```
+/// # use fuzzywuzzy::process::extract_levenshtein;
+/// # use fuzzywuzzy::processor::FullProcess;
+/// let choices = vec!["kitten", "sitting", "mitten", "smitten", "knitting"];
+/// assert_eq!(
+///     extract_levenshtein("kitten", &choices, FullProcess::default(), 70),
+///     vec![("kitten".to_string(), 100u8), ("mitten".to_string(), 83u8), ("smitten".to_string(), 71u8)]);
+/// ```
+pub fn extract_levenshtein<I, T, P>(
+    query: &str,
+    choices: I,
+    processor: P,
+    score_cutoff: u8,
+) -> Vec<(String, u8)>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<str>,
+    P: Processor,
+{
+    let processed_query = processor.process(query);
+    let query_chars: Vec<char> = processed_query.chars().collect();
+    let mut results = vec![];
+    for choice in choices {
+        let processed = processor.process(choice.as_ref());
+        let length = core::cmp::max(query_chars.len(), processed.chars().count());
+        let max_distance = utils::max_distance_for_ratio(length, score_cutoff);
+        let distance = if processed_query.is_ascii() && processed.is_ascii() {
+            ascii::bounded_levenshtein(
+                processed_query.as_bytes(),
+                processed.as_bytes(),
+                max_distance,
+            )
+        } else {
+            let chars: Vec<char> = processed.chars().collect();
+            primitives::bounded_levenshtein(&query_chars, &chars, max_distance)
+        };
+        if let Some(distance) = distance {
+            let score = utils::ratio_from_distance(distance, length);
+            results.push((choice.as_ref().to_string(), score))
+        }
+    }
+    results
+}
+
+/// A collection of choices processed once, ahead of any query.
+///
+/// [extract_without_order] processes every choice again for every query. When the same
+/// choices are searched many times, a corpus keeps them next to their processed form, and
+/// can be [saved](crate::persist) so they are not even processed at startup.
+///
+/// ```
+/// # use fuzzywuzzy::process::Corpus;
+/// # use fuzzywuzzy::scorer::WRatio;
+/// # use fuzzywuzzy::processor::FullProcess;
+/// let corpus = Corpus::new(vec!["New York Mets", "Atlanta Braves"], FullProcess::default());
+/// assert_eq!(corpus.get(0), Some(("New York Mets", "new york mets")));
+/// assert_eq!(
+///     corpus.extract_one("new york", FullProcess::default(), WRatio::default(), 0),
+///     Some(("New York Mets".to_string(), 90u8)));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Corpus {
+    choices: Vec<String>,
+    processed: Vec<String>,
+}
+
+impl Corpus {
+    /// Processes every choice with `processor`.
+    pub fn new<I, T, P>(choices: I, processor: P) -> Corpus
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+        P: Processor,
+    {
+        let mut corpus = Corpus::default();
+        for choice in choices {
+            corpus.choices.push(choice.as_ref().to_string());
+            corpus
+                .processed
+                .push(processor.process(choice.as_ref()).into_owned());
+        }
+        corpus
+    }
+
+    /// The number of choices.
+    pub fn len(&self) -> usize {
+        self.choices.len()
+    }
+
+    /// Whether there are no choices.
+    pub fn is_empty(&self) -> bool {
+        self.choices.is_empty()
+    }
+
+    /// Returns the choice at `index` and its processed form.
+    pub fn get(&self, index: usize) -> Option<(&str, &str)> {
+        Some((self.choices.get(index)?, &self.processed[index]))
+    }
+
+    /// Iterates over the choices and their processed forms.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.choices
+            .iter()
+            .map(|s| s.as_str())
+            .zip(self.processed.iter().map(|s| s.as_str()))
+    }
+
+    /// Like [extract_without_order], with the choices already processed.
+    ///
+    /// `processor` is only applied to the query, and should be the one the corpus was built with.
+    pub fn extract_without_order<P, S>(
+        &self,
+        query: &str,
+        processor: P,
+        scorer: S,
+        score_cutoff: S::Score,
+    ) -> Vec<(String, S::Score)>
+    where
+        P: Processor,
+        S: Scorer,
+    {
+        let processed_query = processor.process(query);
+        let processed_query = scorer.preprocess(&processed_query);
+        let mut results = vec![];
+        for (choice, processed) in self.iter() {
+            let score = scorer.score_processed(&processed_query, &scorer.preprocess(processed));
+            if score >= score_cutoff {
+                results.push((choice.to_string(), score))
+            }
+        }
+        results
+    }
+
+    /// Like [extract_one], with the choices already processed.
+    ///
+    /// `processor` is only applied to the query, and should be the one the corpus was built with.
+    pub fn extract_one<P, S>(
+        &self,
+        query: &str,
+        processor: P,
+        scorer: S,
+        score_cutoff: S::Score,
+    ) -> Option<(String, S::Score)>
+    where
+        P: Processor,
+        S: Scorer,
+    {
+        self.extract_without_order(query, processor, scorer, score_cutoff)
+            .into_iter()
+            // The first of equally good choices is returned, see `extract_one`.
+            .rev()
+            .max_by(|(_, acc_score), (_, score)| compare(acc_score, score))
+    }
+}
+
+/// Orders scores, treating incomparable ones (such as `NaN`) as equal.
+fn compare<T: PartialOrd>(a: &T, b: &T) -> Ordering {
+    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+}
+
+impl Persist for Corpus {
+    const KIND: &'static str = "Corpus";
+    type Context = ();
+
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_usize(self.len());
+        for (choice, processed) in self.iter() {
+            encoder.put_str(choice);
+            encoder.put_str(processed);
+        }
+    }
+
+    fn decode(decoder: &mut Decoder<'_>, _: ()) -> Result<Self, PersistError> {
+        let mut corpus = Corpus::default();
+        for _ in 0..decoder.get_len()? {
+            corpus.choices.push(decoder.get_string()?);
+            corpus.processed.push(decoder.get_string()?);
+        }
+        Ok(corpus)
+    }
+}
+
+/// The scores of every query against every choice, as computed by [cdist].
+///
+/// ```
+/// # use fuzzywuzzy::process::cdist;
+/// # use fuzzywuzzy::processor::FullProcess;
+/// # use fuzzywuzzy::scorer::Ratio;
+/// let matrix = cdist(&["new york", "atlanta"], &["New York Mets", "Atlanta Braves", "Boston"], FullProcess::default(), Ratio);
+/// assert_eq!((matrix.rows(), matrix.columns()), (2, 3));
+/// assert_eq!(matrix.row(0), &[76, 18, 14]);
+/// assert_eq!(matrix.get(1, 1), Some(67));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScoreMatrix<T> {
+    rows: usize,
+    columns: usize,
+    scores: Vec<T>,
+}
+
+impl<T: Copy> ScoreMatrix<T> {
+    /// A matrix of `rows` rows and `columns` columns from its scores, row after row.
+    ///
+    /// Panics if there are not `rows * columns` scores.
+    pub fn new(rows: usize, columns: usize, scores: Vec<T>) -> ScoreMatrix<T> {
+        assert_eq!(
+            scores.len(),
+            rows * columns,
+            "expected {} x {} scores",
+            rows,
+            columns
+        );
+        ScoreMatrix {
+            rows,
+            columns,
+            scores,
+        }
+    }
+
+    /// The number of rows, one per query.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// The number of columns, one per choice.
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// The score of the query at `row` against the choice at `column`.
+    pub fn get(&self, row: usize, column: usize) -> Option<T> {
+        if row < self.rows && column < self.columns {
+            Some(self.scores[row * self.columns + column])
+        } else {
+            None
+        }
+    }
+
+    /// The scores of the query at `row` against every choice.
+    ///
+    /// Panics if `row` is out of bounds.
+    pub fn row(&self, row: usize) -> &[T] {
+        assert!(row < self.rows, "row {} out of bounds", row);
+        &self.scores[row * self.columns..(row + 1) * self.columns]
+    }
+
+    /// All the scores, row after row.
+    pub fn as_slice(&self) -> &[T] {
+        &self.scores
+    }
+}
+
+/// Scores every query against every choice.
+///
+/// Every string is processed and [preprocessed](Scorer::preprocess) once, whichever list it is
+/// in. Use [cdist_sparse] to keep only the scores meeting a cutoff.
+pub fn cdist<A, B, P, S>(
+    queries: &[A],
+    choices: &[B],
+    processor: P,
+    scorer: S,
+) -> ScoreMatrix<S::Score>
+where
+    A: AsRef<str>,
+    B: AsRef<str>,
+    P: Processor,
+    S: Scorer,
+{
+    let queries = prepare(queries, &processor, &scorer);
+    let choices = prepare(choices, &processor, &scorer);
+    ScoreMatrix {
+        rows: queries.len(),
+        columns: choices.len(),
+        scores: dense_rows(&queries, &choices, &scorer),
+    }
+}
+
+/// Scores every query against every choice, and returns `(query, choice, score)` for the scores
+/// meeting `score_cutoff`, in order.
+///
+/// ```
+/// # use fuzzywuzzy::process::cdist_sparse;
+/// # use fuzzywuzzy::processor::FullProcess;
+/// # use fuzzywuzzy::scorer::Ratio;
+/// assert_eq!(
+///     cdist_sparse(&["new york", "atlanta"], &["New York Mets", "Atlanta Braves", "Boston"], FullProcess::default(), Ratio, 60),
+///     vec![(0, 0, 76), (1, 1, 67)]);
+/// ```
+pub fn cdist_sparse<A, B, P, S>(
+    queries: &[A],
+    choices: &[B],
+    processor: P,
+    scorer: S,
+    score_cutoff: S::Score,
+) -> Vec<(usize, usize, S::Score)>
+where
+    A: AsRef<str>,
+    B: AsRef<str>,
+    P: Processor,
+    S: Scorer,
+{
+    let queries = prepare(queries, &processor, &scorer);
+    let choices = prepare(choices, &processor, &scorer);
+    sparse_rows(0, &queries, &choices, &scorer, score_cutoff)
+}
+
+/// Like [cdist], with the queries split between `threads` threads.
+///
+/// The threads own what they work on: each takes a chunk of the processed queries, and they
+/// share the processed choices and the scorer, which is why it must be `'static`. A processed
+/// string is copied once if it still borrows from the input, that is if processing left it
+/// unchanged.
+#[cfg(feature = "std")]
+pub fn cdist_parallel<A, B, P, S>(
+    queries: &[A],
+    choices: &[B],
+    processor: P,
+    scorer: S,
+    threads: usize,
+) -> ScoreMatrix<S::Score>
+where
+    A: AsRef<str>,
+    B: AsRef<str>,
+    P: Processor,
+    S: Scorer + Send + Sync + 'static,
+    S::Score: Send + 'static,
+{
+    let queries = into_owned(prepare(queries, &processor, &scorer));
+    let choices = into_owned(prepare(choices, &processor, &scorer));
+    let (rows, columns) = (queries.len(), choices.len());
+    let scores = in_parallel(
+        queries,
+        choices,
+        scorer,
+        threads,
+        |_, rows, choices, scorer| dense_rows(rows, choices, scorer),
+    );
+    ScoreMatrix {
+        rows,
+        columns,
+        scores,
+    }
+}
+
+/// Like [cdist_sparse], with the queries split between `threads` threads.
+///
+/// The strings and the scorer are handed to the threads as by [cdist_parallel].
+#[cfg(feature = "std")]
+pub fn cdist_sparse_parallel<A, B, P, S>(
+    queries: &[A],
+    choices: &[B],
+    processor: P,
+    scorer: S,
+    score_cutoff: S::Score,
+    threads: usize,
+) -> Vec<(usize, usize, S::Score)>
+where
+    A: AsRef<str>,
+    B: AsRef<str>,
+    P: Processor,
+    S: Scorer + Send + Sync + 'static,
+    S::Score: Send + Sync + 'static,
+{
+    let queries = into_owned(prepare(queries, &processor, &scorer));
+    let choices = into_owned(prepare(choices, &processor, &scorer));
+    in_parallel(
+        queries,
+        choices,
+        scorer,
+        threads,
+        move |first, rows, choices, scorer| sparse_rows(first, rows, choices, scorer, score_cutoff),
+    )
+}
+
+/// Processes, then preprocesses for `scorer`, every string.
+fn prepare<'a, T, P, S>(strings: &'a [T], processor: &P, scorer: &S) -> Vec<Cow<'a, str>>
+where
+    T: AsRef<str>,
+    P: Processor,
+    S: Scorer,
+{
+    strings
+        .iter()
+        .map(|s| normalization::then(processor.process(s.as_ref()), |s| scorer.preprocess(s)))
+        .collect()
+}
+
+fn dense_rows<S: Scorer>(queries: &[Cow<str>], choices: &[Cow<str>], scorer: &S) -> Vec<S::Score> {
+    let mut scores = Vec::with_capacity(queries.len() * choices.len());
+    for query in queries {
+        for choice in choices {
+            scores.push(scorer.score_processed(query, choice));
+        }
+    }
+    scores
+}
+
+/// The scores meeting `score_cutoff`, numbering the queries from `first_row`.
+fn sparse_rows<S: Scorer>(
+    first_row: usize,
+    queries: &[Cow<str>],
+    choices: &[Cow<str>],
+    scorer: &S,
+    score_cutoff: S::Score,
+) -> Vec<(usize, usize, S::Score)> {
+    let mut scores = Vec::new();
+    for (row, query) in queries.iter().enumerate() {
+        for (column, choice) in choices.iter().enumerate() {
+            let score = scorer.score_processed(query, choice);
+            if score >= score_cutoff {
+                scores.push((first_row + row, column, score));
+            }
+        }
+    }
+    scores
+}
+
+/// Splits `queries` into contiguous chunks, one per thread, and concatenates what `rows` returns
+/// for each chunk and the index of its first query.
+///
+/// Each thread takes its chunk, and the threads share `choices` and `scorer`.
+#[cfg(feature = "std")]
+fn in_parallel<S, T, F>(
+    queries: Vec<Cow<'static, str>>,
+    choices: Vec<Cow<'static, str>>,
+    scorer: S,
+    threads: usize,
+    rows: F,
+) -> Vec<T>
+where
+    S: Send + Sync + 'static,
+    T: Send + 'static,
+    F: Fn(usize, &[Cow<str>], &[Cow<str>], &S) -> Vec<T> + Send + Sync + 'static,
+{
+    let threads = core::cmp::max(threads, 1);
+    let chunk = core::cmp::max((queries.len() + threads - 1) / threads, 1);
+    let shared = std::sync::Arc::new((choices, scorer, rows));
+    let mut queries = queries.into_iter();
+    let mut handles = Vec::new();
+    let mut first = 0;
+    loop {
+        let chunk: Vec<Cow<str>> = queries.by_ref().take(chunk).collect();
+        if chunk.is_empty() {
+            break;
+        }
+        let shared = std::sync::Arc::clone(&shared);
+        let start = first;
+        first += chunk.len();
+        handles.push(std::thread::spawn(move || {
+            let (choices, scorer, rows) = &*shared;
+            rows(start, &chunk, choices, scorer)
+        }));
+    }
+    handles
+        .into_iter()
+        .flat_map(|handle| {
+            handle
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        })
+        .collect()
+}
+
+/// Takes ownership of processed strings, copying only those which still borrow from the input.
+#[cfg(feature = "std")]
+fn into_owned(strings: Vec<Cow<str>>) -> Vec<Cow<'static, str>> {
+    strings
+        .into_iter()
+        .map(|s| Cow::Owned(s.into_owned()))
+        .collect()
+}
+
+/// Groups `choices` into the connected components of their similarity graph, in which two
+/// choices are linked if they score at least `score_cutoff` against each other.
+///
+/// Returns the indices of the choices in each cluster, in order, and the clusters by their
+/// first index. Choices similar to no other form clusters of their own. Every pair of choices
+/// is scored once.
+///
+/// ```
+/// # use fuzzywuzzy::process::cluster;
+/// # use fuzzywuzzy::processor::FullProcess;
+/// # use fuzzywuzzy::scorer::TokenSetRatio;
+/// let names = ["Acme Corp", "Globex", "ACME Corporation", "acme corp.", "Globex Inc", "Initech"];
+/// assert_eq!(
+///     cluster(&names, FullProcess::default(), TokenSetRatio::default(), 70),
+///     vec![vec![0, 2, 3], vec![1, 4], vec![5]]);
+/// ```
+pub fn cluster<T, P, S>(
+    choices: &[T],
+    processor: P,
+    scorer: S,
+    score_cutoff: S::Score,
+) -> Vec<Vec<usize>>
+where
+    T: AsRef<str>,
+    P: Processor,
+    S: Scorer,
+{
+    let choices = prepare(choices, &processor, &scorer);
+    // A union-find forest, where each root is the smallest index of its tree.
+    let mut parent: Vec<usize> = (0..choices.len()).collect();
+    for i in 0..choices.len() {
+        for j in i + 1..choices.len() {
+            if scorer.score_processed(&choices[i], &choices[j]) >= score_cutoff {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[core::cmp::max(a, b)] = core::cmp::min(a, b);
+            }
+        }
+    }
+    let mut clusters: Vec<Vec<usize>> = Vec::new();
+    let mut cluster_of = vec![usize::MAX; choices.len()];
+    for i in 0..choices.len() {
+        let r = root(&mut parent, i);
+        if r == i {
+            cluster_of[i] = clusters.len();
+            clusters.push(Vec::new());
+        }
+        clusters[cluster_of[r]].push(i);
+    }
+    clusters
+}
+
+/// The root of `i` in a union-find forest, halving the path to it.
+fn root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// How [cluster_agglomerative] measures the distance between two clusters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Linkage {
+    /// The distance between their closest members. Clusters may chain through intermediate
+    /// choices, and end up like the ones of [cluster].
+    Single,
+    /// The mean distance between their members.
+    Average,
+    /// The distance between their farthest members, so every two members of a cluster are
+    /// within the cutoff.
+    Complete,
+}
+
+/// Groups `choices` by hierarchical agglomerative clustering.
+///
+/// The distance between two choices is `100 - score`, for scorers on the usual 0 to 100 scale.
+/// Starting from one cluster per choice, the two closest clusters according to `linkage` are
+/// merged until none are within `max_distance`. Clusters are returned like in [cluster].
+///
+/// This scores every pair of choices once and takes `O(n²)` memory. Merging takes `O(n²)` time
+/// when few nearest neighbors need to be searched again, and `O(n³)` in the worst case, as each
+/// of the up to `n - 1` merges may search them all again.
+///
+/// ```
+/// # use fuzzywuzzy::process::{cluster_agglomerative, Linkage};
+/// # use fuzzywuzzy::processor::FullProcess;
+/// # use fuzzywuzzy::scorer::Ratio;
+/// let names = ["jon", "john", "johnny", "johnathan"];
+/// assert_eq!(
+///     cluster_agglomerative(&names, FullProcess::default(), Ratio, Linkage::Single, 35.0),
+///     vec![vec![0, 1, 2, 3]]);
+/// assert_eq!(
+///     cluster_agglomerative(&names, FullProcess::default(), Ratio, Linkage::Complete, 35.0),
+///     vec![vec![0, 1, 2], vec![3]]);
+/// ```
+pub fn cluster_agglomerative<T, P, S>(
+    choices: &[T],
+    processor: P,
+    scorer: S,
+    linkage: Linkage,
+    max_distance: f64,
+) -> Vec<Vec<usize>>
+where
+    T: AsRef<str>,
+    P: Processor,
+    S: Scorer,
+    S::Score: Into<f64>,
+{
+    let choices = prepare(choices, &processor, &scorer);
+    let n = choices.len();
+    let mut distances = vec![0.0; n * n];
+    for i in 0..n {
+        for j in i + 1..n {
+            let distance = 100.0 - scorer.score_processed(&choices[i], &choices[j]).into();
+            distances[i * n + j] = distance;
+            distances[j * n + i] = distance;
+        }
+    }
+    // Clusters are numbered by their smallest member, which survives every merge.
+    let mut members: Vec<Vec<usize>> = (0..n).map(|i| vec![i]).collect();
+    let mut active = vec![true; n];
+    let nearest = |distances: &[f64], active: &[bool], i: usize| {
+        (0..n)
+            .filter(|&j| j != i && active[j])
+            .map(|j| (distances[i * n + j], j))
+            .fold(None, |best: Option<(f64, usize)>, candidate| match best {
+                Some(best) if best.0 <= candidate.0 => Some(best),
+                _ => Some(candidate),
+            })
+    };
+    let mut neighbors: Vec<Option<(f64, usize)>> =
+        (0..n).map(|i| nearest(&distances, &active, i)).collect();
+    loop {
+        let closest = (0..n)
+            .filter(|&i| active[i])
+            .filter_map(|i| neighbors[i].map(|(distance, j)| (distance, i, j)))
+            .fold(
+                None,
+                |best: Option<(f64, usize, usize)>, candidate| match best {
+                    Some(best) if best.0 <= candidate.0 => Some(best),
+                    _ => Some(candidate),
+                },
+            );
+        let (a, b) = match closest {
+            Some((distance, a, b)) if distance <= max_distance => (a, b),
+            _ => break,
+        };
+        let (kept, merged) = (core::cmp::min(a, b), core::cmp::max(a, b));
+        let (kept_size, merged_size) = (members[kept].len() as f64, members[merged].len() as f64);
+        active[merged] = false;
+        let moved = core::mem::take(&mut members[merged]);
+        members[kept].extend(moved);
+        for k in (0..n).filter(|&k| active[k] && k != kept) {
+            let (to_kept, to_merged) = (distances[kept * n + k], distances[merged * n + k]);
+            // The Lance-Williams updates of each linkage.
+            let updated = match linkage {
+                Linkage::Single => to_kept.min(to_merged),
+                Linkage::Complete => to_kept.max(to_merged),
+                Linkage::Average => {
+                    (kept_size * to_kept + merged_size * to_merged) / (kept_size + merged_size)
+                }
+            };
+            distances[kept * n + k] = updated;
+            distances[k * n + kept] = updated;
+        }
+        for k in (0..n).filter(|&k| active[k]) {
+            neighbors[k] = match neighbors[k] {
+                // Only the distances to the kept cluster changed.
+                Some((d, j)) if k != kept && j != kept && j != merged => {
+                    let to_kept = (distances[k * n + kept], kept);
+                    Some(if (d, j) <= to_kept { (d, j) } else { to_kept })
+                }
+                _ => nearest(&distances, &active, k),
+            };
+        }
+    }
+    members
+        .into_iter()
+        .filter(|cluster| !cluster.is_empty())
+        .map(|mut cluster| {
+            cluster.sort_unstable();
+            cluster
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::normalization::PassthroughNormalizer;
+    use crate::scorer::{Ratio, WRatio};
+    use rand::{Rng, SeedableRng};
+
+    const NAMES: [&str; 8] = [
+        "new york mets",
+        "New York Yankees",
+        "atlanta braves",
+        "Atlanta Falcons",
+        "dallas cowboys",
+        "",
+        "chicago cubs",
+        "chicago white sox",
+    ];
+
+    #[test]
+    fn cdist_agrees_with_extract() {
+        let processor = crate::processor::FullProcess::default();
+        let matrix = cdist(&NAMES[..5], &NAMES, processor, WRatio::default());
+        for (row, query) in NAMES[..5].iter().enumerate() {
+            let expected: Vec<u8> =
+                extract_without_order(query, NAMES, processor, WRatio::default(), 0)
+                    .into_iter()
+                    .map(|(_, score)| score)
+                    .collect();
+            assert_eq!(matrix.row(row), &expected[..]);
+        }
+        let sparse = cdist_sparse(&NAMES[..5], &NAMES, processor, WRatio::default(), 50);
+        let expected: Vec<(usize, usize, u8)> = (0..5)
+            .flat_map(|row| (0..NAMES.len()).map(move |column| (row, column)))
+            .filter_map(|(row, column)| {
+                let score = matrix.get(row, column)?;
+                if score >= 50 {
+                    Some((row, column, score))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        assert_eq!(sparse, expected);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn parallel_agrees_with_sequential() {
+        let processor = crate::processor::FullProcess::default();
+        let dense = cdist(&NAMES, &NAMES[2..], processor, Ratio);
+        let sparse = cdist_sparse(&NAMES, &NAMES[2..], processor, Ratio, 40);
+        for &threads in [0, 1, 3, 8, 20].iter() {
+            assert_eq!(
+                cdist_parallel(&NAMES, &NAMES[2..], processor, Ratio, threads),
+                dense
+            );
+            assert_eq!(
+                cdist_sparse_parallel(&NAMES, &NAMES[2..], processor, Ratio, 40, threads),
+                sparse
+            );
+        }
+        let empty: [&str; 0] = [];
+        assert_eq!(
+            cdist_parallel(&empty, &NAMES, processor, Ratio, 4).rows(),
+            0
+        );
+    }
+
+    fn random_words(seed: u64, count: usize) -> Vec<String> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        (0..count)
+            .map(|_| {
+                let len = rng.gen_range(1..7);
+                (0..len)
+                    .map(|_| ['a', 'b', 'c'][rng.gen_range(0..3)])
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Agglomerative clustering recomputing every linkage from the members at every step.
+    fn naive_agglomerative(
+        words: &[String],
+        linkage: Linkage,
+        max_distance: f64,
+    ) -> Vec<Vec<usize>> {
+        let distance = |a: usize, b: usize| 100.0 - f64::from(Ratio.score(&words[a], &words[b]));
+        let mut clusters: Vec<Vec<usize>> = (0..words.len()).map(|i| vec![i]).collect();
+        loop {
+            let mut best: Option<(f64, usize, usize)> = None;
+            for a in 0..clusters.len() {
+                for b in a + 1..clusters.len() {
+                    let pairs: Vec<f64> = clusters[a]
+                        .iter()
+                        .flat_map(|&i| clusters[b].iter().map(move |&j| (i, j)))
+                        .map(|(i, j)| distance(i, j))
+                        .collect();
+                    let d = match linkage {
+                        Linkage::Single => pairs.iter().cloned().fold(f64::INFINITY, f64::min),
+                        Linkage::Complete => pairs.iter().cloned().fold(0.0, f64::max),
+                        Linkage::Average => unreachable!(),
+                    };
+                    if best.map_or(true, |best| d < best.0) {
+                        best = Some((d, a, b));
+                    }
+                }
+            }
+            match best {
+                Some((d, a, b)) if d <= max_distance => {
+                    let merged = clusters.remove(b);
+                    clusters[a].extend(merged);
+                    clusters[a].sort_unstable();
+                }
+                _ => return clusters,
+            }
+        }
+    }
+
+    #[test]
+    fn clusters_agree_with_naive_clustering() {
+        for seed in 0..20 {
+            let words = random_words(seed, 24);
+            for &cutoff in [0u8, 50, 70, 85, 100].iter() {
+                let max_distance = f64::from(100 - cutoff);
+                let components = cluster(&words, PassthroughNormalizer, Ratio, cutoff);
+                let single = cluster_agglomerative(
+                    &words,
+                    PassthroughNormalizer,
+                    Ratio,
+                    Linkage::Single,
+                    max_distance,
+                );
+                assert_eq!(single, components);
+                for &linkage in [Linkage::Single, Linkage::Complete].iter() {
+                    assert_eq!(
+                        cluster_agglomerative(
+                            &words,
+                            PassthroughNormalizer,
+                            Ratio,
+                            linkage,
+                            max_distance
+                        ),
+                        naive_agglomerative(&words, linkage, max_distance),
+                        "seed={} cutoff={} {:?}",
+                        seed,
+                        cutoff,
+                        linkage
+                    );
+                }
+                // Every pair of members was merged across clusters whose mean distance was
+                // within the cutoff, so the mean distance within each cluster is too.
+                for members in cluster_agglomerative(
+                    &words,
+                    PassthroughNormalizer,
+                    Ratio,
+                    Linkage::Average,
+                    max_distance,
+                ) {
+                    let pairs: Vec<f64> = members
+                        .iter()
+                        .enumerate()
+                        .flat_map(|(n, &i)| members[n + 1..].iter().map(move |&j| (i, j)))
+                        .map(|(i, j)| 100.0 - f64::from(Ratio.score(&words[i], &words[j])))
+                        .collect();
+                    if !pairs.is_empty() {
+                        let mean = pairs.iter().sum::<f64>() / pairs.len() as f64;
+                        assert!(mean <= max_distance + 1e-9);
+                    }
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,122 @@
+//! Processors prepare strings before they are scored by [process](crate::process) and [index](crate::index).
+//!
+//! A [Processor] returns a [Cow], borrowing its input when it has nothing to change, so
+//! processing choices that are already clean does not allocate. [FullProcess] is
+//! [utils::full_process] as a processor, every [Normalizer] is a processor, and processors
+//! can be chained with [then](Processor::then).
+//!
+//! ```
+//! # use std::borrow::Cow;
+//! # use fuzzywuzzy::normalization::{FormCNormalizer, LowerCaseNormalizer};
+//! # use fuzzywuzzy::processor::{FullProcess, Processor};
+//! let processor = FormCNormalizer.then(LowerCaseNormalizer);
+//! assert_eq!(processor.process("A\u{0308}pfel"), "\u{00E4}pfel");
+//! assert!(matches!(processor.process("\u{00E4}pfel"), Cow::Borrowed(_)));
+//!
+//! assert_eq!(FullProcess::default().process("C'est la vie"), "c est la vie");
+//! assert!(matches!(FullProcess::default().process("c est la vie"), Cow::Borrowed(_)));
+//! ```
+
+use crate::normalization::{self, Normalizer};
+use crate::utils;
+use alloc::borrow::Cow;
+
+/// Prepares a string for scoring.
+pub trait Processor {
+    /// Processes `s`, borrowing it if it is left unchanged.
+    fn process<'a>(&self, s: &'a str) -> Cow<'a, str>;
+
+    /// Processes strings with `self`, then with `next`.
+    fn then<Q: Processor>(self, next: Q) -> Chain<Self, Q>
+    where
+        Self: Sized,
+    {
+        Chain(self, next)
+    }
+}
+
+impl<N: Normalizer> Processor for N {
+    fn process<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        self.normalize_cow(s)
+    }
+}
+
+/// Processes strings with [utils::full_process].
+///
+/// `FullProcess::default()` keeps non-ASCII characters, like [process](crate::process) always
+/// has.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FullProcess {
+    /// Whether non-ASCII characters are removed.
+    pub force_ascii: bool,
+}
+
+impl Processor for FullProcess {
+    fn process<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        // full_process replaces everything but alphanumeric characters with spaces,
+        // lower-cases and trims, so strings made of lower-case alphanumeric characters
+        // and inner spaces come out unchanged.
+        let unchanged = (!self.force_ascii || s.is_ascii())
+            && !s.starts_with(' ')
+            && !s.ends_with(' ')
+            && s.chars()
+                .all(|c| c == ' ' || (c.is_alphanumeric() && normalization::is_lowercase(c)));
+        if unchanged {
+            Cow::Borrowed(s)
+        } else {
+            Cow::Owned(utils::full_process(s, self.force_ascii))
+        }
+    }
+}
+
+/// Two processors applied one after the other, see [Processor::then].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Chain<A, B>(pub A, pub B);
+
+impl<A: Processor, B: Processor> Processor for Chain<A, B> {
+    fn process<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        normalization::then(self.0.process(s), |s| self.1.process(s))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::normalization::{AsciiOnlyNormalizer, ComposedNormalizer, LowerCaseNormalizer};
+    use alloc::boxed::Box;
+
+    #[test]
+    fn processors_agree_with_normalize() {
+        let strings = [
+            "",
+            " ",
+            "lorem ipsum",
+            "Lorem Ipsum",
+            "lorem  ipsum ",
+            "c'est la vie",
+            "ça va",
+            "ΟΔΟΣ",
+            "a¬4ሴ2€耀",
+            "İstanbul",
+            "tab\tseparated",
+        ];
+        let composed = ComposedNormalizer::with(vec![
+            Box::new(LowerCaseNormalizer),
+            Box::new(AsciiOnlyNormalizer),
+        ]);
+        let chained = LowerCaseNormalizer.then(AsciiOnlyNormalizer);
+        for s in strings.iter() {
+            assert_eq!(LowerCaseNormalizer.process(s), s.to_lowercase());
+            assert_eq!(composed.process(s), composed.normalize(s));
+            assert_eq!(chained.process(s), composed.normalize(s));
+            for &force_ascii in [false, true].iter() {
+                assert_eq!(
+                    FullProcess { force_ascii }.process(s),
+                    utils::full_process(s, force_ascii),
+                    "{:?}",
+                    s
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! Matching records made of several fields, each with its own scorer, processor and weight.
+//!
+//! A record is a slice of optional field values, in the order of the [Field]s of a
+//! [RecordMatcher]. The combined score of two records is the weighted mean of their field
+//! scores, and how a missing value (`None`) counts is set per field with [Missing].
+//!
+//! ```
+//! # use fuzzywuzzy::processor::FullProcess;
+//! # use fuzzywuzzy::record::{Field, Missing, RecordMatcher};
+//! # use fuzzywuzzy::scorer::{Ratio, TokenSortRatio, WRatio};
+//! let matcher = RecordMatcher::new(vec![
+//!     Field::new("name", WRatio::default(), FullProcess::default(), 3.0),
+//!     Field::new("address", TokenSortRatio::default(), FullProcess::default(), 2.0),
+//!     Field::new("city", Ratio, FullProcess::default(), 1.0),
+//!     Field::new("phone", Ratio, |s: &str| -> String { s.chars().filter(char::is_ascii_digit).collect() }, 2.0)
+//!         .when_missing(Missing::Score(50)),
+//! ]);
+//! let query = [Some("Acme Corp."), Some("12 Main Street"), Some("Springfield"), None];
+//! let candidates = vec![
+//!     [Some("ACME Corporation"), Some("Main Street 12"), Some("Springfield"), Some("555-0100")],
+//!     [Some("Globex"), Some("1 Industrial Way"), None, Some("555-0199")],
+//! ];
+//! let found = matcher.extract_without_order(&query, &candidates, 70);
+//! assert_eq!(found.len(), 1);
+//! let (index, score) = &found[0];
+//! assert_eq!(*index, 0);
+//! assert_eq!(score.fields, vec![Some(90), Some(100), Some(100), Some(50)]);
+//! assert_eq!(score.score, 84);
+//! ```
+
+use crate::normalization;
+use crate::processor::Processor;
+use crate::scorer::Scorer;
+use crate::utils;
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// How a field counts when either record lacks it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Missing {
+    /// The field is left out, and the combined score is the weighted mean of the other fields.
+    Ignore,
+    /// The field scores this value.
+    Score(u8),
+}
+
+/// A field of the records compared by a [RecordMatcher].
+pub struct Field {
+    name: String,
+    scorer: Box<dyn Scorer<Score = u8>>,
+    processor: Box<dyn Processor>,
+    weight: f64,
+    missing: Missing,
+}
+
+impl Field {
+    /// A field scored with `scorer` after being processed with `processor`, counting `weight`
+    /// times in the combined score. Missing values are [ignored](Missing::Ignore).
+    ///
+    /// Panics if `weight` is negative or not finite.
+    pub fn new<S, P>(name: &str, scorer: S, processor: P, weight: f64) -> Field
+    where
+        S: Scorer<Score = u8> + 'static,
+        P: Processor + 'static,
+    {
+        assert!(
+            weight.is_finite() && weight >= 0.0,
+            "invalid weight {} for field {}",
+            weight,
+            name
+        );
+        Field {
+            name: name.to_string(),
+            scorer: Box::new(scorer),
+            processor: Box::new(processor),
+            weight,
+            missing: Missing::Ignore,
+        }
+    }
+
+    /// Sets how the field counts when either record lacks it.
+    pub fn when_missing(mut self, missing: Missing) -> Field {
+        self.missing = missing;
+        self
+    }
+
+    /// The name of the field.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The weight of the field.
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    fn prepare<'a>(&self, value: Option<&'a str>) -> Option<Cow<'a, str>> {
+        value.map(|value| {
+            normalization::then(self.processor.process(value), |s| self.scorer.preprocess(s))
+        })
+    }
+}
+
+/// The score of a record against another, see [RecordMatcher::score].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordScore {
+    /// The weighted mean of the field scores, or 0 if every field was ignored.
+    pub score: u8,
+    /// The score of each field, or `None` if it was ignored.
+    pub fields: Vec<Option<u8>>,
+}
+
+/// Scores records field by field.
+pub struct RecordMatcher {
+    fields: Vec<Field>,
+}
+
+impl RecordMatcher {
+    /// Matches records made of `fields`, in order.
+    pub fn new(fields: Vec<Field>) -> RecordMatcher {
+        RecordMatcher { fields }
+    }
+
+    /// The fields of the records.
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Scores record `a` against record `b`.
+    ///
+    /// Panics if a record does not have one value per field.
+    pub fn score<T: AsRef<str>>(&self, a: &[Option<T>], b: &[Option<T>]) -> RecordScore {
+        self.score_prepared(&self.prepare(a), b)
+    }
+
+    /// Scores `query` against every candidate, and returns the index and score of those whose
+    /// combined score meets `score_cutoff`, in order.
+    ///
+    /// The query is processed once. Panics if a record does not have one value per field.
+    pub fn extract_without_order<T, I, R, U>(
+        &self,
+        query: &[Option<T>],
+        candidates: I,
+        score_cutoff: u8,
+    ) -> Vec<(usize, RecordScore)>
+    where
+        T: AsRef<str>,
+        I: IntoIterator<Item = R>,
+        R: AsRef<[Option<U>]>,
+        U: AsRef<str>,
+    {
+        let query = self.prepare(query);
+        candidates
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, candidate)| {
+                let score = self.score_prepared(&query, candidate.as_ref());
+                if score.score >= score_cutoff {
+                    Some((index, score))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn prepare<'a, T: AsRef<str>>(&self, record: &'a [Option<T>]) -> Vec<Option<Cow<'a, str>>> {
+        self.check(record.len());
+        self.fields
+            .iter()
+            .zip(record)
+            .map(|(field, value)| field.prepare(value.as_ref().map(|v| v.as_ref())))
+            .collect()
+    }
+
+    fn score_prepared<T: AsRef<str>>(
+        &self,
+        query: &[Option<Cow<str>>],
+        record: &[Option<T>],
+    ) -> RecordScore {
+        self.check(record.len());
+        let mut fields = Vec::with_capacity(self.fields.len());
+        let (mut total, mut weights) = (0.0, 0.0);
+        for ((field, a), b) in self.fields.iter().zip(query).zip(record) {
+            let b = field.prepare(b.as_ref().map(|b| b.as_ref()));
+            let score = match (a, b, field.missing) {
+                (Some(a), Some(b), _) => Some(field.scorer.score_processed(a, &b)),
+                (_, _, Missing::Score(score)) => Some(score),
+                (_, _, Missing::Ignore) => None,
+            };
+            if let Some(score) = score {
+                total += field.weight * f64::from(score);
+                weights += field.weight;
+            }
+            fields.push(score);
+        }
+        let score = if weights > 0.0 {
+            utils::round_to_u8(total / weights)
+        } else {
+            0
+        };
+        RecordScore { score, fields }
+    }
+
+    fn check(&self, len: usize) {
+        assert_eq!(
+            len,
+            self.fields.len(),
+            "expected a record of {} fields",
+            self.fields.len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::processor::FullProcess;
+    use crate::scorer::{Ratio, TokenSortRatio};
+
+    #[test]
+    fn missing_fields_are_handled_as_configured() {
+        let matcher = RecordMatcher::new(vec![
+            Field::new(
+                "name",
+                TokenSortRatio::default(),
+                FullProcess::default(),
+                2.0,
+            ),
+            Field::new("city", Ratio, FullProcess::default(), 1.0),
+            Field::new("phone", Ratio, FullProcess::default(), 1.0).when_missing(Missing::Score(0)),
+        ]);
+        let full = [Some("John Smith"), Some("Boston"), Some("5550100")];
+        assert_eq!(
+            matcher.score(
+                &full,
+                &[Some("smith john"), Some("Boston"), Some("5550100")]
+            ),
+            RecordScore {
+                score: 100,
+                fields: vec![Some(100), Some(100), Some(100)]
+            }
+        );
+        // The missing city is left out, the missing phone scores 0.
+        assert_eq!(
+            matcher.score(&full, &[Some("smith john"), None, None]),
+            RecordScore {
+                score: 67,
+                fields: vec![Some(100), None, Some(0)]
+            }
+        );
+        assert_eq!(
+            matcher.score(&[None, None, None], &full),
+            RecordScore {
+                score: 0,
+                fields: vec![None, None, Some(0)]
+            }
+        );
+        let ignored =
+            RecordMatcher::new(vec![Field::new("name", Ratio, FullProcess::default(), 1.0)]);
+        assert_eq!(
+            ignored.score(&[None::<&str>], &[None]),
+            RecordScore {
+                score: 0,
+                fields: vec![None]
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a record of 2 fields")]
+    fn records_need_every_field() {
+        let matcher = RecordMatcher::new(vec![
+            Field::new("name", Ratio, FullProcess::default(), 1.0),
+            Field::new("city", Ratio, FullProcess::default(), 1.0),
+        ]);
+        matcher.score(&[Some("a")], &[Some("a")]);
+    }
+}
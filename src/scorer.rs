@@ -0,0 +1,339 @@
+//! Scorers as values, for the functions of [process](crate::process) and [index](crate::index).
+//!
+//! A [Scorer] compares two strings and returns a score. Every function in [fuzz] has a
+//! wrapper type implementing it, closures taking two strings implement it, and scorers can
+//! be combined with [Max], [Scaled] and [Processed].
+//!
+//! ```
+//! # use fuzzywuzzy::fuzz;
+//! # use fuzzywuzzy::scorer::{Max, PartialRatio, Ratio, Scaled, Scorer, TokenSortRatio};
+//! assert_eq!(Ratio.score("new york mets", "new YORK mets"), 69);
+//! assert_eq!(TokenSortRatio::default().score("new york mets", "new YORK mets"), 100);
+//!
+//! let scorer = Max(Ratio, Scaled::new(PartialRatio, 0.9));
+//! assert_eq!(scorer.score("new york", "new york mets"), 90);
+//!
+//! let closure = |a: &str, b: &str| fuzz::token_set_ratio(a, b, false, true);
+//! assert_eq!(closure.score("new york mets", "the new york mets"), 100);
+//! ```
+
+use crate::fuzz;
+use crate::normalization;
+use crate::processor::Processor;
+use crate::utils;
+use alloc::borrow::Cow;
+
+/// Scores the similarity of two strings.
+///
+/// The [process](crate::process) functions call [preprocess](Scorer::preprocess) once on the
+/// query and once on every choice, then [score_processed](Scorer::score_processed) on the
+/// results, so work that only depends on one string is not repeated for every pair.
+pub trait Scorer {
+    /// The type of the scores, where greater means more similar.
+    type Score: Copy + PartialOrd;
+
+    /// Prepares a string for [score_processed](Scorer::score_processed).
+    ///
+    /// The default leaves the string as it is.
+    fn preprocess<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(s)
+    }
+
+    /// Scores two strings which have already been [preprocessed](Scorer::preprocess).
+    fn score_processed(&self, a: &str, b: &str) -> Self::Score;
+
+    /// Preprocesses and scores two strings.
+    fn score(&self, a: &str, b: &str) -> Self::Score {
+        self.score_processed(&self.preprocess(a), &self.preprocess(b))
+    }
+}
+
+impl<F, T> Scorer for F
+where
+    F: Fn(&str, &str) -> T,
+    T: Copy + PartialOrd,
+{
+    type Score = T;
+
+    fn score_processed(&self, a: &str, b: &str) -> T {
+        self(a, b)
+    }
+}
+
+/// Scores with [fuzz::ratio].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Ratio;
+
+impl Scorer for Ratio {
+    type Score = u8;
+
+    fn score_processed(&self, a: &str, b: &str) -> u8 {
+        fuzz::ratio(a, b)
+    }
+}
+
+/// Scores with [fuzz::levenshtein_ratio].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LevenshteinRatio;
+
+impl Scorer for LevenshteinRatio {
+    type Score = u8;
+
+    fn score_processed(&self, a: &str, b: &str) -> u8 {
+        fuzz::levenshtein_ratio(a, b)
+    }
+}
+
+/// Scores with [fuzz::partial_ratio].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PartialRatio;
+
+impl Scorer for PartialRatio {
+    type Score = u8;
+
+    fn score_processed(&self, a: &str, b: &str) -> u8 {
+        fuzz::partial_ratio(a, b)
+    }
+}
+
+/// Scores with [fuzz::jaro_winkler_ratio].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JaroWinklerRatio;
+
+impl Scorer for JaroWinklerRatio {
+    type Score = u8;
+
+    fn score_processed(&self, a: &str, b: &str) -> u8 {
+        fuzz::jaro_winkler_ratio(a, b)
+    }
+}
+
+/// Declares a scorer for a `fuzz` function taking `force_ascii` and `full_process` flags,
+/// both of which default to `true`.
+macro_rules! flagged_scorer {
+    ($(#[$doc:meta])* $name:ident, $function:path) => {
+        $(#[$doc])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct $name {
+            /// Whether non-ASCII characters are removed when processing.
+            pub force_ascii: bool,
+            /// Whether the strings are processed with [utils::full_process].
+            pub full_process: bool,
+        }
+
+        impl Default for $name {
+            fn default() -> $name {
+                $name {
+                    force_ascii: true,
+                    full_process: true,
+                }
+            }
+        }
+
+        impl Scorer for $name {
+            type Score = u8;
+
+            fn score_processed(&self, a: &str, b: &str) -> u8 {
+                $function(a, b, self.force_ascii, self.full_process)
+            }
+        }
+    };
+}
+
+flagged_scorer!(
+    /// Scores with [fuzz::token_sort_ratio].
+    TokenSortRatio,
+    fuzz::token_sort_ratio
+);
+flagged_scorer!(
+    /// Scores with [fuzz::partial_token_sort_ratio].
+    PartialTokenSortRatio,
+    fuzz::partial_token_sort_ratio
+);
+flagged_scorer!(
+    /// Scores with [fuzz::token_set_ratio].
+    TokenSetRatio,
+    fuzz::token_set_ratio
+);
+flagged_scorer!(
+    /// Scores with [fuzz::partial_token_set_ratio].
+    PartialTokenSetRatio,
+    fuzz::partial_token_set_ratio
+);
+flagged_scorer!(
+    /// Scores with [fuzz::wratio]; `WRatio { force_ascii: false, .. }` is [fuzz::uwratio].
+    WRatio,
+    fuzz::wratio
+);
+
+/// Scores with [fuzz::qratio]; `QRatio { force_ascii: false }` is [fuzz::uqratio].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QRatio {
+    /// Whether non-ASCII characters are removed when processing.
+    pub force_ascii: bool,
+}
+
+impl Default for QRatio {
+    fn default() -> QRatio {
+        QRatio { force_ascii: true }
+    }
+}
+
+impl Scorer for QRatio {
+    type Score = u8;
+
+    fn score_processed(&self, a: &str, b: &str) -> u8 {
+        fuzz::qratio(a, b, self.force_ascii)
+    }
+}
+
+/// The greater of the scores of two scorers.
+///
+/// Each scorer preprocesses the strings itself.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Max<A, B>(pub A, pub B);
+
+impl<A, B> Scorer for Max<A, B>
+where
+    A: Scorer,
+    B: Scorer<Score = A::Score>,
+{
+    type Score = A::Score;
+
+    fn score_processed(&self, a: &str, b: &str) -> A::Score {
+        let (x, y) = (self.0.score(a, b), self.1.score(a, b));
+        if y > x {
+            y
+        } else {
+            x
+        }
+    }
+}
+
+/// The score of a scorer multiplied by a factor, like the partial scores in [fuzz::wratio].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Scaled<S> {
+    scorer: S,
+    factor: f64,
+}
+
+impl<S> Scaled<S> {
+    /// Scales the scores of `scorer` by `factor`, which should be between 0 and 1.
+    pub fn new(scorer: S, factor: f64) -> Scaled<S> {
+        Scaled { scorer, factor }
+    }
+}
+
+impl<S: Scorer<Score = u8>> Scorer for Scaled<S> {
+    type Score = u8;
+
+    fn preprocess<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        self.scorer.preprocess(s)
+    }
+
+    fn score_processed(&self, a: &str, b: &str) -> u8 {
+        utils::round_to_u8(f64::from(self.scorer.score_processed(a, b)) * self.factor)
+    }
+}
+
+/// A scorer whose strings are first passed through a [Processor], before the scorer's own
+/// preprocessing.
+///
+/// ```
+/// # use fuzzywuzzy::processor::FullProcess;
+/// # use fuzzywuzzy::scorer::{Processed, Ratio, Scorer};
+/// let scorer = Processed::new(Ratio, FullProcess::default());
+/// assert_eq!(scorer.score("New York Mets!", "new york mets"), 100);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Processed<S, P> {
+    scorer: S,
+    processor: P,
+}
+
+impl<S, P> Processed<S, P> {
+    /// Processes strings with `processor` before scoring them with `scorer`.
+    pub fn new(scorer: S, processor: P) -> Processed<S, P> {
+        Processed { scorer, processor }
+    }
+}
+
+impl<S, P> Scorer for Processed<S, P>
+where
+    S: Scorer,
+    P: Processor,
+{
+    type Score = S::Score;
+
+    fn preprocess<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        normalization::then(self.processor.process(s), |s| self.scorer.preprocess(s))
+    }
+
+    fn score_processed(&self, a: &str, b: &str) -> S::Score {
+        self.scorer.score_processed(a, b)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wrappers_agree_with_functions() {
+        let pairs = [
+            ("", ""),
+            ("new york mets", "new YORK mets"),
+            ("new york mets", "the wonderful new york mets"),
+            ("fòllòwbáck", "followback"),
+            (
+                "what about supercalifragilisticexpialidocious",
+                "about what",
+            ),
+        ];
+        for &(a, b) in pairs.iter() {
+            assert_eq!(Ratio.score(a, b), fuzz::ratio(a, b));
+            assert_eq!(LevenshteinRatio.score(a, b), fuzz::levenshtein_ratio(a, b));
+            assert_eq!(PartialRatio.score(a, b), fuzz::partial_ratio(a, b));
+            assert_eq!(JaroWinklerRatio.score(a, b), fuzz::jaro_winkler_ratio(a, b));
+            assert_eq!(QRatio::default().score(a, b), fuzz::qratio(a, b, true));
+            for &force_ascii in [false, true].iter() {
+                for &full_process in [false, true].iter() {
+                    let flags = |a, b, f: fn(&str, &str, bool, bool) -> u8| {
+                        f(a, b, force_ascii, full_process)
+                    };
+                    let tsor = TokenSortRatio {
+                        force_ascii,
+                        full_process,
+                    };
+                    let ptsor = PartialTokenSortRatio {
+                        force_ascii,
+                        full_process,
+                    };
+                    let tser = TokenSetRatio {
+                        force_ascii,
+                        full_process,
+                    };
+                    let ptser = PartialTokenSetRatio {
+                        force_ascii,
+                        full_process,
+                    };
+                    let wratio = WRatio {
+                        force_ascii,
+                        full_process,
+                    };
+                    assert_eq!(tsor.score(a, b), flags(a, b, fuzz::token_sort_ratio));
+                    assert_eq!(
+                        ptsor.score(a, b),
+                        flags(a, b, fuzz::partial_token_sort_ratio)
+                    );
+                    assert_eq!(tser.score(a, b), flags(a, b, fuzz::token_set_ratio));
+                    assert_eq!(
+                        ptser.score(a, b),
+                        flags(a, b, fuzz::partial_token_set_ratio)
+                    );
+                    assert_eq!(wratio.score(a, b), flags(a, b, fuzz::wratio));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,574 @@
+//! Approximate substring search: finding every place a pattern occurs in a text, up to some errors.
+//!
+//! [fuzz::partial_ratio](crate::fuzz::partial_ratio) only scores the best window of a text.
+//! A [FuzzySearcher] locates all the occurrences instead, with the
+//! [Sellers](https://doi.org/10.1016/0196-6774(80)90016-4) algorithm: the edit distance matrix
+//! between the pattern and the text, where an occurrence may start anywhere in the text for free.
+//! Patterns of up to 64 chars use the bit-parallel version by
+//! [Myers](https://doi.org/10.1145/316542.316550), which computes a whole column at once.
+
+use crate::index::hash_chars;
+use crate::utils;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::cmp;
+
+/// An occurrence of a pattern in a text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Occurrence {
+    /// The byte offset of the start of the occurrence.
+    pub start: usize,
+    /// The byte offset of the end of the occurrence, exclusive.
+    pub end: usize,
+    /// The char offset of the start of the occurrence.
+    pub char_start: usize,
+    /// The char offset of the end of the occurrence, exclusive.
+    pub char_end: usize,
+    /// The edit distance between the pattern and the occurrence.
+    pub distance: usize,
+    /// The [levenshtein_ratio](crate::fuzz::levenshtein_ratio) of the pattern and the occurrence.
+    pub score: u8,
+}
+
+/// Finds the occurrences of a pattern within a text, allowing a maximum number of edits.
+///
+/// Occurrences never overlap. Where several candidates overlap, the one with the fewest edits
+/// is kept, then the one closest in length to the pattern, then the first one.
+///
+/// ```
+/// # use fuzzywuzzy::search::FuzzySearcher;
+/// let searcher = FuzzySearcher::new("color", 1);
+/// let text = "The colour of the dolor, the colors: collar.";
+/// let found: Vec<(&str, usize)> = searcher
+///     .find_all(text)
+///     .iter()
+///     .map(|o| (&text[o.start..o.end], o.distance))
+///     .collect();
+/// assert_eq!(found, vec![("colou", 1), ("dolor", 1), ("color", 0)]);
+///
+/// // Offsets are given in bytes and chars.
+/// let found = FuzzySearcher::new("café", 1).find_all("le cafe, le café");
+/// assert_eq!((found[1].start, found[1].end), (12, 17));
+/// assert_eq!((found[1].char_start, found[1].char_end), (12, 16));
+/// ```
+#[derive(Clone, Debug)]
+pub struct FuzzySearcher {
+    pattern: Vec<char>,
+    max_distance: usize,
+    /// Score every occurrence must meet, when built from a ratio.
+    score_cutoff: u8,
+    /// Bit masks of the positions of each char in the pattern, for patterns of up to 64 chars.
+    masks: Option<Masks>,
+}
+
+#[derive(Clone, Debug)]
+struct Masks {
+    ascii: [u64; 128],
+    other: BTreeMap<char, u64>,
+}
+
+impl Masks {
+    fn new(pattern: &[char]) -> Masks {
+        let mut masks = Masks {
+            ascii: [0; 128],
+            other: BTreeMap::new(),
+        };
+        for (i, &c) in pattern.iter().enumerate() {
+            if c.is_ascii() {
+                masks.ascii[c as usize] |= 1 << i;
+            } else {
+                *masks.other.entry(c).or_insert(0) |= 1 << i;
+            }
+        }
+        masks
+    }
+
+    fn get(&self, c: char) -> u64 {
+        if c.is_ascii() {
+            self.ascii[c as usize]
+        } else {
+            self.other.get(&c).copied().unwrap_or(0)
+        }
+    }
+}
+
+impl FuzzySearcher {
+    /// Creates a searcher for occurrences of `pattern` within `max_distance` edits.
+    pub fn new(pattern: &str, max_distance: usize) -> FuzzySearcher {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let masks = if pattern.len() <= 64 {
+            Some(Masks::new(&pattern))
+        } else {
+            None
+        };
+        FuzzySearcher {
+            pattern,
+            max_distance,
+            score_cutoff: 0,
+            masks,
+        }
+    }
+
+    /// Creates a searcher for occurrences whose [levenshtein_ratio](crate::fuzz::levenshtein_ratio)
+    /// with `pattern` meets `score_cutoff`.
+    ///
+    /// The budget of edits is the largest an occurrence meeting the cutoff can need, but never
+    /// more than the length of the pattern, which no occurrence exceeds.
+    ///
+    /// ```
+    /// # use fuzzywuzzy::search::FuzzySearcher;
+    /// let found = FuzzySearcher::with_ratio("fuzzy wuzzy", 80).find_all("fuzy wuzzy was a bear, fuzzy wuzzy had no hair");
+    /// let scores: Vec<u8> = found.iter().map(|o| o.score).collect();
+    /// assert_eq!(scores, vec![91, 100]);
+    /// ```
+    pub fn with_ratio(pattern: &str, score_cutoff: u8) -> FuzzySearcher {
+        let length = pattern.chars().count();
+        // An occurrence may start anywhere, so it never takes more edits than the pattern has
+        // chars: a larger budget finds nothing more.
+        let max_distance = if score_cutoff == 0 {
+            length
+        } else {
+            // Longer occurrences allow more edits, but are also further away. An occurrence of
+            // `longer` chars can only meet the cutoff if the length difference alone is within
+            // its allowance, `longer - length <= longer * (201 - 2 * cutoff) / 200`, that is
+            // `longer <= 200 * length / (2 * cutoff - 1)`.
+            let cutoff = cmp::min(score_cutoff, 100) as usize;
+            let longest = 200 * length / (2 * cutoff - 1);
+            cmp::min(length, utils::max_distance_for_ratio(longest, score_cutoff))
+        };
+        let mut searcher = FuzzySearcher::new(pattern, max_distance);
+        searcher.score_cutoff = score_cutoff;
+        searcher
+    }
+
+    /// The largest number of edits in an occurrence.
+    pub fn max_distance(&self) -> usize {
+        self.max_distance
+    }
+
+    /// Returns the occurrences of the pattern in `text`, in order.
+    pub fn find_all(&self, text: &str) -> Vec<Occurrence> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut offsets: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+        offsets.push(text.len());
+        self.find_in(&chars, &offsets, 0)
+    }
+
+    /// Finds the occurrences within `chars`, a slice of a text starting at char `base`, where
+    /// `offsets` has the byte offset of every char of the text and of its end.
+    pub(crate) fn find_in(
+        &self,
+        chars: &[char],
+        offsets: &[usize],
+        base: usize,
+    ) -> Vec<Occurrence> {
+        if self.pattern.is_empty() {
+            return Vec::new();
+        }
+        let ends = self.end_distances(chars);
+        let mut found: Vec<Occurrence> = Vec::new();
+        for end in 1..=chars.len() {
+            let distance = ends[end];
+            // Only the bottoms of valleys of distances can be the best end of an occurrence.
+            let is_minimum = distance <= self.max_distance
+                && distance <= ends[end - 1]
+                && ends.get(end + 1).map_or(true, |&next| distance <= next);
+            if !is_minimum {
+                continue;
+            }
+            let start = self.best_start(chars, end, distance);
+            let length = cmp::max(self.pattern.len(), end - start);
+            let occurrence = Occurrence {
+                start: offsets[base + start],
+                end: offsets[base + end],
+                char_start: base + start,
+                char_end: base + end,
+                distance,
+                score: utils::ratio_from_distance(distance, length),
+            };
+            let gap = |o: &Occurrence| {
+                let length = o.char_end - o.char_start;
+                cmp::max(length, self.pattern.len()) - cmp::min(length, self.pattern.len())
+            };
+            match found.last_mut() {
+                Some(last) if last.char_end > occurrence.char_start => {
+                    if (occurrence.distance, gap(&occurrence)) < (last.distance, gap(last)) {
+                        *last = occurrence;
+                    }
+                }
+                _ => found.push(occurrence),
+            }
+        }
+        found.retain(|o| o.score >= self.score_cutoff);
+        found
+    }
+
+    /// The smallest edit distance between the pattern and a substring of `text` ending at each
+    /// char offset, capped at `max_distance + 1`.
+    fn end_distances(&self, text: &[char]) -> Vec<usize> {
+        let cap = self.max_distance + 1;
+        let m = self.pattern.len();
+        let mut ends = Vec::with_capacity(text.len() + 1);
+        ends.push(cmp::min(m, cap));
+        match &self.masks {
+            Some(masks) => {
+                // Myers' algorithm, with a free start: the top row of the matrix stays at zero.
+                let last = 1u64 << (m - 1);
+                let mut positive = if m == 64 { u64::MAX } else { (1u64 << m) - 1 };
+                let mut negative = 0u64;
+                let mut score = m;
+                for &c in text {
+                    let eq = masks.get(c);
+                    let xv = eq | negative;
+                    let xh = ((eq & positive).wrapping_add(positive) ^ positive) | eq;
+                    let mut ph = negative | !(xh | positive);
+                    let mut mh = positive & xh;
+                    if ph & last != 0 {
+                        score += 1;
+                    } else if mh & last != 0 {
+                        score -= 1;
+                    }
+                    ph <<= 1;
+                    mh <<= 1;
+                    positive = mh | !(xv | ph);
+                    negative = ph & xv;
+                    ends.push(cmp::min(score, cap));
+                }
+            }
+            None => {
+                let mut column: Vec<usize> = (0..=m).collect();
+                for &c in text {
+                    let mut diagonal = column[0];
+                    for i in 1..=m {
+                        let substitution = diagonal + usize::from(self.pattern[i - 1] != c);
+                        diagonal = column[i];
+                        column[i] = cmp::min(substitution, cmp::min(column[i], column[i - 1]) + 1);
+                    }
+                    ends.push(cmp::min(column[m], cap));
+                }
+            }
+        }
+        ends
+    }
+
+    /// The start of the occurrence at `distance` ending at `end`, with the length closest to the
+    /// pattern's, and the earliest in case of ties.
+    fn best_start(&self, text: &[char], end: usize, distance: usize) -> usize {
+        // Matches the reversed pattern against the text read backwards from `end`.
+        let m = self.pattern.len();
+        let low = end.saturating_sub(m + self.max_distance);
+        let mut column: Vec<usize> = (0..=m).collect();
+        let mut best = end;
+        let mut best_gap = if column[m] == distance { m } else { usize::MAX };
+        for start in (low..end).rev() {
+            let c = text[start];
+            let mut diagonal = column[0];
+            column[0] += 1;
+            for i in 1..=m {
+                let substitution = diagonal + usize::from(self.pattern[m - i] != c);
+                diagonal = column[i];
+                column[i] = cmp::min(substitution, cmp::min(column[i], column[i - 1]) + 1);
+            }
+            let gap = cmp::max(end - start, m) - cmp::min(end - start, m);
+            if column[m] == distance && gap <= best_gap {
+                best = start;
+                best_gap = gap;
+            }
+        }
+        best
+    }
+}
+
+/// Returns the occurrences of `pattern` in `text` within `max_distance` edits, see [FuzzySearcher].
+pub fn find_all(pattern: &str, text: &str, max_distance: usize) -> Vec<Occurrence> {
+    FuzzySearcher::new(pattern, max_distance).find_all(text)
+}
+
+/// Identifies a pattern of a [MultiSearcher]. IDs are assigned in order, starting at zero.
+pub type PatternId = usize;
+
+/// An occurrence of one of the patterns of a [MultiSearcher].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PatternMatch {
+    /// The pattern found.
+    pub pattern: PatternId,
+    /// Where it was found.
+    pub occurrence: Occurrence,
+}
+
+/// Finds the occurrences of many patterns at once, each with its own error budget.
+///
+/// Running a [FuzzySearcher] per pattern reads the whole text once per pattern. Instead, by
+/// the pigeonhole principle, a pattern occurring with at most `k` edits contains one of any
+/// `k + 1` disjoint pieces of it unchanged. The pieces of all the patterns are looked up
+/// exactly in a single pass over the text, and patterns are only verified around the places
+/// where one of their pieces was found. Patterns too short to be cut into `k + 1` pieces can
+/// occur anywhere, so they are verified against the whole text.
+///
+/// The occurrences of each pattern are the same as those of its own [FuzzySearcher].
+///
+/// ```
+/// # use fuzzywuzzy::search::MultiSearcher;
+/// let mut searcher = MultiSearcher::new();
+/// let acme = searcher.add("Acme Corporation", 2);
+/// let globex = searcher.add_with_ratio("Globex Inc", 85);
+/// let text = "This agreement between Acme Corproation and Globex, Inc. is made on ...";
+/// let found: Vec<(usize, &str)> = searcher
+///     .find_all(text)
+///     .iter()
+///     .map(|m| (m.pattern, &text[m.occurrence.start..m.occurrence.end]))
+///     .collect();
+/// assert_eq!(found, vec![(acme, "Acme Corproation"), (globex, "Globex, Inc")]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MultiSearcher {
+    patterns: Vec<FuzzySearcher>,
+    /// By length, the pieces of that length keyed by hash, with the pattern they come from and their offset in it.
+    pieces: BTreeMap<usize, BTreeMap<u64, Vec<(PatternId, usize)>>>,
+    /// Patterns which are verified against the whole text.
+    unfiltered: Vec<PatternId>,
+}
+
+impl MultiSearcher {
+    /// Creates a searcher without patterns.
+    pub fn new() -> MultiSearcher {
+        MultiSearcher::default()
+    }
+
+    /// The number of patterns.
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Whether there are no patterns.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Adds a pattern to find within `max_distance` edits, see [FuzzySearcher::new].
+    pub fn add(&mut self, pattern: &str, max_distance: usize) -> PatternId {
+        self.add_searcher(FuzzySearcher::new(pattern, max_distance))
+    }
+
+    /// Adds a pattern to find with a ratio of at least `score_cutoff`, see [FuzzySearcher::with_ratio].
+    ///
+    /// With a low cutoff the budget of edits reaches the length of the pattern, which then
+    /// cannot be cut into enough pieces: like a short pattern, it is verified against the whole
+    /// text.
+    pub fn add_with_ratio(&mut self, pattern: &str, score_cutoff: u8) -> PatternId {
+        self.add_searcher(FuzzySearcher::with_ratio(pattern, score_cutoff))
+    }
+
+    fn add_searcher(&mut self, searcher: FuzzySearcher) -> PatternId {
+        let id = self.patterns.len();
+        let m = searcher.pattern.len();
+        let parts = searcher.max_distance + 1;
+        if m < parts {
+            self.unfiltered.push(id);
+        } else {
+            // Cut the pattern into `parts` pieces whose lengths differ by at most one.
+            let mut offset = 0;
+            for part in 0..parts {
+                let length = m / parts + usize::from(part < m % parts);
+                let piece = &searcher.pattern[offset..offset + length];
+                self.pieces
+                    .entry(length)
+                    .or_default()
+                    .entry(hash_chars(piece))
+                    .or_default()
+                    .push((id, offset));
+                offset += length;
+            }
+        }
+        self.patterns.push(searcher);
+        id
+    }
+
+    /// Returns the occurrences of every pattern in `text`, ordered by start, then by pattern.
+    pub fn find_all(&self, text: &str) -> Vec<PatternMatch> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut offsets: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+        offsets.push(text.len());
+        let n = chars.len();
+
+        // The char ranges of the text to verify for each pattern.
+        let mut windows: BTreeMap<PatternId, Vec<(usize, usize)>> = BTreeMap::new();
+        for &id in self.unfiltered.iter() {
+            windows.entry(id).or_default().push((0, n));
+        }
+        for (&length, pieces) in self.pieces.iter() {
+            if length > n {
+                break;
+            }
+            for at in 0..=n - length {
+                let window = &chars[at..at + length];
+                let candidates = match pieces.get(&hash_chars(window)) {
+                    Some(candidates) => candidates,
+                    None => continue,
+                };
+                for &(id, offset) in candidates {
+                    let searcher = &self.patterns[id];
+                    if searcher.pattern[offset..offset + length] != *window {
+                        continue;
+                    }
+                    let k = searcher.max_distance;
+                    let m = searcher.pattern.len();
+                    // The pattern starts within `k` chars of where the piece puts it.
+                    let start = at.saturating_sub(offset + k);
+                    let end = cmp::min(n, (at + m + k).saturating_sub(offset));
+                    windows.entry(id).or_default().push((start, end));
+                }
+            }
+        }
+
+        let mut found = Vec::new();
+        for (id, mut ranges) in windows {
+            ranges.sort_unstable();
+            let mut merged: Vec<(usize, usize)> = Vec::new();
+            for (start, end) in ranges {
+                match merged.last_mut() {
+                    Some(last) if start <= last.1 => last.1 = cmp::max(last.1, end),
+                    _ => merged.push((start, end)),
+                }
+            }
+            for (start, end) in merged {
+                let occurrences = self.patterns[id].find_in(&chars[start..end], &offsets, start);
+                found.extend(occurrences.into_iter().map(|occurrence| PatternMatch {
+                    pattern: id,
+                    occurrence,
+                }));
+            }
+        }
+        found.sort_unstable_by_key(|m| (m.occurrence.start, m.pattern, m.occurrence.end));
+        found
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::primitives;
+    use alloc::string::String;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn bit_parallel_agrees_with_dynamic_programming() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(39);
+        for _ in 0..500 {
+            let pattern: String = (0..rng.gen_range(1..70))
+                .map(|_| ['a', 'b', 'ç'][rng.gen_range(0..3)])
+                .collect();
+            let text: Vec<char> = (0..rng.gen_range(0..120))
+                .map(|_| ['a', 'b', 'ç'][rng.gen_range(0..3)])
+                .collect();
+            let max_distance = rng.gen_range(0..6);
+            let searcher = FuzzySearcher::new(&pattern, max_distance);
+            let mut sellers = searcher.clone();
+            sellers.masks = None;
+            assert_eq!(searcher.end_distances(&text), sellers.end_distances(&text));
+        }
+    }
+
+    #[test]
+    fn multi_search_agrees_with_single_searches() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(40);
+        let alphabet = ['a', 'b', 'c', 'ð'];
+        for _ in 0..100 {
+            let mut searcher = MultiSearcher::new();
+            let mut singles = Vec::new();
+            for _ in 0..rng.gen_range(1..6) {
+                let pattern: String = (0..rng.gen_range(1..10))
+                    .map(|_| alphabet[rng.gen_range(0..4)])
+                    .collect();
+                let max_distance = rng.gen_range(0..4);
+                searcher.add(&pattern, max_distance);
+                singles.push(FuzzySearcher::new(&pattern, max_distance));
+            }
+            let text: String = (0..rng.gen_range(0..200))
+                .map(|_| alphabet[rng.gen_range(0..4)])
+                .collect();
+            let mut expected: Vec<PatternMatch> = singles
+                .iter()
+                .enumerate()
+                .flat_map(|(pattern, single)| {
+                    single
+                        .find_all(&text)
+                        .into_iter()
+                        .map(move |occurrence| PatternMatch {
+                            pattern,
+                            occurrence,
+                        })
+                })
+                .collect();
+            expected.sort_unstable_by_key(|m| (m.occurrence.start, m.pattern, m.occurrence.end));
+            assert_eq!(searcher.find_all(&text), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn low_cutoffs_are_bounded_by_the_pattern_length() {
+        assert_eq!(FuzzySearcher::with_ratio("abc", 0).max_distance(), 3);
+        assert_eq!(
+            FuzzySearcher::with_ratio("fuzzy wuzz", 1).max_distance(),
+            10
+        );
+        let mut rng = rand::rngs::StdRng::seed_from_u64(41);
+        for _ in 0..200 {
+            let pattern: String = (0..rng.gen_range(1..10))
+                .map(|_| ['a', 'b', 'ç'][rng.gen_range(0..3)])
+                .collect();
+            let text: String = (0..rng.gen_range(0..60))
+                .map(|_| ['a', 'b', 'ç'][rng.gen_range(0..3)])
+                .collect();
+            for &score_cutoff in [0, 1, 30, 60, 90].iter() {
+                let searcher = FuzzySearcher::with_ratio(&pattern, score_cutoff);
+                let found = searcher.find_all(&text);
+                // A larger budget finds the same occurrences.
+                let mut unbounded = FuzzySearcher::new(&pattern, 200 * searcher.pattern.len());
+                unbounded.score_cutoff = score_cutoff;
+                assert_eq!(unbounded.find_all(&text), found, "{:?} {:?}", pattern, text);
+                let mut multi = MultiSearcher::new();
+                multi.add_with_ratio(&pattern, score_cutoff);
+                let occurrences: Vec<Occurrence> = multi
+                    .find_all(&text)
+                    .into_iter()
+                    .map(|m| m.occurrence)
+                    .collect();
+                assert_eq!(occurrences, found, "{:?} {:?}", pattern, text);
+            }
+        }
+    }
+
+    #[test]
+    fn occurrences_are_valid_and_disjoint() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(139);
+        for _ in 0..300 {
+            let pattern: Vec<char> = (0..rng.gen_range(1..8))
+                .map(|_| ['a', 'b', 'ç'][rng.gen_range(0..3)])
+                .collect();
+            let text: String = (0..rng.gen_range(0..60))
+                .map(|_| ['a', 'b', 'ç'][rng.gen_range(0..3)])
+                .collect();
+            let chars: Vec<char> = text.chars().collect();
+            let max_distance = rng.gen_range(0..3);
+            let pattern_str: String = pattern.iter().collect();
+            let found = find_all(&pattern_str, &text, max_distance);
+            for pair in found.windows(2) {
+                assert!(pair[0].char_end <= pair[1].char_start);
+            }
+            for o in found.iter() {
+                let span = &chars[o.char_start..o.char_end];
+                assert_eq!(primitives::levenshtein(&pattern, span), o.distance);
+                assert!(o.distance <= max_distance);
+                assert_eq!(&text[o.start..o.end], span.iter().collect::<String>());
+            }
+            // An exact occurrence is always found, or overlapped by another exact one.
+            if let Some(at) = text.find(&pattern_str) {
+                let char_at = text[..at].chars().count();
+                assert!(found.iter().any(|o| o.distance == 0
+                    && o.char_start < char_at + pattern.len()
+                    && char_at < o.char_end));
+            }
+        }
+    }
+}
@@ -36,6 +36,8 @@
 //!
 //! In order to usefully compare strings like these, [normalization][crate::normalization] must be done prior to segmentation.
 
+use alloc::vec::Vec;
+
 /// Represents a strategy for segmenting a string into units for comparison.
 ///
 /// The trait is also implemented for functions matching the signature of the `segment` method.
@@ -79,7 +81,7 @@ impl<'a> Segmenter<'a> for ByteSegmenter {
     // Returns an owned `Vec<u8>` because allocating additional `u8`s is cheaper than pointers into the original string.
     type Output = u8;
     fn segment(&self, s: &'a str) -> Vec<Self::Output> {
-        s.as_bytes().iter().copied().collect()
+        s.as_bytes().to_vec()
     }
 }
 
@@ -110,12 +112,31 @@ impl<'a> Segmenter<'a> for CodePointSegmenter {
     }
 }
 
+/// A strategy for segmenting strings into whitespace separated words.
+///
+/// Internally, this is just `s.split_whitespace().collect()`.
+///
+/// ```
+/// # use fuzzywuzzy::segmentation::{Segmenter, WhitespaceSegmenter};
+/// assert_eq!(WhitespaceSegmenter.segment("new york  mets"), vec!["new", "york", "mets"]);
+/// assert_eq!(WhitespaceSegmenter.segment(" "), Vec::<&str>::new());
+/// ```
+pub struct WhitespaceSegmenter;
+
+impl<'a> Segmenter<'a> for WhitespaceSegmenter {
+    type Output = &'a str;
+    fn segment(&self, s: &'a str) -> Vec<Self::Output> {
+        s.split_whitespace().collect()
+    }
+}
+
 #[cfg(feature = "segmentation")]
 pub use self::unicode_segmenters::*;
 
 #[cfg(feature = "segmentation")]
 mod unicode_segmenters {
     use super::Segmenter;
+    use alloc::vec::Vec;
     use unicode_segmentation::UnicodeSegmentation;
 
     /// A strategy for segmenting strings into their constituent Unicode graphemes. Requires default feature "segmentation".
@@ -1,5 +1,9 @@
 //! Standalone functions used by the rest of the crate. You might also find them useful.
 
+use crate::ascii;
+use alloc::string::String;
+use alloc::vec::Vec;
+
 /// Used to preprocess strings into 'canonical' forms.
 ///
 /// Process string by
@@ -29,15 +33,37 @@
 /// assert_eq!(full_process("Á", true), "");
 /// ```
 pub fn full_process(s: &str, force_ascii: bool) -> String {
-    let mut result = s.to_string();
-    if force_ascii {
-        result = result.chars().filter(char::is_ascii).collect();
+    let mut result = String::new();
+    full_process_into(s, force_ascii, &mut result);
+    result
+}
+
+/// [full_process], writing into `out`.
+pub(crate) fn full_process_into(s: &str, force_ascii: bool, out: &mut String) {
+    out.clear();
+    // `str::to_lowercase` lowercases every character on its own except for the final sigma,
+    // which depends on its neighbours. Fall back to it in that (rare) case.
+    if s.contains('Σ') && !force_ascii {
+        out.extend(s.chars().map(|c| if c.is_alphanumeric() { c } else { ' ' }));
+        let lowered = out.to_lowercase();
+        out.clear();
+        out.push_str(lowered.trim());
+        return;
+    }
+    for c in s.chars() {
+        if force_ascii && !c.is_ascii() {
+            continue;
+        }
+        if c.is_alphanumeric() {
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(' ');
+        }
     }
-    result = result
-        .chars()
-        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
-        .collect();
-    result.to_lowercase().trim().into()
+    let end = out.trim_end().len();
+    out.truncate(end);
+    let start = out.len() - out.trim_start().len();
+    out.drain(..start);
 }
 
 /// A vestigial function from the port from Python's fuzzywuzzy.
@@ -79,11 +105,11 @@ pub fn validate_string(s: &str) -> bool {
 /// Scalar Values which are distinct from bytes and grapheme clusters. For
 /// example, `y̆` is three bytes (b'y\xcc\x86'), two Unicode Scalar Values
 /// ('y\u{0306}'), but just one grapheme cluster (`y̆`).
-fn slice_utf8(string: &str, low: usize, high: usize) -> &str {
+pub(crate) fn slice_utf8(string: &str, low: usize, high: usize) -> &str {
     // I'm unsure if this is O(1) or O(n) due to the implementation.
     let char_count = string.chars().count();
-    debug_assert!(!(low > high));
-    debug_assert!(!(high > char_count));
+    debug_assert!(low <= high);
+    debug_assert!(high <= char_count);
     if low == high {
         return "";
     }
@@ -146,7 +172,7 @@ fn find_longest_match<'a>(
     let slen = high1 - low1;
     for size in (1..slen + 1).rev() {
         for start in 0..slen - size + 1 {
-            let substr = slice_utf8(&shorter, low1 + start, low1 + start + size);
+            let substr = slice_utf8(shorter, low1 + start, low1 + start + size);
             // Note: str::match_indices returns byte offsets, not char indices.
             if let Some((startb, matchstr)) = longsub.match_indices(substr).next() {
                 return (
@@ -177,6 +203,10 @@ fn find_longest_match<'a>(
 /// ```
 #[allow(clippy::many_single_char_names)]
 pub fn get_matching_blocks<'a>(a: &'a str, b: &'a str) -> Vec<(usize, usize, usize)> {
+    if a.is_ascii() && b.is_ascii() {
+        // Every char is a byte, so the byte offsets of the fast path are char offsets.
+        return ascii::get_matching_blocks(a.as_bytes(), b.as_bytes());
+    }
     let flipped;
     let (shorter, len1, longer, len2) = {
         let a_len = a.chars().count();
@@ -231,6 +261,64 @@ pub fn get_matching_blocks<'a>(a: &'a str, b: &'a str) -> Vec<(usize, usize, usi
         .collect()
 }
 
+/// Converts an edit distance into a similarity ratio between 0 and 100.
+///
+/// `length` is the length of the longer of the two compared sequences, so the
+/// ratio is `100 * (1 - distance / length)`, rounded half up. Two empty
+/// sequences (a `length` of zero) are identical and have a ratio of 100.
+///
+/// ```
+/// # use fuzzywuzzy::utils::ratio_from_distance;
+/// assert_eq!(ratio_from_distance(0, 0), 100);
+/// assert_eq!(ratio_from_distance(0, 7), 100);
+/// assert_eq!(ratio_from_distance(3, 7), 57);
+/// assert_eq!(ratio_from_distance(7, 7), 0);
+/// ```
+pub fn ratio_from_distance(distance: usize, length: usize) -> u8 {
+    debug_assert!(distance <= length);
+    if length == 0 {
+        return 100;
+    }
+    // round(100 * (length - distance) / length) in integer arithmetic.
+    ((200 * (length - distance) + length) / (2 * length)) as u8
+}
+
+/// The largest edit distance whose [ratio_from_distance] is still at least `score_cutoff`.
+///
+/// This is how a `score_cutoff` on a distance based ratio is translated into a
+/// bound for [bounded_levenshtein](crate::primitives::bounded_levenshtein).
+///
+/// ```
+/// # use fuzzywuzzy::utils::{max_distance_for_ratio, ratio_from_distance};
+/// assert_eq!(max_distance_for_ratio(7, 57), 3);
+/// assert_eq!(ratio_from_distance(3, 7), 57);
+/// assert_eq!(ratio_from_distance(4, 7), 43);
+/// assert_eq!(max_distance_for_ratio(7, 0), 7);
+/// assert_eq!(max_distance_for_ratio(7, 100), 0);
+/// ```
+pub fn max_distance_for_ratio(length: usize, score_cutoff: u8) -> usize {
+    let cutoff = core::cmp::min(score_cutoff, 100) as usize;
+    // ratio_from_distance(d, length) >= cutoff
+    //   <=> 200 * (length - d) + length >= 2 * length * cutoff
+    //   <=> d <= length * (201 - 2 * cutoff) / 200
+    core::cmp::min(length, length * (201 - 2 * cutoff) / 200)
+}
+
+/// Rounds a non-negative score to the nearest integer, with halves rounded up.
+///
+/// This is `x.round() as u8`, which is not available without `std`.
+pub(crate) fn round_to_u8(x: f64) -> u8 {
+    debug_assert!(x >= 0.0);
+    let truncated = x as u64;
+    // The fractional part of a float is always exactly representable.
+    let fraction = x - truncated as f64;
+    if fraction >= 0.5 {
+        (truncated + 1) as u8
+    } else {
+        truncated as u8
+    }
+}
+
 /// some common short circuiting for ratio finding functions.
 /// If the strings are equal, they have a ratio of 100%.
 /// If only one of the strings is empty, they have a ratio of 0%.
@@ -286,9 +374,18 @@ mod test {
         assert_eq!(slice_utf8(s, 1, 4), "\u{0306}es");
     }
 
+    #[test]
+    fn round_to_u8_matches_std() {
+        for i in 0..=10_000 {
+            let x = i as f64 / 100.0;
+            assert_eq!(round_to_u8(x), x.round() as u8);
+        }
+        assert_eq!(round_to_u8(f64::from(100.0f32 * (2.0 * 2.0 / 6.0))), 67);
+    }
+
     #[test]
     #[should_panic]
-    #[cfg(debug)]
+    #[cfg(debug_assertions)]
     fn overlarge() {
         let s = "abcde";
         slice_utf8(s, 0, 10);
@@ -296,7 +393,7 @@ mod test {
 
     #[test]
     #[should_panic]
-    #[cfg(debug)]
+    #[cfg(debug_assertions)]
     fn low_greater_then_high() {
         let s = "abcde";
         slice_utf8(s, 4, 2);
@@ -0,0 +1,73 @@
+//! Checks that a warmed-up `ScoringContext` does not allocate.
+//!
+//! This swaps the global allocator, so it lives in its own test binary. The binary runs without
+//! the test harness, whose threads would otherwise allocate while the allocations are counted.
+
+use fuzzywuzzy::context::ScoringContext;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts the allocations made by the process.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.load(Ordering::SeqCst)
+}
+
+// The final sigma is the one case that falls back to `str::to_lowercase`, so it is left out.
+const PAIRS: [(&str, &str); 10] = [
+    ("new york mets", "new YORK mets"),
+    ("new york mets", "the wonderful new york mets"),
+    (
+        "new york mets vs atlanta braves",
+        "atlanta braves vs new york mets",
+    ),
+    ("fuzzy was a bear", "fuzzy fuzzy was a bear"),
+    (
+        "what about supercalifragilisticexpialidocious",
+        "supercalifragilisticexpialidocious about what",
+    ),
+    ("スマホでchance", "chance"),
+    ("fòllòwbáck", "followback"),
+    ("", "nonempty"),
+    ("¬Camarões assados", "camaroes assados"),
+    ("a", "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"),
+];
+
+fn score_all(ctx: &mut ScoringContext) -> usize {
+    let mut total = 0;
+    for &(a, b) in PAIRS.iter() {
+        total += ctx.ratio(a, b) as usize;
+        total += ctx.partial_ratio(a, b) as usize;
+        total += ctx.uqratio(a, b) as usize;
+        total += ctx.token_sort_ratio(a, b, true, true) as usize;
+        total += ctx.partial_token_set_ratio(a, b, true, true) as usize;
+        total += ctx.wratio(a, b, true, true) as usize;
+        total += ctx.uwratio(a, b, true) as usize;
+    }
+    total
+}
+
+fn main() {
+    let mut ctx = ScoringContext::new();
+    let warm = score_all(&mut ctx);
+    let before = allocations();
+    let hot = score_all(&mut ctx);
+    assert_eq!(allocations(), before);
+    assert_eq!(warm, hot);
+}